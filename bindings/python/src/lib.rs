@@ -0,0 +1,180 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Python bindings for `chardetng`, built with maturin (see
+//! `pyproject.toml`):
+//!
+//! ```python
+//! import chardetng
+//! chardetng.detect(b"Ma\xf0ur", tld="is")  # "windows-1252"
+//! detector = chardetng.Detector()
+//! for chunk in chunks:
+//!     detector.feed(chunk)
+//! detector.feed(b"", last=True)
+//! detector.guess_assess(tld=None, allow_utf8=False)  # ("windows-1252", True)
+//! ```
+//!
+//! Encodings are returned as their WHATWG names, which Python's codec
+//! registry accepts except for a few (e.g. `x-user-defined`, which
+//! chardetng never returns). The GIL is released while bytes are being
+//! fed to the detector. Misuse that would panic in Rust raises instead.
+
+// The code generated by pyo3's `#[pymethods]` converts `PyErr` into itself.
+#![allow(clippy::useless_conversion)]
+
+use chardetng::EncodingDetector;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Checks the condition under which `guess` would panic.
+fn tld_bytes(tld: Option<&str>) -> PyResult<Option<&[u8]>> {
+    match tld {
+        Some(label)
+            if label
+                .bytes()
+                .any(|b| b >= 0x80 || b == b'.' || b.is_ascii_uppercase()) =>
+        {
+            Err(PyValueError::new_err(
+                "tld must be a lower-case ASCII label (Punycode) without periods",
+            ))
+        }
+        _ => Ok(tld.map(str::as_bytes)),
+    }
+}
+
+/// A streaming encoding detector.
+///
+/// Call `feed` with the chunks of the input, passing `last=True` with the
+/// last (possibly empty) chunk, and then `guess` or `guess_assess`.
+#[pyclass(module = "chardetng")]
+struct Detector {
+    // Boxed, because the detector may be over-aligned (see the
+    // `multithreading` feature) and Python objects are not.
+    inner: Box<EncodingDetector>,
+    closed: bool,
+}
+
+#[pymethods]
+impl Detector {
+    #[new]
+    fn new() -> Self {
+        Detector {
+            inner: Box::new(EncodingDetector::new()),
+            closed: false,
+        }
+    }
+
+    /// Feeds a chunk of bytes. Returns whether non-ASCII has been seen.
+    #[pyo3(signature = (buffer, last = false))]
+    fn feed(&mut self, py: Python<'_>, buffer: &[u8], last: bool) -> PyResult<bool> {
+        if self.closed {
+            return Err(PyRuntimeError::new_err(
+                "must not feed again after feeding with last=True",
+            ));
+        }
+        self.closed = last;
+        let inner = &mut self.inner;
+        Ok(py.allow_threads(move || inner.feed(buffer, last)))
+    }
+
+    /// Returns the WHATWG name of the guessed encoding. `tld` is the
+    /// rightmost label of the host name in lower-case Punycode or `None`.
+    #[pyo3(signature = (tld = None, allow_utf8 = true))]
+    fn guess(&self, tld: Option<&str>, allow_utf8: bool) -> PyResult<&'static str> {
+        Ok(self.inner.guess(tld_bytes(tld)?, allow_utf8).name())
+    }
+
+    /// Like `guess`, but returns a tuple of the name and whether the
+    /// guessed encoding had a higher score than at least one other
+    /// candidate.
+    #[pyo3(signature = (tld = None, allow_utf8 = true))]
+    fn guess_assess(&self, tld: Option<&str>, allow_utf8: bool) -> PyResult<(&'static str, bool)> {
+        let (encoding, assessment) = self.inner.guess_assess(tld_bytes(tld)?, allow_utf8);
+        Ok((encoding.name(), assessment))
+    }
+
+    /// The number of non-ASCII bytes fed so far.
+    fn non_ascii_count(&self) -> u64 {
+        self.inner.non_ascii_count()
+    }
+}
+
+/// Detects the encoding of `data` (the whole input) and returns its
+/// WHATWG name.
+#[pyfunction]
+#[pyo3(signature = (data, tld = None, allow_utf8 = true))]
+fn detect(
+    py: Python<'_>,
+    data: &[u8],
+    tld: Option<&str>,
+    allow_utf8: bool,
+) -> PyResult<&'static str> {
+    let tld = tld_bytes(tld)?;
+    let mut detector = EncodingDetector::new();
+    py.allow_threads(|| detector.feed(data, true));
+    Ok(detector.guess(tld, allow_utf8).name())
+}
+
+#[pymodule]
+#[pyo3(name = "chardetng")]
+fn chardetng_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Detector>()?;
+    m.add_function(wrap_pyfunction!(detect, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    fn run(code: &str) {
+        Python::with_gil(|py| {
+            let module = PyModule::new_bound(py, "chardetng").unwrap();
+            chardetng_py(&module).unwrap();
+            let locals = PyDict::new_bound(py);
+            locals.set_item("chardetng", module).unwrap();
+            py.run_bound(code, None, Some(&locals))
+                .map_err(|e| e.print(py))
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_detect() {
+        run(r#"
+assert chardetng.detect(b"Ma\xf0ur", tld="is") == "windows-1252"
+assert chardetng.detect("Привет".encode()) == "UTF-8"
+assert chardetng.detect("Привет".encode(), allow_utf8=False) == "windows-1252"
+try:
+    chardetng.detect(b"", tld="co.uk")
+    assert False
+except ValueError:
+    pass
+"#);
+    }
+
+    #[test]
+    fn test_detector() {
+        run(r#"
+d = chardetng.Detector()
+assert not d.feed(b"M")
+assert d.feed(b"a\xf0ur", last=True)
+assert d.guess() == "windows-1252"
+assert d.guess_assess(tld="is", allow_utf8=False) == ("windows-1252", True)
+assert d.non_ascii_count() == 1
+try:
+    d.feed(b"")
+    assert False
+except RuntimeError:
+    pass
+"#);
+    }
+}
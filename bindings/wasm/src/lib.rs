@@ -0,0 +1,169 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! WebAssembly bindings for `chardetng`.
+//!
+//! Build with `cargo build --release --target wasm32-unknown-unknown -p
+//! chardetng_wasm` followed by `wasm-bindgen` (or use `wasm-pack build
+//! bindings/wasm`). From JavaScript:
+//!
+//! ```js
+//! const detector = new Detector();
+//! for await (const chunk of file.stream()) {
+//!   detector.feed(chunk, false);
+//! }
+//! detector.feed(new Uint8Array(), true);
+//! const name = detector.guess(null, false); // e.g. "windows-1252"
+//! ```
+//!
+//! Encodings are returned as their WHATWG names, which `TextDecoder`
+//! accepts. Misuse that would panic in Rust throws an `Error` instead.
+
+use chardetng::EncodingDetector;
+use wasm_bindgen::prelude::*;
+
+/// Returns an error message if `guess` would panic on `tld`.
+fn check_tld(tld: &str) -> Option<&'static str> {
+    if tld
+        .bytes()
+        .any(|b| b >= 0x80 || b == b'.' || b.is_ascii_uppercase())
+    {
+        Some("The TLD must be a lower-case ASCII label (Punycode) without periods.")
+    } else {
+        None
+    }
+}
+
+/// A streaming detector. Feed it the chunks of a file and then ask for
+/// a guess.
+#[wasm_bindgen]
+pub struct Detector {
+    inner: EncodingDetector,
+    closed: bool,
+}
+
+/// The result of `Detector.guessAssess()`.
+#[wasm_bindgen]
+pub struct Assessment {
+    encoding: &'static str,
+    confident: bool,
+}
+
+#[wasm_bindgen]
+impl Assessment {
+    /// The WHATWG name of the guessed encoding.
+    #[wasm_bindgen(getter)]
+    pub fn encoding(&self) -> String {
+        String::from(self.encoding)
+    }
+
+    /// Whether the guessed encoding had a higher score than at least one
+    /// other candidate.
+    #[wasm_bindgen(getter)]
+    pub fn confident(&self) -> bool {
+        self.confident
+    }
+}
+
+#[wasm_bindgen]
+impl Detector {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Detector {
+        Detector {
+            inner: EncodingDetector::new(),
+            closed: false,
+        }
+    }
+
+    /// Feeds a chunk (a `Uint8Array`). Pass `true` as `last` with the last
+    /// (possibly empty) chunk. Returns whether non-ASCII has been seen.
+    pub fn feed(&mut self, chunk: &[u8], last: bool) -> Result<bool, JsError> {
+        if self.closed {
+            return Err(JsError::new("Must not feed after the last chunk."));
+        }
+        self.closed = last;
+        Ok(self.inner.feed(chunk, last))
+    }
+
+    /// Returns the WHATWG name of the guessed encoding. `tld` is the
+    /// rightmost label of the host name, or `null` if not applicable.
+    pub fn guess(&self, tld: Option<String>, allow_utf8: bool) -> Result<String, JsError> {
+        Ok(String::from(self.guess_assess(tld, allow_utf8)?.encoding))
+    }
+
+    /// Like `guess()`, but also returns whether the guess is likely
+    /// to be right.
+    #[wasm_bindgen(js_name = guessAssess)]
+    pub fn guess_assess(
+        &self,
+        tld: Option<String>,
+        allow_utf8: bool,
+    ) -> Result<Assessment, JsError> {
+        if let Some(message) = tld.as_deref().and_then(check_tld) {
+            return Err(JsError::new(message));
+        }
+        let (encoding, confident) = self
+            .inner
+            .guess_assess(tld.as_deref().map(str::as_bytes), allow_utf8);
+        Ok(Assessment {
+            encoding: encoding.name(),
+            confident,
+        })
+    }
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Detector::new()
+    }
+}
+
+/// Detects the encoding of a complete file (a `Uint8Array`) and returns
+/// its WHATWG name.
+#[wasm_bindgen]
+pub fn detect(bytes: &[u8], tld: Option<String>, allow_utf8: bool) -> Result<String, JsError> {
+    let mut detector = Detector::new();
+    detector.feed(bytes, true)?;
+    detector.guess(tld, allow_utf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `JsError` can only be constructed on wasm32, so only the success
+    // paths are tested natively.
+
+    #[test]
+    fn test_streaming() {
+        let mut detector = Detector::new();
+        assert!(!detector.feed(b"M", false).unwrap());
+        assert!(detector.feed(b"a\xF0ur", true).unwrap());
+        assert_eq!(
+            detector.guess(Some(String::from("is")), false).unwrap(),
+            "windows-1252"
+        );
+        let assessment = detector.guess_assess(None, true).unwrap();
+        assert_eq!(assessment.encoding(), "windows-1252");
+        assert!(assessment.confident());
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("Привет".as_bytes(), None, true).unwrap(), "UTF-8");
+    }
+
+    #[test]
+    fn test_check_tld() {
+        assert!(check_tld("xn--p1ai").is_none());
+        assert!(check_tld("RU").is_some());
+        assert!(check_tld("co.uk").is_some());
+        assert!(check_tld("рф").is_some());
+    }
+}
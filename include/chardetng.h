@@ -0,0 +1,85 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// C API of chardetng, available with the `ffi` cargo feature. See
+// src/ffi.rs for the documentation.
+//
+// The encodings are the same `Encoding` pointers as in the C API of
+// encoding_rs (encoding_rs.h from the encoding_c crate), which may be
+// included before or after this header. As with encoding_rs.h, C++ code
+// needs to declare `Encoding` or define `ENCODING_RS_ENCODING` first.
+
+#ifndef chardetng_h_
+#define chardetng_h_
+
+// Generated by tools/header from cbindgen.toml and src/ffi.rs. Don't edit
+// by hand; run `cargo run -p chardetng_header` instead.
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef ENCODING_RS_ENCODING
+#define ENCODING_RS_ENCODING Encoding
+#ifndef __cplusplus
+typedef struct Encoding_ Encoding;
+#endif
+#endif
+
+#ifndef CHARDETNG_ENCODING_DETECTOR
+#define CHARDETNG_ENCODING_DETECTOR EncodingDetector
+#ifdef __cplusplus
+class EncodingDetector;
+#else
+typedef struct EncodingDetector_ EncodingDetector;
+#endif
+#endif
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+CHARDETNG_ENCODING_DETECTOR *chardetng_encoding_detector_new(void);
+
+void chardetng_encoding_detector_free(CHARDETNG_ENCODING_DETECTOR *detector);
+
+bool chardetng_encoding_detector_feed(CHARDETNG_ENCODING_DETECTOR *detector,
+                                      const uint8_t *buffer,
+                                      size_t buffer_len,
+                                      bool last);
+
+const ENCODING_RS_ENCODING *chardetng_encoding_detector_guess(const CHARDETNG_ENCODING_DETECTOR *detector,
+                                                              const uint8_t *tld,
+                                                              size_t tld_len,
+                                                              bool allow_utf8);
+
+const ENCODING_RS_ENCODING *chardetng_encoding_detector_guess_assess(const CHARDETNG_ENCODING_DETECTOR *detector,
+                                                                     const uint8_t *tld,
+                                                                     size_t tld_len,
+                                                                     bool allow_utf8,
+                                                                     bool *assessment);
+
+bool chardetng_encoding_detector_tld_may_affect_guess(const uint8_t *tld,
+                                                      size_t tld_len);
+
+void chardetng_encoding_detector_set_dos_code_pages(CHARDETNG_ENCODING_DETECTOR *detector,
+                                                    bool enabled);
+
+const ENCODING_RS_ENCODING *chardetng_encoding_detector_guess_with_dos(const CHARDETNG_ENCODING_DETECTOR *detector,
+                                                                       const uint8_t *tld,
+                                                                       size_t tld_len,
+                                                                       bool allow_utf8,
+                                                                       bool *assessment,
+                                                                       uint16_t *code_page);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* chardetng_h_ */
@@ -0,0 +1,211 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reports on whether the choice between two encodings matters for a
+//! given input.
+//!
+//! Pairs such as windows-1250 and ISO-8859-2 often both survive with
+//! similar scores, but many inputs decode the same in both. The input is
+//! decoded in both encodings while keeping track of the byte range of
+//! each character, and the byte ranges where the decodings differ are
+//! listed. When the two decoders split the input into characters
+//! differently, e.g. Big5 and GBK, a range extends until the splits agree
+//! again.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use encoding_rs::DecoderResult;
+use encoding_rs::Encoding;
+
+/// A byte range of the input that decodes differently in the two
+/// encodings of an `Ambiguity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The offset of the range in the input.
+    pub offset: usize,
+    /// The length of the range in bytes.
+    pub len: usize,
+    /// The range decoded as the first encoding.
+    pub first: String,
+    /// The range decoded as the second encoding.
+    pub second: String,
+}
+
+/// Where two encodings decode an input differently. Malformed sequences
+/// decode to the REPLACEMENT CHARACTER.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiguity {
+    /// The first encoding, e.g. the top candidate.
+    pub first: &'static Encoding,
+    /// The second encoding, e.g. the runner-up.
+    pub second: &'static Encoding,
+    /// The differing ranges in ascending order of offset.
+    pub differences: Vec<Difference>,
+}
+
+impl Ambiguity {
+    /// Compares the decodings of `input` as `first` and as `second`.
+    /// Byte order marks are not taken into account.
+    pub fn between(first: &'static Encoding, second: &'static Encoding, input: &[u8]) -> Self {
+        let differences = if first == second {
+            Vec::new()
+        } else {
+            differences(
+                &decode_with_offsets(first, input),
+                &decode_with_offsets(second, input),
+                input.len(),
+            )
+        };
+        Ambiguity {
+            first,
+            second,
+            differences,
+        }
+    }
+
+    /// Returns `true` if the input decodes the same in both encodings,
+    /// i.e. either choice is fine.
+    pub fn is_harmless(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// A character and the offset after the last byte that decoded to it.
+/// Characters decoded from the same bytes share the offset.
+struct Decoded {
+    end: usize,
+    c: char,
+}
+
+fn decode_with_offsets(encoding: &'static Encoding, input: &[u8]) -> Vec<Decoded> {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    // Room for the at most two characters that a byte can complete.
+    let mut out = [0u8; 16];
+    let mut decoded = Vec::with_capacity(input.len());
+    let mut pos = 0;
+    loop {
+        let end = core::cmp::min(pos + 1, input.len());
+        let last = end == input.len();
+        let (result, read, written) =
+            decoder.decode_to_utf8_without_replacement(&input[pos..end], &mut out, last);
+        pos += read;
+        let text = core::str::from_utf8(&out[..written]).expect("decoders output UTF-8");
+        for c in text.chars() {
+            decoded.push(Decoded { end: pos, c });
+        }
+        match result {
+            DecoderResult::Malformed(_, _) => decoded.push(Decoded {
+                end: pos,
+                c: '\u{FFFD}',
+            }),
+            DecoderResult::InputEmpty if last && pos == input.len() => break,
+            _ => {}
+        }
+    }
+    decoded
+}
+
+fn differences(first: &[Decoded], second: &[Decoded], len: usize) -> Vec<Difference> {
+    let end_of = |decoded: &[Decoded], i: usize| decoded.get(i).map_or(len, |d| d.end);
+    let mut differences = Vec::new();
+    let mut i = 0;
+    let mut j = 0;
+    let mut offset = 0;
+    while i < first.len() || j < second.len() {
+        // Extend the range until both decodings end a character at the
+        // same offset and include all the characters that end there.
+        let mut i_end = i;
+        let mut j_end = j;
+        let mut end = core::cmp::max(end_of(first, i), end_of(second, j));
+        loop {
+            while i_end < first.len() && first[i_end].end <= end {
+                i_end += 1;
+            }
+            while j_end < second.len() && second[j_end].end <= end {
+                j_end += 1;
+            }
+            let next = core::cmp::max(
+                if i_end > i { first[i_end - 1].end } else { 0 },
+                if j_end > j { second[j_end - 1].end } else { 0 },
+            );
+            if next == end || (i_end == first.len() && j_end == second.len()) {
+                break;
+            }
+            end = next;
+        }
+        let a = &first[i..i_end];
+        let b = &second[j..j_end];
+        if a.iter().map(|d| d.c).ne(b.iter().map(|d| d.c)) {
+            differences.push(Difference {
+                offset,
+                len: end - offset,
+                first: a.iter().map(|d| d.c).collect(),
+                second: b.iter().map(|d| d.c).collect(),
+            });
+        }
+        i = i_end;
+        j = j_end;
+        offset = end;
+    }
+    differences
+}
+
+#[cfg(all(test, feature = "central", feature = "greek"))]
+mod tests {
+    use super::*;
+    use crate::EncodingDetector;
+    use encoding_rs::ISO_8859_2;
+    use encoding_rs::ISO_8859_7;
+    use encoding_rs::SHIFT_JIS;
+    use encoding_rs::WINDOWS_1250;
+    use encoding_rs::WINDOWS_1252;
+    use encoding_rs::WINDOWS_1253;
+
+    #[test]
+    fn test_ambiguity() {
+        // Polish that decodes the same in both encodings.
+        let bytes = WINDOWS_1250.encode("Miłego dnia, do zobaczenia w Łodzi.").0;
+        let mut det = EncodingDetector::new();
+        det.feed(&bytes, true);
+        let ambiguity = det.ambiguity(&bytes, None).unwrap();
+        assert_eq!(ambiguity.first, WINDOWS_1250);
+        assert_eq!(ambiguity.second, ISO_8859_2);
+        assert!(ambiguity.is_harmless());
+
+        // Greek where the choice matters for the first letter.
+        let bytes = ISO_8859_7.encode("Άλλη μια καλή μέρα στην Αθήνα.").0;
+        let mut det = EncodingDetector::new();
+        det.feed(&bytes, true);
+        let ambiguity = det.ambiguity(&bytes, None).unwrap();
+        assert_eq!(ambiguity.first, ISO_8859_7);
+        assert_eq!(ambiguity.second, WINDOWS_1253);
+        assert_eq!(
+            ambiguity.differences,
+            [Difference {
+                offset: 0,
+                len: 1,
+                first: "Ά".into(),
+                second: "¶".into(),
+            }]
+        );
+
+        // A range spans characters until the decoders agree on a boundary.
+        let ambiguity = Ambiguity::between(SHIFT_JIS, WINDOWS_1252, b"a\x93\xFAb");
+        assert_eq!(
+            ambiguity.differences,
+            [Difference {
+                offset: 1,
+                len: 2,
+                first: "日".into(),
+                second: "“ú".into(),
+            }]
+        );
+        assert!(Ambiguity::between(SHIFT_JIS, SHIFT_JIS, b"\x93\xFA").is_harmless());
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `--convert` mode: rewrite the input as UTF-8 using the guessed encoding.
+
+use chardetng::EncodingDetector;
+use chardetng::SubtitleDetector;
+use encoding_rs::Encoding;
+use encoding_rs::UTF_8;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct ConvertOptions {
+    /// Rename the original to `<name>.orig` instead of overwriting it.
+    pub keep_originals: bool,
+    /// Leave the file alone if `guess_assess` returns `false`.
+    pub skip_unsure: bool,
+    /// Guess from the text of SRT or SSA cues only.
+    pub subtitles: bool,
+}
+
+pub enum Outcome {
+    /// The file was rewritten as UTF-8. `had_errors` is `true` if
+    /// decoding produced U+FFFD REPLACEMENT CHARACTERs.
+    Converted { had_errors: bool },
+    /// The input was already UTF-8 (without a BOM), so it was left alone.
+    AlreadyUtf8,
+    /// The guess was not confident and `skip_unsure` was set.
+    SkippedUnsure,
+}
+
+pub struct Conversion {
+    pub encoding: &'static Encoding,
+    pub assessment: bool,
+    pub outcome: Outcome,
+}
+
+/// Guesses the encoding of `bytes` and decodes them. A BOM takes
+/// precedence over the detector, and UTF-8 is always permitted as a guess,
+/// since rejecting it would double-encode files that are already UTF-8.
+fn decode(
+    bytes: &[u8],
+    tld: Option<&[u8]>,
+    options: &ConvertOptions,
+) -> (&'static Encoding, bool, Option<(String, bool)>) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (encoding, true, Some((text.into_owned(), had_errors)));
+    }
+    let (encoding, assessment) = if options.subtitles {
+        let mut detector = SubtitleDetector::new();
+        detector.feed(bytes, true);
+        let guess = detector.guess(tld, true);
+        (guess.encoding, guess.confident)
+    } else {
+        let mut detector = EncodingDetector::new();
+        detector.feed(bytes, true);
+        detector.guess_assess(tld, true)
+    };
+    if encoding == UTF_8 || (options.skip_unsure && !assessment) {
+        return (encoding, assessment, None);
+    }
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (encoding, assessment, Some((text.into_owned(), had_errors)))
+}
+
+/// Converts the file at `path` in place.
+pub fn convert_file(
+    path: &Path,
+    tld: Option<&[u8]>,
+    options: &ConvertOptions,
+) -> io::Result<Conversion> {
+    let bytes = fs::read(path)?;
+    let permissions = fs::metadata(path)?.permissions();
+    let (encoding, assessment, decoded) = decode(&bytes, tld, options);
+    let outcome = match decoded {
+        None if encoding == UTF_8 => Outcome::AlreadyUtf8,
+        None => Outcome::SkippedUnsure,
+        Some((text, had_errors)) => {
+            let temporary = with_suffix(path, ".chardetng-tmp");
+            fs::write(&temporary, text.as_bytes())?;
+            // The new file would otherwise get the default mode.
+            fs::set_permissions(&temporary, permissions)?;
+            if options.keep_originals {
+                fs::rename(path, with_suffix(path, ".orig"))?;
+            }
+            fs::rename(&temporary, path)?;
+            Outcome::Converted { had_errors }
+        }
+    };
+    Ok(Conversion {
+        encoding,
+        assessment,
+        outcome,
+    })
+}
+
+/// Converts `bytes` read from standard input and writes the result
+/// (or the input unchanged if it is not converted) to `out`.
+pub fn convert_stream<W: io::Write>(
+    bytes: &[u8],
+    tld: Option<&[u8]>,
+    options: &ConvertOptions,
+    out: &mut W,
+) -> io::Result<Conversion> {
+    let (encoding, assessment, decoded) = decode(bytes, tld, options);
+    let outcome = match decoded {
+        None => {
+            out.write_all(bytes)?;
+            if encoding == UTF_8 {
+                Outcome::AlreadyUtf8
+            } else {
+                Outcome::SkippedUnsure
+            }
+        }
+        Some((text, had_errors)) => {
+            out.write_all(text.as_bytes())?;
+            Outcome::Converted { had_errors }
+        }
+    };
+    Ok(Conversion {
+        encoding,
+        assessment,
+        outcome,
+    })
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> OsString {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    name
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_convert_file_keeps_permissions() {
+        let options = ConvertOptions {
+            keep_originals: false,
+            skip_unsure: false,
+            subtitles: false,
+        };
+        for &mode in [0o755, 0o640].iter() {
+            let path = std::env::temp_dir().join(format!(
+                "chardetng-convert-{}-{:o}.txt",
+                std::process::id(),
+                mode
+            ));
+            fs::write(&path, b"Caf\xE9 cr\xE8me br\xFBl\xE9e \xE0 la fran\xE7aise").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+            let conversion = convert_file(&path, None, &options).unwrap();
+            let text = fs::read_to_string(&path).unwrap();
+            let converted_mode = fs::metadata(&path).unwrap().permissions().mode();
+            fs::remove_file(&path).unwrap();
+            assert!(matches!(
+                conversion.outcome,
+                Outcome::Converted { had_errors: false }
+            ));
+            assert_eq!(text, "Café crème brûlée à la française");
+            assert_eq!(converted_mode & 0o777, mode);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `--jsonl` mode: one JSON object per input, computed in parallel.
+
+use chardetng::EncodingDetector;
+use rayon::prelude::*;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// One top-level domain per class of TLDs that the detector treats
+/// differently. Each line lists the classes whose guess differs from
+/// the main guess.
+const TLD_VARIANTS: [&str; 22] = [
+    "ba", // Central European and Cyrillic
+    "cn", // Simplified Chinese
+    "cz", // Central European, windows-1250 first
+    "eu", // European Union
+    "ge", // Western and Cyrillic
+    "gr", // Greek
+    "hk", // Traditional and Simplified Chinese
+    "il", // Hebrew
+    "is", // Icelandic and Faroese
+    "jp", // Japanese
+    "kr", // Korean
+    "lt", // Baltic
+    "my", // Western and Arabic
+    "pl", // Central European, ISO-8859-2 first
+    "ru", // Cyrillic
+    "sa", // Arabic
+    "sg", // Simplified and Traditional Chinese
+    "th", // Thai
+    "tr", // Turkish and Azeri
+    "tw", // Traditional Chinese
+    "uk", // Western
+    "vn", // Vietnamese
+];
+
+/// How many inputs are processed in parallel before their lines are
+/// written, so that the lines can be written in input order without
+/// holding all of them.
+const BATCH_LEN: usize = 1024;
+
+struct Record {
+    length: u64,
+    detector: EncodingDetector,
+}
+
+fn detect(path: &Path) -> io::Result<Record> {
+    let mut detector = EncodingDetector::new();
+    let mut length = 0u64;
+    let mut buffer = [0u8; 8192];
+    let mut reader: Box<dyn Read> = if path.as_os_str() == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path)?)
+    };
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            detector.feed(b"", true);
+            return Ok(Record { length, detector });
+        }
+        length += read as u64;
+        detector.feed(&buffer[..read], false);
+    }
+}
+
+fn push_json_string(line: &mut String, s: &str) {
+    line.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            '\u{0}'..='\u{1F}' => {
+                let _ = write!(line, "\\u{:04x}", u32::from(c));
+            }
+            _ => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+fn format_line(
+    path: &Path,
+    result: io::Result<Record>,
+    tld: Option<&[u8]>,
+    allow_utf8: bool,
+) -> String {
+    let mut line = String::from("{\"path\":");
+    push_json_string(&mut line, &path.to_string_lossy());
+    if path.to_str().is_none() {
+        // The path isn't Unicode, so the string above can't be mapped
+        // back to the file.
+        line.push_str(",\"lossy_path\":true");
+    }
+    match result {
+        Ok(Record { length, detector }) => {
+            let (encoding, assessment) = detector.guess_assess(tld, allow_utf8);
+            let _ = write!(
+                line,
+                ",\"length\":{},\"non_ascii\":{},\"encoding\":\"{}\",\"confident\":{},\"tld_variants\":{{",
+                length,
+                detector.non_ascii_count(),
+                encoding.name(),
+                assessment
+            );
+            let mut first = true;
+            for variant_tld in TLD_VARIANTS.iter() {
+                let variant = detector.guess(Some(variant_tld.as_bytes()), allow_utf8);
+                if variant == encoding {
+                    continue;
+                }
+                if !first {
+                    line.push(',');
+                }
+                first = false;
+                let _ = write!(line, "\"{}\":\"{}\"", variant_tld, variant.name());
+            }
+            line.push_str("}}");
+        }
+        Err(err) => {
+            line.push_str(",\"error\":");
+            push_json_string(&mut line, &err.to_string());
+            line.push('}');
+        }
+    }
+    line
+}
+
+/// Writes one line per input to `out` in input order. The inputs are
+/// processed in parallel in batches of `BATCH_LEN`. Returns `true` if
+/// any input failed.
+pub fn write_all<W: Write>(
+    inputs: &[PathBuf],
+    tld: Option<&[u8]>,
+    allow_utf8: bool,
+    out: &mut W,
+) -> bool {
+    let mut failed = false;
+    for batch in inputs.chunks(BATCH_LEN) {
+        let lines: Vec<(String, bool)> = batch
+            .par_iter()
+            .map(|path| {
+                let result = detect(path);
+                let failed = result.is_err();
+                let mut line = format_line(path, result, tld, allow_utf8);
+                line.push('\n');
+                (line, failed)
+            })
+            .collect();
+        for (line, line_failed) in lines {
+            let _ = out.write_all(line.as_bytes());
+            failed |= line_failed;
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string_escaping() {
+        let mut line = String::new();
+        push_json_string(&mut line, "a\"b\\c\nd\u{1}é");
+        assert_eq!(line, "\"a\\\"b\\\\c\\nd\\u0001é\"");
+    }
+
+    #[test]
+    fn test_lines_are_in_input_order() {
+        let inputs: Vec<PathBuf> = (0..BATCH_LEN + 10)
+            .map(|i| PathBuf::from(format!("/nonexistent/chardetng-{}", i)))
+            .collect();
+        let mut out = Vec::new();
+        assert!(write_all(&inputs, None, false, &mut out));
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), inputs.len());
+        for (line, path) in out.lines().zip(inputs.iter()) {
+            let prefix = format!("{{\"path\":\"{}\",\"error\":", path.display());
+            assert!(line.starts_with(&prefix));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lossy_path_is_flagged() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let error = || io::Error::new(io::ErrorKind::NotFound, "gone");
+        let path = Path::new(OsStr::from_bytes(b"caf\xE9.txt"));
+        let line = format_line(path, Err(error()), None, false);
+        assert!(line.starts_with("{\"path\":\"caf\u{FFFD}.txt\",\"lossy_path\":true,"));
+        let line = format_line(Path::new("café.txt"), Err(error()), None, false);
+        assert!(!line.contains("lossy_path"));
+    }
+
+    #[test]
+    fn test_tld_variants_are_sorted_and_non_generic() {
+        for pair in TLD_VARIANTS.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+        for tld in TLD_VARIANTS.iter() {
+            assert!(EncodingDetector::tld_may_affect_guess(Some(tld.as_bytes())));
+        }
+    }
+}
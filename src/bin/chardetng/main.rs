@@ -0,0 +1,343 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Command-line front end for `chardetng`.
+//!
+//! Prints the guessed encoding and the `guess_assess` Boolean for each
+//! input file (or standard input), emits JSON Lines records in `--jsonl`
+//! mode, or, in `--convert` mode, rewrites the inputs as UTF-8.
+
+mod convert;
+mod jsonl;
+
+use chardetng::EncodingDetector;
+use chardetng::RankedCandidates;
+use chardetng::SubtitleDetector;
+use convert::ConvertOptions;
+use convert::Outcome;
+use encoding_rs::Encoding;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: chardetng [OPTIONS] [FILE]...
+
+Guesses the character encoding of each FILE. With no FILE, or when FILE
+is -, reads standard input.
+
+Options:
+      --tld <LABEL>   Top-level domain the content was loaded from, in
+                      lower-case ASCII (Punycode for IDNs)
+      --allow-utf8    Permit UTF-8 as a guess
+      --candidates    Also list the candidate encodings with their scores
+      --subtitles     Treat each FILE as SRT or SSA subtitles: only the
+                      text of the cues counts, and a byte order mark
+                      overrides the guess
+      --jsonl         Print one JSON object per FILE with the keys path,
+                      length, non_ascii, encoding, confident and
+                      tld_variants (the guesses for TLDs whose guess
+                      differs), plus lossy_path: true if the path isn't
+                      Unicode. FILEs are processed in parallel and the
+                      lines are printed in input order
+      --files-from <LIST>
+                      Also read newline-separated FILE names from LIST
+                      (- for standard input)
+      --convert       Rewrite each FILE as UTF-8 using the guessed encoding
+                      (standard input is converted to standard output).
+                      UTF-8 is always permitted as a guess in this mode and
+                      a byte order mark overrides the guess
+      --keep-originals
+                      With --convert, rename each original to FILE.orig
+                      instead of overwriting it
+      --skip-unsure   With --convert, leave files alone when the guess is
+                      not confident (guess_assess returns false)
+  -h, --help          Print this help
+  -V, --version       Print version
+";
+
+struct Options {
+    tld: Option<Vec<u8>>,
+    allow_utf8: bool,
+    candidates: bool,
+    subtitles: bool,
+    jsonl: bool,
+    convert: Option<ConvertOptions>,
+    inputs: Vec<PathBuf>,
+}
+
+fn parse_args<I: Iterator<Item = OsString>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        tld: None,
+        allow_utf8: false,
+        candidates: false,
+        subtitles: false,
+        jsonl: false,
+        convert: None,
+        inputs: Vec::new(),
+    };
+    let mut convert = false;
+    let mut keep_originals = false;
+    let mut skip_unsure = false;
+    let mut only_files = false;
+    while let Some(arg) = args.next() {
+        if only_files {
+            options.inputs.push(arg.into());
+            continue;
+        }
+        match arg.to_str() {
+            Some("--") => only_files = true,
+            Some("-h") | Some("--help") => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            Some("-V") | Some("--version") => {
+                println!("chardetng {}", env!("CARGO_PKG_VERSION"));
+                process::exit(0);
+            }
+            Some("--allow-utf8") => options.allow_utf8 = true,
+            Some("--candidates") => options.candidates = true,
+            Some("--subtitles") => options.subtitles = true,
+            Some("--jsonl") => options.jsonl = true,
+            Some("--files-from") => {
+                let list = args.next().ok_or("--files-from requires a value")?;
+                read_file_list(&list, &mut options.inputs)?;
+            }
+            Some("--convert") => convert = true,
+            Some("--keep-originals") => keep_originals = true,
+            Some("--skip-unsure") => skip_unsure = true,
+            Some("--tld") => {
+                let value = args.next().ok_or("--tld requires a value")?;
+                options.tld = Some(parse_tld(&value)?);
+            }
+            Some(s) if s.starts_with("--tld=") => {
+                options.tld = Some(parse_tld(&OsString::from(&s["--tld=".len()..]))?);
+            }
+            Some(s) if s.starts_with('-') && s != "-" => {
+                return Err(format!("unknown option: {}", s));
+            }
+            _ => options.inputs.push(arg.into()),
+        }
+    }
+    if options.candidates && options.jsonl {
+        return Err(String::from("--candidates cannot be used with --jsonl"));
+    }
+    if options.subtitles && options.jsonl {
+        return Err(String::from("--subtitles cannot be used with --jsonl"));
+    }
+    if convert {
+        if options.candidates || options.jsonl {
+            return Err(String::from(
+                "--candidates and --jsonl cannot be used with --convert",
+            ));
+        }
+        options.convert = Some(ConvertOptions {
+            keep_originals,
+            skip_unsure,
+            subtitles: options.subtitles,
+        });
+    } else if keep_originals || skip_unsure {
+        return Err(String::from(
+            "--keep-originals and --skip-unsure require --convert",
+        ));
+    }
+    if options.inputs.is_empty() {
+        options.inputs.push(PathBuf::from("-"));
+    }
+    Ok(options)
+}
+
+fn read_file_list(list: &OsString, inputs: &mut Vec<PathBuf>) -> Result<(), String> {
+    let reader: Box<dyn BufRead> = if list == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        let file = File::open(list)
+            .map_err(|err| format!("{}: {}", PathBuf::from(list).display(), err))?;
+        Box::new(BufReader::new(file))
+    };
+    for line in reader.lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        if !line.is_empty() {
+            inputs.push(PathBuf::from(line));
+        }
+    }
+    Ok(())
+}
+
+/// Validates the TLD the same way `EncodingDetector::guess` would, but
+/// reports an error instead of panicking.
+fn parse_tld(value: &OsString) -> Result<Vec<u8>, String> {
+    let label = value
+        .to_str()
+        .ok_or_else(|| String::from("--tld must be ASCII"))?;
+    if label
+        .bytes()
+        .any(|b| b >= 0x80 || b == b'.' || b.is_ascii_uppercase())
+    {
+        return Err(format!(
+            "--tld must be a single lower-case ASCII label: {}",
+            label
+        ));
+    }
+    Ok(label.as_bytes().to_vec())
+}
+
+fn detect<R: Read>(mut reader: R) -> std::io::Result<EncodingDetector> {
+    let mut detector = EncodingDetector::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            detector.feed(b"", true);
+            return Ok(detector);
+        }
+        detector.feed(&buffer[..read], false);
+    }
+}
+
+fn detect_subtitles<R: Read>(mut reader: R) -> std::io::Result<SubtitleDetector> {
+    let mut detector = SubtitleDetector::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            detector.feed(b"", true);
+            return Ok(detector);
+        }
+        detector.feed(&buffer[..read], false);
+    }
+}
+
+/// Returns the guess, the `guess_assess` Boolean, and the candidates for
+/// one input.
+fn guess_one<R: Read>(
+    reader: R,
+    options: &Options,
+) -> std::io::Result<(&'static Encoding, bool, RankedCandidates)> {
+    let tld = options.tld.as_deref();
+    if options.subtitles {
+        let detector = detect_subtitles(reader)?;
+        let guess = detector.guess(tld, options.allow_utf8);
+        return Ok((
+            guess.encoding,
+            guess.confident,
+            detector.detector().ranked_candidates(tld),
+        ));
+    }
+    let detector = detect(reader)?;
+    let (encoding, assessment) = detector.guess_assess(tld, options.allow_utf8);
+    Ok((encoding, assessment, detector.ranked_candidates(tld)))
+}
+
+fn convert_all(options: &Options, convert_options: &ConvertOptions) -> bool {
+    let tld = options.tld.as_deref();
+    let mut failed = false;
+    for input in options.inputs.iter() {
+        let result = if input.as_os_str() == "-" {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .lock()
+                .read_to_end(&mut bytes)
+                .and_then(|_| {
+                    let stdout = std::io::stdout();
+                    let mut out = stdout.lock();
+                    convert::convert_stream(&bytes, tld, convert_options, &mut out)
+                })
+        } else {
+            convert::convert_file(input, tld, convert_options)
+        };
+        let conversion = match result {
+            Ok(conversion) => conversion,
+            Err(err) => {
+                eprintln!("chardetng: {}: {}", input.display(), err);
+                failed = true;
+                continue;
+            }
+        };
+        // Standard output may carry the converted text, so report on
+        // standard error.
+        let action = match conversion.outcome {
+            Outcome::Converted { had_errors: false } => "converted",
+            Outcome::Converted { had_errors: true } => "converted with replacement characters",
+            Outcome::AlreadyUtf8 => "unchanged",
+            Outcome::SkippedUnsure => "skipped",
+        };
+        eprintln!(
+            "{}: {} {} {}",
+            input.display(),
+            conversion.encoding.name(),
+            conversion.assessment,
+            action
+        );
+    }
+    failed
+}
+
+fn guess_all(options: &Options) -> bool {
+    let print_names = options.inputs.len() > 1;
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let mut failed = false;
+    for input in options.inputs.iter() {
+        let result = if input.as_os_str() == "-" {
+            guess_one(std::io::stdin().lock(), options)
+        } else {
+            File::open(input).and_then(|file| guess_one(file, options))
+        };
+        let (encoding, assessment, candidates) = match result {
+            Ok(guess) => guess,
+            Err(err) => {
+                eprintln!("chardetng: {}: {}", input.display(), err);
+                failed = true;
+                continue;
+            }
+        };
+        if print_names {
+            let _ = write!(out, "{}: ", input.display());
+        }
+        let _ = writeln!(out, "{} {}", encoding.name(), assessment);
+        if options.candidates {
+            for (candidate, score) in candidates.into_iter() {
+                let _ = writeln!(out, "    {} {}", candidate.name(), score);
+            }
+        }
+    }
+    failed
+}
+
+fn main() {
+    let options = match parse_args(std::env::args_os().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("chardetng: {}", message);
+            eprint!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let failed = if let Some(convert_options) = options.convert.as_ref() {
+        convert_all(&options, convert_options)
+    } else if options.jsonl {
+        jsonl::write_all(
+            &options.inputs,
+            options.tld.as_deref(),
+            options.allow_utf8,
+            &mut std::io::stdout().lock(),
+        )
+    } else {
+        guess_all(&options)
+    };
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detection for the values of a database column.
+
+use crate::EncodingDetector;
+use alloc::vec::Vec;
+use encoding_rs::Encoding;
+
+/// The outcome of `ColumnDetector::guess()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnGuess {
+    /// The encoding of the column.
+    pub encoding: &'static Encoding,
+    /// The Boolean returned by `guess_assess()`.
+    pub confident: bool,
+    /// The zero-based indices of the rows that are invalid in `encoding`
+    /// in ascending order.
+    pub outliers: Vec<u64>,
+}
+
+/// Wraps an `EncodingDetector` in the short-string mode (see
+/// `EncodingDetector::set_short_strings()`) for the values of one text
+/// column, which are assumed to share an encoding.
+///
+/// Each value is fed as a fragment (see
+/// `EncodingDetector::feed_fragment()`), so no character pairs are
+/// scored across values. Unlike with `ShortStringDetector`, a value that
+/// is invalid in an encoding doesn't rule the encoding out. The value
+/// just doesn't count for that encoding, and it is reported as an
+/// outlier if that encoding wins. An encoding is ruled out if more than
+/// a given percentage of the values that contain non-ASCII are invalid
+/// in it; see `set_max_outlier_percent()`.
+pub struct ColumnDetector {
+    detector: EncodingDetector,
+    rows: u64,
+    non_ascii_rows: u64,
+    /// The number of rejected rows by candidate index.
+    rejections: [u64; 27],
+    /// The rows that were rejected by some candidate together with the
+    /// candidates as bits by index.
+    rejected_rows: Vec<(u64, u32)>,
+    max_outlier_percent: u8,
+}
+
+wrapper! {
+    ColumnDetector,
+    /// Creates a new instance for one column with a new
+    /// `EncodingDetector`.
+    fn new;
+    /// Returns the wrapped detector for calling its other methods, such
+    /// as `non_ascii_count()`. Its `guess()` doesn't tolerate outliers.
+    fn detector;
+}
+
+impl ColumnDetector {
+    /// Creates a new instance that takes the values of the column with
+    /// `detector`, which may have been configured but must not have been
+    /// fed yet, and switches it to the short-string mode, in which
+    /// invalid values are tallied instead of ruling encodings out.
+    pub fn with_detector(mut detector: EncodingDetector) -> Self {
+        detector.set_short_strings(true);
+        detector.set_tolerant();
+        ColumnDetector {
+            detector,
+            rows: 0,
+            non_ascii_rows: 0,
+            rejections: [0; 27],
+            rejected_rows: Vec::new(),
+            max_outlier_percent: 1,
+        }
+    }
+
+    /// Sets the percentage of the values containing non-ASCII that may be
+    /// invalid in the encoding of the column. The default is 1. With 0,
+    /// every invalid value rules an encoding out.
+    pub fn set_max_outlier_percent(&mut self, percent: u8) {
+        self.max_outlier_percent = percent;
+    }
+
+    /// Adds the value of the next row. Returns `true` if the column has
+    /// contained at least one non-ASCII byte so far.
+    pub fn feed(&mut self, value: &[u8]) -> bool {
+        let non_ascii_before = self.detector.non_ascii_count();
+        let non_ascii = self.detector.feed_fragment(value, false);
+        if self.detector.non_ascii_count() != non_ascii_before {
+            self.non_ascii_rows += 1;
+        }
+        let rejected = self.detector.take_rejections();
+        if rejected != 0 {
+            for (i, count) in self.rejections.iter_mut().enumerate() {
+                if rejected & (1 << i) != 0 {
+                    *count += 1;
+                }
+            }
+            self.rejected_rows.push((self.rows, rejected));
+        }
+        self.rows += 1;
+        non_ascii
+    }
+
+    /// Returns the encoding of the column and the rows that are invalid
+    /// in it. The arguments work the same way as for
+    /// `EncodingDetector::guess()`.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters.
+    pub fn guess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> ColumnGuess {
+        let tolerated = u64::from(self.max_outlier_percent) * self.non_ascii_rows;
+        let mut excluded = 0u32;
+        for (i, &count) in self.rejections.iter().enumerate() {
+            if count * 100 > tolerated {
+                excluded |= 1 << i;
+            }
+        }
+        let (encoding, confident) = self
+            .detector
+            .guess_assess_impl(tld, allow_utf8, excluded, None);
+        let mask = self.detector.candidate_mask(encoding);
+        let outliers = self
+            .rejected_rows
+            .iter()
+            .filter(|&&(_, rejected)| rejected & mask != 0)
+            .map(|&(row, _)| row)
+            .collect();
+        ColumnGuess {
+            encoding,
+            confident,
+            outliers,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "cyrillic"))]
+mod tests {
+    use super::*;
+    use encoding_rs::UTF_8;
+    use encoding_rs::WINDOWS_1251;
+
+    #[test]
+    fn test_column_detector() {
+        let names = [
+            "Иванов",
+            "Петрова",
+            "Сидоров",
+            "Кузнецова",
+            "Смирнов",
+            "Попов",
+            "Лебедева",
+            "Козлов",
+            "Новиков",
+            "Морозова",
+        ];
+        let mut column = ColumnDetector::new();
+        column.set_max_outlier_percent(10);
+        let mut strict = ColumnDetector::new();
+        strict.set_max_outlier_percent(0);
+        for (i, name) in names.iter().enumerate() {
+            let value = WINDOWS_1251.encode(name).0;
+            column.feed(&value);
+            strict.feed(&value);
+            if i == 3 {
+                // Byte 0x98 is unassigned in windows-1251.
+                column.feed(b"\xCE\x98\xE8\xED");
+                strict.feed(b"\xCE\x98\xE8\xED");
+                column.feed(b"NULL");
+                strict.feed(b"NULL");
+            }
+        }
+        let guess = column.guess(None, false);
+        assert_eq!(guess.encoding, WINDOWS_1251);
+        assert_eq!(guess.outliers, [4]);
+        // Without tolerance, the odd row rules windows-1251 out.
+        assert_ne!(strict.guess(None, false).encoding, WINDOWS_1251);
+
+        // A UTF-8 column with a row from a legacy client.
+        let mut column = ColumnDetector::new();
+        column.set_max_outlier_percent(10);
+        for name in [
+            "Zoë",
+            "Chloé",
+            "Agnès",
+            "Hélène",
+            "Loïc",
+            "Françoise",
+            "Jérôme",
+            "Cécile",
+            "Maëlle",
+            "Noël",
+            "Bénédicte",
+        ]
+        .iter()
+        {
+            column.feed(name.as_bytes());
+        }
+        column.feed(b"Ren\xE9e");
+        let guess = column.guess(None, true);
+        assert_eq!(guess.encoding, UTF_8);
+        assert_eq!(guess.outliers, [11]);
+    }
+}
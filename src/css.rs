@@ -0,0 +1,256 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A front-end for style sheets.
+//!
+//! In CSS, non-ASCII is mostly found in strings (e.g. the `content`
+//! property) and in comments. Only those are fed to the detector, so that
+//! e.g. non-ASCII file names in `url()` don't count.
+
+use crate::wrap;
+use crate::wrap::Router;
+use crate::EncodingDetector;
+use encoding_rs::Encoding;
+use encoding_rs::UTF_16BE;
+use encoding_rs::UTF_16LE;
+use encoding_rs::UTF_8;
+
+/// The CSS Syntax spec only looks for `@charset` in the first 1024 bytes.
+const PREFIX_LEN: usize = 1024;
+
+/// What determined the encoding returned by `CssDetector::guess()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CssSource {
+    /// A byte order mark.
+    Bom,
+    /// A leading `@charset` rule.
+    Charset,
+    /// The `EncodingDetector`.
+    Detector,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    /// After `/` in code.
+    Slash,
+    /// The quote and whether the string is the argument of `url()`.
+    String(u8, bool),
+    /// After a backslash in a string.
+    StringEscape(u8, bool),
+    /// Whether the previous byte was `*`.
+    Comment(bool),
+}
+
+/// Wraps an `EncodingDetector` for style sheets that are not labeled by
+/// the transport layer.
+///
+/// A byte order mark or a leading `@charset "...";` rule determines the
+/// encoding as in the CSS Syntax spec. Otherwise, the detector guesses
+/// from the strings and comments of the style sheet, each fed as a
+/// fragment (see `EncodingDetector::feed_fragment()`).
+pub struct CssDetector {
+    detector: EncodingDetector,
+    state: State,
+    /// Whether the bytes not fed yet belong to a string or a comment.
+    in_run: bool,
+    /// The last four bytes of code, lower-cased, to recognize `url(`.
+    recent: [u8; 4],
+    prefix: [u8; PREFIX_LEN],
+    prefix_len: usize,
+}
+
+wrapper! {
+    CssDetector,
+    /// Creates a new instance for one style sheet with a new
+    /// `EncodingDetector`.
+    fn new;
+    /// Returns the wrapped detector, which has seen the strings and
+    /// comments so far, for calling its other methods, such as
+    /// `ranked_candidates()`. It knows nothing about a byte order mark or
+    /// a `@charset` rule.
+    fn detector;
+}
+
+impl CssDetector {
+    /// Creates a new instance that feeds the strings and comments of the
+    /// style sheet to `detector`, which may have been configured, e.g.
+    /// with a budget, but must not have been fed yet.
+    pub fn with_detector(detector: EncodingDetector) -> Self {
+        CssDetector {
+            detector,
+            state: State::Code,
+            in_run: false,
+            recent: [0; 4],
+            prefix: [0; PREFIX_LEN],
+            prefix_len: 0,
+        }
+    }
+
+    /// Tokenizes a chunk of the style sheet and keeps its start for
+    /// `guess()`. Returns `true` if the strings and comments have
+    /// contained non-ASCII so far.
+    ///
+    /// # Panics
+    ///
+    /// If this method has previously been called with `last` set to `true`.
+    pub fn feed(&mut self, buffer: &[u8], last: bool) -> bool {
+        let copy = core::cmp::min(buffer.len(), PREFIX_LEN - self.prefix_len);
+        self.prefix[self.prefix_len..self.prefix_len + copy].copy_from_slice(&buffer[..copy]);
+        self.prefix_len += copy;
+        wrap::feed(self, buffer, last)
+    }
+
+    /// Returns the encoding of the style sheet and what determined it.
+    /// The arguments work the same way as for
+    /// `EncodingDetector::guess()` and only matter when the detector
+    /// determines the encoding.
+    ///
+    /// A `@charset` rule naming UTF-16 means UTF-8 as in the CSS Syntax
+    /// spec. A rule with an unknown label is ignored.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters.
+    pub fn guess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> (&'static Encoding, CssSource) {
+        let prefix = &self.prefix[..self.prefix_len];
+        if let Some((encoding, _)) = Encoding::for_bom(prefix) {
+            return (encoding, CssSource::Bom);
+        }
+        if let Some(encoding) = charset_rule(prefix) {
+            return (encoding, CssSource::Charset);
+        }
+        (self.detector.guess(tld, allow_utf8), CssSource::Detector)
+    }
+}
+
+/// The route is whether the bytes are part of a string or a comment.
+impl Router for CssDetector {
+    type Route = bool;
+
+    fn detector_mut(&mut self) -> &mut EncodingDetector {
+        &mut self.detector
+    }
+
+    fn route(&self) -> bool {
+        self.in_run
+    }
+
+    fn set_route(&mut self, in_run: bool) {
+        self.in_run = in_run;
+    }
+
+    fn weight(&self, in_run: bool) -> Option<u16> {
+        if in_run {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    fn step(&mut self, b: u8) -> bool {
+        match self.state {
+            State::Code | State::Slash => {
+                if self.state == State::Slash && b == b'*' {
+                    self.state = State::Comment(false);
+                    return false;
+                }
+                self.state = match b {
+                    b'/' => State::Slash,
+                    b'"' | b'\'' => State::String(b, &self.recent == b"url("),
+                    _ => State::Code,
+                };
+                if !b.is_ascii_whitespace() {
+                    self.recent = [
+                        self.recent[1],
+                        self.recent[2],
+                        self.recent[3],
+                        b.to_ascii_lowercase(),
+                    ];
+                }
+                false
+            }
+            State::String(quote, url) => {
+                if b == quote || b == b'\n' {
+                    self.state = State::Code;
+                    return false;
+                }
+                if b == b'\\' {
+                    self.state = State::StringEscape(quote, url);
+                }
+                !url
+            }
+            State::StringEscape(quote, url) => {
+                self.state = State::String(quote, url);
+                !url
+            }
+            State::Comment(star) => {
+                if star && b == b'/' {
+                    self.state = State::Code;
+                    return false;
+                }
+                self.state = State::Comment(b == b'*');
+                true
+            }
+        }
+    }
+}
+
+/// Returns the encoding of a `@charset "...";` rule at the start of
+/// `prefix`.
+fn charset_rule(prefix: &[u8]) -> Option<&'static Encoding> {
+    let rest = prefix.strip_prefix(b"@charset \"")?;
+    let end = memchr::memchr(b'"', rest)?;
+    if rest.get(end + 1) != Some(&b';') {
+        return None;
+    }
+    let encoding = Encoding::for_label(&rest[..end])?;
+    if encoding == UTF_16BE || encoding == UTF_16LE {
+        Some(UTF_8)
+    } else {
+        Some(encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::ISO_8859_15;
+    use encoding_rs::WINDOWS_1252;
+
+    #[test]
+    fn test_css_detector() {
+        let css =
+            b".a::before { content: \"Caf\xE9 cr\xE8me \xE0 la fran\xE7aise\"; } /* \xE9t\xE9 */\n\
+.b { background: url(\"\xC3\xA4.png\"); content: '\\'\xE9' }";
+        let mut det = CssDetector::new();
+        assert!(det.feed(css, true));
+        // Only the strings and the comment count, not the URL.
+        assert_eq!(det.detector().non_ascii_count(), 7);
+        assert_eq!(det.guess(None, true), (WINDOWS_1252, CssSource::Detector));
+
+        let check = |css: &[u8], expected| {
+            let mut det = CssDetector::new();
+            det.feed(css, true);
+            assert_eq!(det.guess(None, true), expected);
+        };
+        check(
+            b"@charset \"iso-8859-15\"; a { content: \"\xC3\xA4\" }",
+            (ISO_8859_15, CssSource::Charset),
+        );
+        check(b"@charset \"utf-16\";", (UTF_8, CssSource::Charset));
+        check(b"\xEF\xBB\xBF@charset \"koi8-r\";", (UTF_8, CssSource::Bom));
+        // Not exactly the syntax that the spec looks for.
+        check(
+            b"@charset 'iso-8859-15'; a { content: \"\xC3\xA4\" }",
+            (UTF_8, CssSource::Detector),
+        );
+        check(b"@charset \"bogus\";", (UTF_8, CssSource::Detector));
+    }
+}
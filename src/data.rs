@@ -41,47 +41,86 @@ pub const ASCII_DIGIT: usize = 100;
 
 #[repr(align(64))] // Align to cache lines
 pub struct DetectorData {
+    #[cfg(feature = "cjk")]
     pub frequent_simplified: [u16; 128],
+    #[cfg(feature = "cjk")]
     pub frequent_kanji: [u16; 128],
+    #[cfg(feature = "cjk")]
     pub frequent_hangul: [u16; 128],
     latin_ascii: [u8; 128],
+    #[cfg(any(
+        feature = "cyrillic",
+        feature = "greek",
+        feature = "hebrew",
+        feature = "arabic",
+        feature = "thai"
+    ))]
     non_latin_ascii: [u8; 128],
+    #[cfg(feature = "turkish")]
     turkish_ascii: [u8; 128],
+    #[cfg(feature = "vietnamese")]
     windows_1258: [u8; 128],
+    #[cfg(feature = "central")]
     windows_1250: [u8; 128],
+    #[cfg(feature = "central")]
     iso_8859_2: [u8; 128],
+    #[cfg(feature = "cyrillic")]
     windows_1251: [u8; 128],
+    #[cfg(feature = "cyrillic")]
     koi8_u: [u8; 128],
+    #[cfg(feature = "cyrillic")]
     iso_8859_5: [u8; 128],
+    #[cfg(feature = "cyrillic")]
     ibm866: [u8; 128],
     windows_1252: [u8; 128],
     windows_1252_icelandic: [u8; 128],
+    #[cfg(feature = "greek")]
     windows_1253: [u8; 128],
+    #[cfg(feature = "greek")]
     iso_8859_7: [u8; 128],
+    #[cfg(feature = "turkish")]
     windows_1254: [u8; 128],
+    #[cfg(feature = "hebrew")]
     windows_1255: [u8; 128],
+    #[cfg(feature = "hebrew")]
     iso_8859_8: [u8; 128],
+    #[cfg(feature = "arabic")]
     windows_1256: [u8; 128],
+    #[cfg(feature = "arabic")]
     iso_8859_6: [u8; 128],
+    #[cfg(feature = "baltic")]
     windows_1257: [u8; 128],
+    #[cfg(feature = "baltic")]
     iso_8859_13: [u8; 128],
+    #[cfg(feature = "baltic")]
     iso_8859_4: [u8; 128],
+    #[cfg(feature = "thai")]
     windows_874: [u8; 128],
+    #[cfg(feature = "vietnamese")]
     vietnamese: [u8; 1975],
+    #[cfg(feature = "central")]
     central: [u8; 3895],
+    #[cfg(feature = "cyrillic")]
     cyrillic: [u8; 2112],
     western: [u8; 2752],
     icelandic: [u8; 871],
+    #[cfg(feature = "greek")]
     greek: [u8; 1365],
+    #[cfg(feature = "turkish")]
     turkish: [u8; 845],
+    #[cfg(feature = "hebrew")]
     hebrew: [u8; 1292],
+    #[cfg(feature = "arabic")]
     arabic: [u8; 2805],
+    #[cfg(feature = "baltic")]
     baltic: [u8; 1387],
+    #[cfg(feature = "thai")]
     thai: [u8; 5180],
 }
 
 #[rustfmt::skip]
 pub static DETECTOR_DATA: DetectorData = DetectorData {
+    #[cfg(feature = "cjk")]
     frequent_simplified: [
         0x7684, 0x5E74, 0x56FD, 0x65E5, 0x6708, 0x4E2D, 0x4EBA, 0x4E00, 0x5927, 0x4E3A, 0x5728, 0x662F, 0x5B66, 0x6709, 0x884C, 0x4F1A, 
         0x65AF, 0x4E8E, 0x5730, 0x533A, 0x6587, 0x548C, 0x5C14, 0x540D, 0x7B2C, 0x516C, 0x65F6, 0x5C0F, 0x90E8, 0x4E0D, 0x5E02, 0x53F0, 
@@ -92,6 +131,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
         0x4E0B, 0x8D5B, 0x9762, 0x52A0, 0x4ED6, 0x9A6C, 0x5176, 0x53C2, 0x53BF, 0x4EE3, 0x5185, 0x7406, 0x4E16, 0x4E8C, 0x7EBF, 0x53CA, 
         0x5EFA, 0x8868, 0x4F4D, 0x7F57, 0x7531, 0x7ACB, 0x591A, 0x53EF, 0x534E, 0x6797, 0x7EF4, 0x5EA6, 0x4E8B, 0x5E73, 0x5916, 0x4F53, 
     ],
+    #[cfg(feature = "cjk")]
     frequent_kanji: [
         0x5E74, 0x65E5, 0x6708, 0x5927, 0x672C, 0x5B66, 0x4EBA, 0x56FD, 0x4F1A, 0x4E2D, 0x51FA, 0x4E00, 0x8005, 0x5E02, 0x4F5C, 0x540D, 
         0x90E8, 0x7528, 0x5730, 0x884C, 0x5834, 0x7530, 0x7B2C, 0x751F, 0x5408, 0x5B50, 0x9053, 0x4E0A, 0x6771, 0x6642, 0x770C, 0x4EE3, 
@@ -102,6 +142,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
         0x756A, 0x5EA6, 0x5317, 0x5165, 0x5916, 0x983C, 0x8A9E, 0x5973, 0x8A71, 0x6A5F, 0x8A2D, 0x539F, 0x4E09, 0x524A, 0x533A, 0x6D77, 
         0x4F9D, 0x5F53, 0x73FE, 0x5BFE, 0x4F4D, 0x6570, 0x5316, 0x795E, 0x66F2, 0x7406, 0x6559, 0x7279, 0x7248, 0x5728, 0x6CD5, 0x898B, 
     ],
+    #[cfg(feature = "cjk")]
     frequent_hangul: [
         0xC774, 0xC758, 0xB2E4, 0xAE30, 0xC5D0, 0xB85C, 0xC0AC, 0xB144, 0xC2A4, 0xB9AC, 0xB294, 0xC77C, 0xD558, 0xAC00, 0xC2DC, 0xC9C0, 
         0xB300, 0xC11C, 0xBD84, 0xAD6D, 0xD55C, 0xB3C4, 0xC778, 0xACE0, 0xB958, 0xC790, 0xC8FC, 0xC544, 0xC744, 0xB77C, 0xC218, 0xC81C, 
@@ -122,6 +163,13 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
          16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,  0,  0,  0,  0,  0,
     ],
+    #[cfg(any(
+        feature = "cyrillic",
+        feature = "greek",
+        feature = "hebrew",
+        feature = "arabic",
+        feature = "thai"
+    ))]
     non_latin_ascii: [
           0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
           0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
@@ -132,6 +180,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           0,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,
           1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  1,  0,  0,  0,  0,  0,
     ],
+    #[cfg(feature = "turkish")]
     turkish_ascii: [
           0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
           0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
@@ -142,6 +191,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           0,  1,  2,  3,  4,  5,  6,  7,  8, 27,  9, 10, 11, 12, 13, 14,
          15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,  0,  0,  0,  0,  0,
     ],
+    #[cfg(feature = "vietnamese")]
     windows_1258: [
           0,255,  0, 53,  0,  0,  0,  0,  0,  0,255,  0,155,255,255,255,
         255,  0,  0,  0,  0,  0,  0,  0,  0,  0,255,  0, 27,255,255,179,
@@ -152,6 +202,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          31, 32, 33, 34, 27, 27, 27, 27, 35, 36, 37, 27, 38, 39, 40, 41,
          42, 27, 43, 44, 45, 46, 27, 56, 27, 47, 48, 49, 27, 50, 53, 51,
     ],
+    #[cfg(feature = "central")]
     windows_1250: [
           0,255,  0,255,  0,  0,  0,  0,255,  0,156,  0,157,158,159,160,
         255,  0,  0,  0,  0,  0,  0,  0,255,  0, 28,  0, 29, 30, 31, 32,
@@ -162,6 +213,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53,
          54, 55, 56, 57, 58, 59, 60, 72, 61, 62, 63, 64, 65, 66, 67, 69,
     ],
+    #[cfg(feature = "central")]
     iso_8859_2: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -172,6 +224,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53,
          54, 55, 56, 57, 58, 59, 60, 72, 61, 62, 63, 64, 65, 66, 67, 69,
     ],
+    #[cfg(feature = "cyrillic")]
     windows_1251: [
         131,130,  0,  2,  0,  0,  0,  0,  0,  0,132,  0,133,130,134,135,
           3,  0,  0,  0,  0,  0,  0,  0,255,  0,  4,  0,  5,  2,  6,  7,
@@ -182,6 +235,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29,
          30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45,
     ],
+    #[cfg(feature = "cyrillic")]
     koi8_u: [
          47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47, 47,
          47, 47, 47, 47, 47, 46,  0, 50, 50, 50,  0, 47, 49, 48, 46, 50,
@@ -192,6 +246,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
         172,142,143,164,146,147,162,145,163,150,151,152,153,154,155,156,
         157,173,158,159,160,161,148,144,170,169,149,166,171,167,165,168,
     ],
+    #[cfg(feature = "cyrillic")]
     iso_8859_5: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -202,6 +257,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45,
          47, 10,  3,  2, 11,  2,  9, 13, 12,  4,  5,  6,  2, 47,  8,  7,
     ],
+    #[cfg(feature = "cyrillic")]
     ibm866: [
         142,143,144,145,146,147,148,149,150,151,152,153,154,155,156,157,
         158,159,160,161,162,163,164,165,166,167,168,169,170,171,172,173,
@@ -232,6 +288,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          27, 28, 27, 27, 29, 27, 30, 27, 27, 31, 27, 27, 27, 32, 27, 27,
          33, 27, 27, 34, 27, 27, 35, 44, 36, 27, 37, 27, 27, 38, 39, 27,
     ],
+    #[cfg(feature = "greek")]
     windows_1253: [
          38,255,  0, 38,  0,  0,  0,  0,255,  0,255,  0,255,255,255,255,
         255,  0,  0,  0,  0,  0,  0,  0,255,  0,255,  0,255,255,255,255,
@@ -242,6 +299,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           2,  7,  8,  9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
          22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36,255,
     ],
+    #[cfg(feature = "greek")]
     iso_8859_7: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -252,6 +310,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           2,  7,  8,  9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
          22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36,255,
     ],
+    #[cfg(feature = "turkish")]
     windows_1254: [
           0,255,  0, 40,  0,  0,  0,  0,  0,  0,156,  0,156,255,255,255,
         255,  0,  0,  0,  0,  0,  0,  0,  0,  0, 28,  0, 28,255,255,156,
@@ -262,6 +321,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          28, 28, 30, 28, 29, 28, 28, 31, 28, 28, 32, 28, 28, 28, 33, 28,
          34, 28, 28, 28, 28, 28, 35, 43, 28, 28, 28, 36, 37, 26, 38, 28,
     ],
+    #[cfg(feature = "hebrew")]
     windows_1255: [
           0,255,  0, 37,  0,  0,  0,  0,  0,  0,255,  0,255,255,255,255,
         255,  0,  0,  0,  0,  0,  0,  0,  0,  0,255,  0,255,255,255,255,
@@ -272,6 +332,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
          25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35,255,255, 37, 37,255,
     ],
+    #[cfg(feature = "hebrew")]
     iso_8859_8: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -282,6 +343,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
          25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35,255,255, 37, 37,255,
     ],
+    #[cfg(feature = "arabic")]
     windows_1256: [
           0,  3,  0, 54,  0,  0,  0,  0,  0,  0,  4,  0,129,  5,  6,  7,
           8,  0,  0,  0,  0,  0,  0,  0,  9,  0, 10,  0,  1,  2, 53, 11,
@@ -292,6 +354,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           1, 44,  1, 45, 46, 47, 48,  1,  1,  1,  1,  1, 49, 50,  1,  1,
          51, 51, 51, 51,  1, 51, 51, 57, 51,  1, 51,  1,  1, 54, 54, 52,
     ],
+    #[cfg(feature = "arabic")]
     iso_8859_6: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -302,6 +365,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 51, 51, 51, 51,
          51, 51, 51,255,255,255,255,255,255,255,255,255,255,255,255,255,
     ],
+    #[cfg(feature = "baltic")]
     windows_1257: [
           0,255,  0,255,  0,  0,  0,  0,255,  0,255,  0,255, 47, 47, 47,
         255,  0,  0,  0,  0,  0,  0,  0,255,  0,255,  0,255, 47, 47,255,
@@ -312,6 +376,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          29, 30, 31, 27, 27, 27, 32, 33, 34, 27, 27, 35, 36, 37, 38, 39,
          40, 27, 41, 27, 42, 27, 27, 50, 43, 27, 27, 44, 27, 27, 45, 47,
     ],
+    #[cfg(feature = "baltic")]
     iso_8859_13: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -322,6 +387,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          29, 30, 31, 27, 27, 27, 32, 33, 34, 27, 27, 35, 36, 37, 38, 39,
          40, 27, 41, 27, 42, 27, 27, 50, 43, 27, 27, 44, 27, 27, 45, 46,
     ],
+    #[cfg(feature = "baltic")]
     iso_8859_4: [
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
         255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,
@@ -332,6 +398,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          31, 27, 27, 27, 27, 27, 27, 30, 34, 27, 32, 27, 35, 27, 27, 38,
          27, 41, 42, 37, 27, 27, 27, 50, 27, 43, 27, 27, 27, 27, 44, 47,
     ],
+    #[cfg(feature = "thai")]
     windows_874: [
          77,255,255,255,255,  0,255,255,255,255,255,255,255,255,255,255,
         255,  0,  0,  0,  0,  0,  0,  0,255,255,255,255,255,255,255,255,
@@ -342,6 +409,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          57, 58, 59, 60, 61, 71, 62, 63, 64, 65, 66, 67, 68, 69, 70, 77,
          77, 77, 77, 77, 77, 77, 77, 77, 77, 77, 77, 77,255,255,255,255,
     ],
+    #[cfg(feature = "vietnamese")]
     vietnamese: [
                                                                                                                       0, 58, 58, 22, 33,  7,  0,  0,  0,  0,  1, 57,  3,  0,  0,  0, 39, 14,  8,  3,  0, 11,  0,  5,  0, //  ,
                                                                                                                       0,  3, 16,  2,  0,  0,  0,  0,  0,  0,  0,  3,  1,255,  0, 17,  2,  1,  0,  0,  1,  0,  0,  1,255, // a,
@@ -397,6 +465,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           0,  0,255,255,255,255,255,255,255,255,255,255,  0,255,255,  0,255,255,  0,255,255,  0,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255,255, // ÿ,
       //   ,  a,  b,  c,  d,  e,  f,  g,  h,  i,  j,  k,  l,  m,  n,  o,  p,  q,  r,  s,  t,  u,  v,  w,  x,  y,  z,  ß,  ̀,  ̉,  ̃,  à,  á,  â,  ă,  è,  é,  ê,  ́,  í,  î,  ï,  đ,  ̣,  ó,  ô,  ơ,  ù,  ú,  û,  ư,  ÿ,
     ],
+    #[cfg(feature = "central")]
     central: [
                                                                                                                       0, 42, 11, 51, 30,  3,154, 77, 18, 20, 23,  0,139,  0,254,  1,  0, 79,  0, 70,132,121,  0, 52,241, 14,  5,  7, 17,  8, 74,  0, 58,  0,  8, 33, 36,  9,  1,105,  8, //  ,
                                                                                                                       0, 70,  0, 11,146,  0,115,  0, 11, 23, 12,  0,  0,  0,  2,  0,  0, 66,  0,107,  1,  0,  0,  0, 10,  0, 10, 25,  0,  8,  1,  0,  1,  0,  9,  0,  0,  0,  0,  0, 48, // a,
@@ -468,6 +537,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          20,111,  1, 33,  0, 31,  0,  0,  0, 31,  0,  0,  5,  1, 78,  5,  2,  0, 10,  1,  0, 25,  2,  0,  0,  0,  0,255,255,255,255,255,255,255,255,  0,255,255,255,  0,  0, 25,255,255,255,255,255,  0,255,255,255,  0,  4,255,255,255,255,  0,255,255,255,255,255,  0,255,255,255,  0, // ţ,
       //   ,  a,  b,  c,  d,  e,  f,  g,  h,  i,  j,  k,  l,  m,  n,  o,  p,  q,  r,  s,  t,  u,  v,  w,  x,  y,  z,  ß,  š,  ś,  ť,  ž,  ź,  ł,  ą,  ş,  ľ,  ż,  ŕ,  á,  â,  ă,  ä,  ĺ,  ć,  ç,  č,  é,  ę,  ë,  ě,  í,  î,  ď,  đ,  ń,  ň,  ó,  ô,  ő,  ö,  ř,  ů,  ú,  ű,  ü,  ý,  ţ,
     ],
+    #[cfg(feature = "cyrillic")]
     cyrillic: [
                   0,  0,  0,  0,  1,  0, 16, 38,  0,  2,  5, 10,121,  4, 20, 25, 26, 53,  9,  5, 61, 23, 20, 26, 15, 95, 60,  2, 26, 15, 25, 29,  0, 14,  6,  6, 25,  1,  0, 27, 25,  8,  5, 39, //  ,
                   0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0, // a,
@@ -622,6 +692,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          87,  1,255,255,  0,  0,  0,  0,  0,  0,  0,  0,  2,  0,  0,  0,  0,255,  0,  0,  0,  0,  0,255,255,  0,255,255,  0,255,  0,  0,  2,  0,  0,  0,255,  0,  0,  0, // þ,
       //   ,  a,  b,  c,  d,  e,  f,  g,  h,  i,  j,  k,  l,  m,  n,  o,  p,  q,  r,  s,  t,  u,  v,  w,  x,  y,  z,  ß,  á,  ä,  æ,  é,  í,  ð,  ó,  ö,  ø,  ú,  ý,  þ,
     ],
+    #[cfg(feature = "greek")]
     greek: [
                   0, 12,  0, 16,  6, 69,  1,  0,  0, 24,  0, 33,  0, 41,  2,  2,  1, 50,  0, 44,  1,  2,105,  1,  2, 33,  0,  0,  0,  2,  0,  0, 15,  5,  1, //  ,
                 255,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,255,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0, // a,
@@ -662,6 +733,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           0,  0,255,255,255,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  2,  2,  1,  0,  2,  0,  0,  0,  3,255,  0,  1,  0,  0,  1,  0,  0,  0,255,255,255,255, // ώ,
       //   ,  a,  ΐ,  ά,  έ,  ή,  ί,  α,  β,  γ,  δ,  ε,  ζ,  η,  θ,  ι,  κ,  λ,  μ,  ν,  ξ,  ο,  π,  ρ,  ς,  σ,  τ,  υ,  φ,  χ,  ψ,  ω,  ϊ,  ϋ,  ό,  ύ,  ώ,
     ],
+    #[cfg(feature = "turkish")]
     turkish: [
                                                                                                                 195,254,  0,140,  0, 12,220,165,  2,  1, 58, 25, 27, //  ,
                                                                                                                   1, 23,  0,  2,  0, 19,  0,  0,  4,  0,  0,  1, 26, // a,
@@ -704,6 +776,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          33, 45,  1,  0,  0, 18,  0,  0,  0,  0,  0,  0,  0,  1,  1,  0,  0, 14,  0,  0, 12,  0,  1,  5,  0,  0, 28, 33,  0, 11,  0,  0,  6,  5,  0,  0,  2,  6,  0, // ş,
       //   ,  a,  b,  c,  d,  e,  f,  g,  h,  j,  k,  l,  m,  n,  o,  p,  q,  r,  s,  t,  u,  v,  w,  x,  y,  z,  ı,  i,  ß,  ä,  â,  ç,  ê,  î,  ğ,  ö,  û,  ü,  ş,
     ],
+    #[cfg(feature = "hebrew")]
     hebrew: [
                   0,  1,  0,  0,  0,  0,  0, 28, 29, 17, 24,144, 46, 26,  7, 88, 68, 22,  2, 71,106,  3,200,  0, 35, 69, 10,  2,  9,  2, 13,104, 19,138, //  ,
                   0,255,255,  0,255,255,255,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,255,  0,  0,  0,  0,  0,  0,  0, // a,
@@ -743,6 +816,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          21,  0,  0,  0,  0,  0,255,255,  0, 17,  8,  1,  2,  9, 65,  0,  6,  1, 34,  0,  5,  5,  0,  8,  0,  8,  2,  4,255,  5,  0,  1,  1, 10, 11,  1, // ת,
       //   ,  a,  ְ,  ַ,  ָ,  ּ,  װ,  ױ,  ײ,  א,  ב,  ג,  ד,  ה,  ו,  ז,  ח,  ט,  י,  ך,  כ,  ל,  ם,  מ,  ן,  נ,  ס,  ע,  ף,  פ,  ץ,  צ,  ק,  ר,  ש,  ת,
     ],
+    #[cfg(feature = "arabic")]
     arabic: [
                   0,  8, 11,  2,  0,  9,  7, 40,  0, 34,  6, 74,  8,  1,  0,  0,  0, 10, 74, 21, 87, 40,  2,  3,  3,  5, 50,  0, 78, 23, 29,  9,  3,  2,  5,  5, 13,  7,  0, 22,  7,  4, 35, 42, 69, 85, 37, 11, 44,  0, 49, //  ,
                   0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,255,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0, // a,
@@ -799,6 +873,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
           2,  0,255,  0,  0,  1,  0,  0,  0, 12,  0,  0,  0,  6,  0,  0,255,  0,255,  5,  0,  0,255,  1,  0,  0,  0,  0,  0,  0,  0,  0,  4,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  1,  0,  4,  0,  0,255,  0,  0,  0, // ے,
       //   ,  a,  ‌,  پ,  ٹ,  چ,  ژ,  ڈ,  گ,  ک,  ڑ,  ں,  ھ,  ہ,  ء,  آ,  أ,  ؤ,  إ,  ئ,  ا,  ب,  ة,  ت,  ث,  ج,  ح,  خ,  د,  ذ,  ر,  ز,  س,  ش,  ص,  ض,  ط,  ظ,  ع,  غ,  ـ,  ف,  ق,  ك,  ل,  م,  ن,  ه,  و,  ى,  ي,  ً,  ے,
     ],
+    #[cfg(feature = "baltic")]
     baltic: [
                                                                                                                       0,  0, 57, 42,135, 14, 20,  3,119,  0,  0, 18,  1, 18,  0,  0,205,  1,  4, //  ,
                                                                                                                       0,  0,  0,  0,  0,  0,  0,  2,  0,  0,  1,  0, 16, 39, 14,  0,  0,  0, 16, // a,
@@ -848,6 +923,7 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
          46, 17,  0,  0, 30, 11,  0,  1,  0,  3,  0,  0,  1,  3,  1,  2,  0,  0,  8,  0,  0, 16,  0,  0,  0,  2,  0,  0,255,  1,  1,  0,  0,  0,  0,  3,255,255,  0,  0,  0,  0,255,  0,  0,  0, // ž,
       //   ,  a,  b,  c,  d,  e,  f,  g,  h,  i,  j,  k,  l,  m,  n,  o,  p,  q,  r,  s,  t,  u,  v,  w,  x,  y,  z,  ß,  ŗ,  ą,  į,  ā,  ę,  ē,  č,  ė,  ģ,  ķ,  ī,  ļ,  š,  ņ,  ō,  ų,  ū,  ž,
     ],
+    #[cfg(feature = "thai")]
     thai: [
                   6,  0,  2,  0, 14,  0,  0,  1,  0,  0,  0,  0,  0,  0,  0,  0,  1,  4,  2,  0,  1,  0, 21,  3,  1,  0,  0,  5,  0,  0,  9,  9,  4,  0,  4,  2,  6,  0,  2,  0,  0,  4,  0,  0,  4,  0, 11,  0,  2,  8,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  1,  0, 10,  3,  0,  0, 16,  0,  0,  0, //  ,
                   0,  0,  0,255,  0,  0,255,  0,255,255,  0,255,255,  0,255,255,  0,  0,  0,  0,  0,255,  0,  0,  0,255,255,  0,  0,255,  0,  0,  0,255,  0,  0,  0,  0,  0,255,255,  0,255,255,  0,  0,  0,  0,  0,  0,255,  0,  0,  0,  0,  0,  0,255,255,255,  0,  0,  0,  0,  0,  0,  0,  0,255,255, // a,
@@ -925,27 +1001,45 @@ pub static DETECTOR_DATA: DetectorData = DetectorData {
     ],
 };
 
+#[cfg(feature = "vietnamese")]
 const VIETNAMESE_ASCII: usize = 27;
+#[cfg(feature = "vietnamese")]
 const VIETNAMESE_NON_ASCII: usize = 25;
+#[cfg(feature = "central")]
 const CENTRAL_ASCII: usize = 27;
+#[cfg(feature = "central")]
 const CENTRAL_NON_ASCII: usize = 41;
+#[cfg(feature = "cyrillic")]
 const CYRILLIC_ASCII: usize = 2;
+#[cfg(feature = "cyrillic")]
 const CYRILLIC_NON_ASCII: usize = 44;
 const WESTERN_ASCII: usize = 27;
 const WESTERN_NON_ASCII: usize = 32;
 const ICELANDIC_ASCII: usize = 27;
 const ICELANDIC_NON_ASCII: usize = 13;
+#[cfg(feature = "greek")]
 const GREEK_ASCII: usize = 2;
+#[cfg(feature = "greek")]
 const GREEK_NON_ASCII: usize = 35;
+#[cfg(feature = "turkish")]
 const TURKISH_ASCII: usize = 26;
+#[cfg(feature = "turkish")]
 const TURKISH_NON_ASCII: usize = 13;
+#[cfg(feature = "hebrew")]
 const HEBREW_ASCII: usize = 2;
+#[cfg(feature = "hebrew")]
 const HEBREW_NON_ASCII: usize = 34;
+#[cfg(feature = "arabic")]
 const ARABIC_ASCII: usize = 2;
+#[cfg(feature = "arabic")]
 const ARABIC_NON_ASCII: usize = 51;
+#[cfg(feature = "baltic")]
 const BALTIC_ASCII: usize = 27;
+#[cfg(feature = "baltic")]
 const BALTIC_NON_ASCII: usize = 19;
+#[cfg(feature = "thai")]
 const THAI_ASCII: usize = 2;
+#[cfg(feature = "thai")]
 const THAI_NON_ASCII: usize = 70;
 #[inline(always)]
 fn compute_index(
@@ -972,11 +1066,11 @@ fn compute_index(
 
 pub struct SingleByteData {
     pub encoding: &'static Encoding,
-    lower: &'static [u8; 128],
-    upper: &'static [u8; 128],
-    probabilities: &'static [u8],
-    ascii: usize,
-    non_ascii: usize,
+    pub lower: &'static [u8; 128],
+    pub upper: &'static [u8; 128],
+    pub probabilities: &'static [u8],
+    pub ascii: usize,
+    pub non_ascii: usize,
 }
 
 impl SingleByteData {
@@ -997,6 +1091,13 @@ impl SingleByteData {
         caseless_class_usize > 0 && caseless_class_usize < (self.ascii + self.non_ascii)
     }
 
+    #[cfg(any(
+        feature = "cyrillic",
+        feature = "greek",
+        feature = "hebrew",
+        feature = "arabic",
+        feature = "thai"
+    ))]
     #[inline(always)]
     pub fn is_non_latin_alphabetic(
         &'static self,
@@ -1134,6 +1235,7 @@ impl PartialEq for SingleByteData {
 }
 
 pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
+    #[cfg(feature = "vietnamese")]
     SingleByteData {
         encoding: &WINDOWS_1258_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1142,6 +1244,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: VIETNAMESE_ASCII,
         non_ascii: VIETNAMESE_NON_ASCII,
     },
+    #[cfg(not(feature = "vietnamese"))]
+    SingleByteData {
+        encoding: &WINDOWS_1258_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "central")]
     SingleByteData {
         encoding: &WINDOWS_1250_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1150,6 +1262,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: CENTRAL_ASCII,
         non_ascii: CENTRAL_NON_ASCII,
     },
+    #[cfg(not(feature = "central"))]
+    SingleByteData {
+        encoding: &WINDOWS_1250_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "central")]
     SingleByteData {
         encoding: &ISO_8859_2_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1158,6 +1280,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: CENTRAL_ASCII,
         non_ascii: CENTRAL_NON_ASCII,
     },
+    #[cfg(not(feature = "central"))]
+    SingleByteData {
+        encoding: &ISO_8859_2_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "cyrillic")]
     SingleByteData {
         encoding: &WINDOWS_1251_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1166,6 +1298,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: CYRILLIC_ASCII,
         non_ascii: CYRILLIC_NON_ASCII,
     },
+    #[cfg(not(feature = "cyrillic"))]
+    SingleByteData {
+        encoding: &WINDOWS_1251_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "cyrillic")]
     SingleByteData {
         encoding: &KOI8_U_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1174,6 +1316,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: CYRILLIC_ASCII,
         non_ascii: CYRILLIC_NON_ASCII,
     },
+    #[cfg(not(feature = "cyrillic"))]
+    SingleByteData {
+        encoding: &KOI8_U_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "cyrillic")]
     SingleByteData {
         encoding: &ISO_8859_5_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1182,6 +1334,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: CYRILLIC_ASCII,
         non_ascii: CYRILLIC_NON_ASCII,
     },
+    #[cfg(not(feature = "cyrillic"))]
+    SingleByteData {
+        encoding: &ISO_8859_5_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "cyrillic")]
     SingleByteData {
         encoding: &IBM866_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1190,6 +1352,15 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: CYRILLIC_ASCII,
         non_ascii: CYRILLIC_NON_ASCII,
     },
+    #[cfg(not(feature = "cyrillic"))]
+    SingleByteData {
+        encoding: &IBM866_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
     SingleByteData {
         encoding: &WINDOWS_1252_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1206,6 +1377,7 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: ICELANDIC_ASCII,
         non_ascii: ICELANDIC_NON_ASCII,
     },
+    #[cfg(feature = "greek")]
     SingleByteData {
         encoding: &WINDOWS_1253_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1214,6 +1386,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: GREEK_ASCII,
         non_ascii: GREEK_NON_ASCII,
     },
+    #[cfg(not(feature = "greek"))]
+    SingleByteData {
+        encoding: &WINDOWS_1253_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "greek")]
     SingleByteData {
         encoding: &ISO_8859_7_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1222,6 +1404,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: GREEK_ASCII,
         non_ascii: GREEK_NON_ASCII,
     },
+    #[cfg(not(feature = "greek"))]
+    SingleByteData {
+        encoding: &ISO_8859_7_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "turkish")]
     SingleByteData {
         encoding: &WINDOWS_1254_INIT,
         lower: &DETECTOR_DATA.turkish_ascii,
@@ -1230,6 +1422,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: TURKISH_ASCII,
         non_ascii: TURKISH_NON_ASCII,
     },
+    #[cfg(not(feature = "turkish"))]
+    SingleByteData {
+        encoding: &WINDOWS_1254_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "hebrew")]
     SingleByteData {
         encoding: &WINDOWS_1255_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1238,6 +1440,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: HEBREW_ASCII,
         non_ascii: HEBREW_NON_ASCII,
     },
+    #[cfg(not(feature = "hebrew"))]
+    SingleByteData {
+        encoding: &WINDOWS_1255_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "hebrew")]
     SingleByteData {
         encoding: &ISO_8859_8_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1246,6 +1458,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: HEBREW_ASCII,
         non_ascii: HEBREW_NON_ASCII,
     },
+    #[cfg(not(feature = "hebrew"))]
+    SingleByteData {
+        encoding: &ISO_8859_8_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "arabic")]
     SingleByteData {
         encoding: &WINDOWS_1256_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1254,6 +1476,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: ARABIC_ASCII,
         non_ascii: ARABIC_NON_ASCII,
     },
+    #[cfg(not(feature = "arabic"))]
+    SingleByteData {
+        encoding: &WINDOWS_1256_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "arabic")]
     SingleByteData {
         encoding: &ISO_8859_6_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1262,6 +1494,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: ARABIC_ASCII,
         non_ascii: ARABIC_NON_ASCII,
     },
+    #[cfg(not(feature = "arabic"))]
+    SingleByteData {
+        encoding: &ISO_8859_6_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "baltic")]
     SingleByteData {
         encoding: &WINDOWS_1257_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1270,6 +1512,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: BALTIC_ASCII,
         non_ascii: BALTIC_NON_ASCII,
     },
+    #[cfg(not(feature = "baltic"))]
+    SingleByteData {
+        encoding: &WINDOWS_1257_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "baltic")]
     SingleByteData {
         encoding: &ISO_8859_13_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1278,6 +1530,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: BALTIC_ASCII,
         non_ascii: BALTIC_NON_ASCII,
     },
+    #[cfg(not(feature = "baltic"))]
+    SingleByteData {
+        encoding: &ISO_8859_13_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "baltic")]
     SingleByteData {
         encoding: &ISO_8859_4_INIT,
         lower: &DETECTOR_DATA.latin_ascii,
@@ -1286,6 +1548,16 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: BALTIC_ASCII,
         non_ascii: BALTIC_NON_ASCII,
     },
+    #[cfg(not(feature = "baltic"))]
+    SingleByteData {
+        encoding: &ISO_8859_4_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
+    #[cfg(feature = "thai")]
     SingleByteData {
         encoding: &WINDOWS_874_INIT,
         lower: &DETECTOR_DATA.non_latin_ascii,
@@ -1294,6 +1566,15 @@ pub static SINGLE_BYTE_DATA: [SingleByteData; 20] = [
         ascii: THAI_ASCII,
         non_ascii: THAI_NON_ASCII,
     },
+    #[cfg(not(feature = "thai"))]
+    SingleByteData {
+        encoding: &WINDOWS_874_INIT,
+        lower: &[255; 128],
+        upper: &[255; 128],
+        probabilities: &[],
+        ascii: 0,
+        non_ascii: 0,
+    },
 ];
 
 pub const WINDOWS_1258_INDEX: usize = 0;
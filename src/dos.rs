@@ -0,0 +1,294 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! DOS code pages other than IBM866.
+//!
+//! ZIP archives created on DOS and Windows store entry names in the OEM
+//! code page, which is CP437 by default. encoding_rs doesn't support the
+//! Latin DOS code pages, so this module has stand-in decoding tables for
+//! them. There are no trained models for them, either: the bytes are
+//! mapped to windows-1252 via the decoding table and scored with the
+//! detector's windows-1252 model, so a model passed to
+//! `EncodingDetector::with_model()` applies to them, too. Characters that
+//! windows-1252 doesn't have, such as box drawing, are scored as a
+//! symbol.
+
+use crate::data::SingleByteData;
+use crate::LatinCandidate;
+use encoding_rs::Encoding;
+
+/// A DOS code page that encoding_rs doesn't support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DosCodePage {
+    /// CP437, the original IBM PC code page and the default for ZIP
+    /// entry names.
+    Ibm437,
+    /// CP850, DOS Latin 1.
+    Ibm850,
+}
+
+impl DosCodePage {
+    /// The IANA name of the code page.
+    pub fn name(self) -> &'static str {
+        match self {
+            DosCodePage::Ibm437 => "IBM437",
+            DosCodePage::Ibm850 => "IBM850",
+        }
+    }
+
+    /// The number of the code page, e.g. 437.
+    pub fn number(self) -> u16 {
+        match self {
+            DosCodePage::Ibm437 => 437,
+            DosCodePage::Ibm850 => 850,
+        }
+    }
+
+    /// Decodes a byte. The lower half is ASCII, including the C0
+    /// controls, which archive names don't use for their graphical
+    /// meaning anyway.
+    pub fn decode_byte(self, byte: u8) -> char {
+        if byte < 0x80 {
+            char::from(byte)
+        } else {
+            self.upper_half()[usize::from(byte - 0x80)]
+        }
+    }
+
+    /// Decodes `bytes`. Every byte decodes to a character, so there are
+    /// no malformed sequences.
+    #[cfg(feature = "alloc")]
+    pub fn decode(self, bytes: &[u8]) -> alloc::string::String {
+        bytes.iter().map(|&b| self.decode_byte(b)).collect()
+    }
+
+    fn upper_half(self) -> &'static [char; 128] {
+        match self {
+            DosCodePage::Ibm437 => &IBM437_UPPER,
+            DosCodePage::Ibm850 => &IBM850_UPPER,
+        }
+    }
+
+    /// The byte to score `byte` as with the windows-1252 model.
+    fn to_windows_1252(self, byte: u8) -> u8 {
+        if byte < 0x80 {
+            return byte;
+        }
+        match u32::from(self.decode_byte(byte)) {
+            c @ 0xA0..=0xFF => c as u8,
+            0x0192 => 0x83, // ƒ
+            _ => 0xA6,      // ¦
+        }
+    }
+}
+
+/// The outcome of `EncodingDetector::guess_with_dos()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameEncoding {
+    /// An encoding supported by encoding_rs.
+    Encoding(&'static Encoding),
+    /// A DOS code page that encoding_rs doesn't support.
+    Dos(DosCodePage),
+}
+
+/// A candidate for a DOS code page.
+pub(crate) struct DosCandidate {
+    code_page: DosCodePage,
+    inner: LatinCandidate,
+    score: Option<i64>,
+}
+
+impl DosCandidate {
+    /// `data` is the windows-1252 model of the detector.
+    pub fn new(code_page: DosCodePage, data: &'static SingleByteData) -> Self {
+        DosCandidate {
+            code_page,
+            inner: LatinCandidate::new(data, true),
+            score: Some(0),
+        }
+    }
+
+    pub fn code_page(&self) -> DosCodePage {
+        self.code_page
+    }
+
+    pub fn score(&self) -> Option<i64> {
+        self.score
+    }
+
+    /// Scores `buffer` with `weight` and, if `last`, the end of the
+    /// stream, which is not weighted.
+    pub fn feed(&mut self, buffer: &[u8], last: bool, weight: i64) {
+        let mut buf = [0u8; 64];
+        for chunk in buffer.chunks(buf.len()) {
+            for (dst, &src) in buf.iter_mut().zip(chunk.iter()) {
+                *dst = self.code_page.to_windows_1252(src);
+            }
+            self.add(&buf[..chunk.len()], weight);
+        }
+        if last {
+            self.add(b" ", 1);
+        }
+    }
+
+    /// Scores the boundary after a fragment.
+    pub fn end_fragment(&mut self) {
+        self.add(b" ", 1);
+    }
+
+    fn add(&mut self, buffer: &[u8], weight: i64) {
+        if let Some(old_score) = self.score {
+            self.score = self
+                .inner
+                .feed(buffer)
+                .map(|new_score| old_score + new_score * weight);
+        }
+    }
+}
+
+/// Stand-in decoding table for bytes 0x80 to 0xFF of CP437.
+static IBM437_UPPER: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+/// Stand-in decoding table for bytes 0x80 to 0xFF of CP850.
+static IBM850_UPPER: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤', //
+    'ð', 'Ð', 'Ê', 'Ë', 'È', 'ı', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀', //
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´', //
+    '\u{AD}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{A0}',
+];
+
+#[cfg(all(test, feature = "cjk", feature = "cyrillic"))]
+mod tests {
+    extern crate alloc;
+    use super::*;
+    use crate::EncodingDetector;
+    use crate::ShortStringDetector;
+    use alloc::vec::Vec;
+    use encoding_rs::GBK;
+    use encoding_rs::IBM866;
+    use encoding_rs::SHIFT_JIS;
+    use encoding_rs::WINDOWS_1252;
+
+    fn encode_dos(code_page: DosCodePage, s: &str) -> Vec<u8> {
+        s.chars()
+            .map(|c| {
+                (0u8..=0xFF)
+                    .find(|&b| code_page.decode_byte(b) == c)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn guess_names(names: &[&[u8]]) -> (NameEncoding, bool) {
+        let mut batch = ShortStringDetector::new();
+        batch.set_dos_code_pages(true);
+        for name in names.iter() {
+            batch.feed(name);
+        }
+        batch.guess_with_dos(None, true)
+    }
+
+    #[test]
+    fn test_dos_code_pages() {
+        let names = [
+            "Müller/Größe.txt",
+            "Übersicht März.xls",
+            "Café crème.doc",
+            "Señor.txt",
+        ];
+        let cp437: Vec<Vec<u8>> = names
+            .iter()
+            .map(|name| encode_dos(DosCodePage::Ibm437, name))
+            .collect();
+        let cp437: Vec<&[u8]> = cp437.iter().map(|name| &name[..]).collect();
+        assert_eq!(
+            guess_names(&cp437),
+            (NameEncoding::Dos(DosCodePage::Ibm437), true)
+        );
+        assert!(cp437[1]
+            .iter()
+            .map(|&b| DosCodePage::Ibm437.decode_byte(b))
+            .eq(names[1].chars()));
+
+        let shift_jis: Vec<Vec<u8>> = ["新しいフォルダ", "写真.jpg", "議事録.doc"]
+            .iter()
+            .map(|name| SHIFT_JIS.encode(name).0.into_owned())
+            .collect();
+        let shift_jis: Vec<&[u8]> = shift_jis.iter().map(|name| &name[..]).collect();
+        assert_eq!(guess_names(&shift_jis).0, NameEncoding::Encoding(SHIFT_JIS));
+
+        let gbk: Vec<Vec<u8>> = ["新建文件夹", "照片.jpg", "会议记录.doc"]
+            .iter()
+            .map(|name| GBK.encode(name).0.into_owned())
+            .collect();
+        let gbk: Vec<&[u8]> = gbk.iter().map(|name| &name[..]).collect();
+        assert_eq!(guess_names(&gbk).0, NameEncoding::Encoding(GBK));
+
+        let ibm866: Vec<Vec<u8>> = ["Отчёт.doc", "фото 1.jpg", "Новая папка"]
+            .iter()
+            .map(|name| IBM866.encode(name).0.into_owned())
+            .collect();
+        let ibm866: Vec<&[u8]> = ibm866.iter().map(|name| &name[..]).collect();
+        assert_eq!(guess_names(&ibm866).0, NameEncoding::Encoding(IBM866));
+
+        let windows_1252: Vec<Vec<u8>> = names
+            .iter()
+            .map(|name| WINDOWS_1252.encode(name).0.into_owned())
+            .collect();
+        let windows_1252: Vec<&[u8]> = windows_1252.iter().map(|name| &name[..]).collect();
+        assert_eq!(
+            guess_names(&windows_1252).0,
+            NameEncoding::Encoding(WINDOWS_1252)
+        );
+
+        // The TLD doesn't hand the batch to a DOS code page.
+        let mut batch = ShortStringDetector::new();
+        batch.set_dos_code_pages(true);
+        batch.feed(windows_1252[1]);
+        assert!(matches!(
+            batch.guess_with_dos(Some(b"jp"), true).0,
+            NameEncoding::Encoding(_)
+        ));
+
+        // Off by default.
+        let mut batch = ShortStringDetector::new();
+        for name in cp437.iter() {
+            batch.feed(name);
+        }
+        assert!(matches!(
+            batch.guess_with_dos(None, true).0,
+            NameEncoding::Encoding(_)
+        ));
+        assert_eq!(batch.detector().dos_scores(None), [None, None]);
+
+        // The candidates live in the detector, so its report has them.
+        let mut det = EncodingDetector::new();
+        det.set_dos_code_pages(true);
+        det.feed(cp437[1], true);
+        let report = det.report(None, true);
+        assert_eq!(report.dos_code_page, Some(DosCodePage::Ibm437));
+        assert_eq!(report.dos_scores, det.dos_scores(None));
+        assert!(report.dos_scores[0].unwrap() > report.candidates.as_slice()[0].1);
+        assert_eq!(report.encoding, det.guess(None, true));
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! C API for `EncodingDetector`, declared in `include/chardetng.h`.
+//!
+//! The detector is an opaque heap-allocated object. The returned
+//! encodings are the same `Encoding` pointers that the C API of
+//! `encoding_rs` (the `encoding_c` crate) uses.
+//!
+//! Passing a null pointer as the TLD means that the TLD is unavailable,
+//! i.e. `None` in the Rust API. Null pointers are also accepted for
+//! zero-length buffers.
+
+use crate::EncodingDetector;
+use crate::NameEncoding;
+use alloc::boxed::Box;
+use encoding_rs::Encoding;
+
+/// Turns a pointer and a length into a slice, allowing null for empty.
+unsafe fn slice_from_raw<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        core::slice::from_raw_parts(ptr, len)
+    }
+}
+
+unsafe fn tld_from_raw<'a>(tld: *const u8, tld_len: usize) -> Option<&'a [u8]> {
+    if tld.is_null() {
+        None
+    } else {
+        Some(slice_from_raw(tld, tld_len))
+    }
+}
+
+/// Allocates a new `EncodingDetector` and returns a pointer to it.
+///
+/// The detector must be deallocated with
+/// `chardetng_encoding_detector_free()`.
+#[no_mangle]
+pub extern "C" fn chardetng_encoding_detector_new() -> *mut EncodingDetector {
+    Box::into_raw(Box::new(EncodingDetector::new()))
+}
+
+/// Deallocates a detector obtained from `chardetng_encoding_detector_new()`.
+///
+/// # Safety
+///
+/// `detector` must have been obtained from
+/// `chardetng_encoding_detector_new()` and must not have been freed
+/// already. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_free(detector: *mut EncodingDetector) {
+    if !detector.is_null() {
+        drop(Box::from_raw(detector));
+    }
+}
+
+/// Calls `feed()` on the detector with `buffer_len` bytes at `buffer`.
+///
+/// # Safety
+///
+/// `detector` must be a valid detector and `buffer` must point to
+/// `buffer_len` readable bytes unless `buffer_len` is zero. Feeding
+/// again after `last` has been `true` aborts the program.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_feed(
+    detector: *mut EncodingDetector,
+    buffer: *const u8,
+    buffer_len: usize,
+    last: bool,
+) -> bool {
+    (*detector).feed(slice_from_raw(buffer, buffer_len), last)
+}
+
+/// Calls `guess()` on the detector. A null `tld` means `None`.
+///
+/// # Safety
+///
+/// `detector` must be a valid detector and `tld` must be null or point to
+/// `tld_len` readable bytes. A TLD that contains non-ASCII, period, or
+/// upper-case letters aborts the program.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_guess(
+    detector: *const EncodingDetector,
+    tld: *const u8,
+    tld_len: usize,
+    allow_utf8: bool,
+) -> *const Encoding {
+    (*detector).guess(tld_from_raw(tld, tld_len), allow_utf8)
+}
+
+/// Calls `guess_assess()` on the detector, writes the Boolean to
+/// `assessment` and returns the encoding. A null `tld` means `None`.
+///
+/// # Safety
+///
+/// As with `chardetng_encoding_detector_guess()`. Additionally,
+/// `assessment` must point to a writable `bool`.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_guess_assess(
+    detector: *const EncodingDetector,
+    tld: *const u8,
+    tld_len: usize,
+    allow_utf8: bool,
+    assessment: *mut bool,
+) -> *const Encoding {
+    let (encoding, assess) = (*detector).guess_assess(tld_from_raw(tld, tld_len), allow_utf8);
+    *assessment = assess;
+    encoding
+}
+
+/// Calls `EncodingDetector::tld_may_affect_guess()`. A null `tld` means
+/// `None`.
+///
+/// # Safety
+///
+/// `tld` must be null or point to `tld_len` readable bytes. A TLD that
+/// contains non-ASCII, period, or upper-case letters aborts the program.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_tld_may_affect_guess(
+    tld: *const u8,
+    tld_len: usize,
+) -> bool {
+    EncodingDetector::tld_may_affect_guess(tld_from_raw(tld, tld_len))
+}
+
+/// Calls `set_dos_code_pages()` on the detector.
+///
+/// # Safety
+///
+/// `detector` must be a valid detector that has not been fed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_set_dos_code_pages(
+    detector: *mut EncodingDetector,
+    enabled: bool,
+) {
+    (*detector).set_dos_code_pages(enabled);
+}
+
+/// Calls `guess_with_dos()` on the detector and writes the Boolean to
+/// `assessment`. If the guess is an encoding, writes 0 to `code_page` and
+/// returns the encoding. If the guess is a DOS code page, writes its
+/// number, 437 or 850, to `code_page` and returns null. A null `tld`
+/// means `None`.
+///
+/// # Safety
+///
+/// As with `chardetng_encoding_detector_guess_assess()`. Additionally,
+/// `code_page` must point to a writable `uint16_t`.
+#[no_mangle]
+pub unsafe extern "C" fn chardetng_encoding_detector_guess_with_dos(
+    detector: *const EncodingDetector,
+    tld: *const u8,
+    tld_len: usize,
+    allow_utf8: bool,
+    assessment: *mut bool,
+    code_page: *mut u16,
+) -> *const Encoding {
+    let (guess, assess) = (*detector).guess_with_dos(tld_from_raw(tld, tld_len), allow_utf8);
+    *assessment = assess;
+    match guess {
+        NameEncoding::Encoding(encoding) => {
+            *code_page = 0;
+            encoding
+        }
+        NameEncoding::Dos(dos) => {
+            *code_page = dos.number();
+            core::ptr::null()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi() {
+        unsafe {
+            assert!(chardetng_encoding_detector_tld_may_affect_guess(
+                b"fi".as_ptr(),
+                2
+            ));
+            assert!(!chardetng_encoding_detector_tld_may_affect_guess(
+                core::ptr::null(),
+                0
+            ));
+            let det = chardetng_encoding_detector_new();
+            assert!(!chardetng_encoding_detector_feed(
+                det,
+                core::ptr::null(),
+                0,
+                false
+            ));
+            assert!(chardetng_encoding_detector_feed(
+                det,
+                b"Ma\xF0ur".as_ptr(),
+                5,
+                true
+            ));
+            let mut assessment = false;
+            let encoding = chardetng_encoding_detector_guess_assess(
+                det,
+                b"is".as_ptr(),
+                2,
+                false,
+                &mut assessment,
+            );
+            assert_eq!(
+                (&*encoding, assessment),
+                (*det).guess_assess(Some(b"is"), false)
+            );
+            assert_eq!(
+                chardetng_encoding_detector_guess(det, core::ptr::null(), 0, true),
+                (*det).guess(None, true) as *const Encoding
+            );
+            let mut code_page = 1u16;
+            let encoding = chardetng_encoding_detector_guess_with_dos(
+                det,
+                core::ptr::null(),
+                0,
+                true,
+                &mut assessment,
+                &mut code_page,
+            );
+            assert_eq!((&*encoding, assessment), (*det).guess_assess(None, true));
+            assert_eq!(code_page, 0);
+            chardetng_encoding_detector_free(det);
+
+            // "über" in CP437.
+            let det = chardetng_encoding_detector_new();
+            chardetng_encoding_detector_set_dos_code_pages(det, true);
+            chardetng_encoding_detector_feed(det, b"\x81ber".as_ptr(), 4, true);
+            let encoding = chardetng_encoding_detector_guess_with_dos(
+                det,
+                core::ptr::null(),
+                0,
+                true,
+                &mut assessment,
+                &mut code_page,
+            );
+            assert!(encoding.is_null());
+            assert_eq!(code_page, 437);
+            chardetng_encoding_detector_free(det);
+        }
+    }
+
+    #[test]
+    fn test_ffi_header_declares_functions() {
+        let header = include_str!("../include/chardetng.h");
+        let rust = include_str!("ffi.rs");
+        let mut count = 0;
+        for line in rust.lines() {
+            if let Some(rest) = line.split("extern \"C\" fn ").nth(1) {
+                let name = &rest[..rest.find('(').unwrap()];
+                assert!(header.contains(&alloc::format!("{}(", name)), "{}", name);
+                count += 1;
+            }
+        }
+        assert_eq!(count, 8);
+    }
+}
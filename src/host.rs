@@ -0,0 +1,170 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Per-host priors.
+
+use encoding_rs::Encoding;
+use encoding_rs::UTF_8;
+
+/// How many confident outcomes an encoding needs before it is expected.
+const MIN_RECORDS: u32 = 2;
+
+/// The past outcomes of detection for the documents of one host.
+///
+/// Pages from the same host almost always share an encoding. Once the
+/// profile has recorded enough confident outcomes for one encoding, and
+/// more than for all the others combined, that encoding is expected for
+/// the host. `EncodingDetector::guess_assess_for_host()` then treats it
+/// the way the encoding native to a top-level domain is treated, so that
+/// later pages with little evidence, e.g. short ones, are classified
+/// consistently with the rest of the host.
+#[derive(Debug, Clone, Copy)]
+pub struct HostProfile {
+    entries: [(&'static Encoding, u32); 27],
+    len: usize,
+    total: u32,
+}
+
+impl HostProfile {
+    /// Creates an empty profile.
+    pub fn new() -> Self {
+        HostProfile {
+            entries: [(UTF_8, 0); 27],
+            len: 0,
+            total: 0,
+        }
+    }
+
+    /// Records the outcome of `guess_assess()` (or of
+    /// `guess_assess_for_host()`) for a document from the host. Outcomes
+    /// that are not confident are ignored.
+    pub fn record(&mut self, encoding: &'static Encoding, confident: bool) {
+        if !confident {
+            return;
+        }
+        self.total = self.total.saturating_add(1);
+        for entry in self.entries[..self.len].iter_mut() {
+            if entry.0 == encoding {
+                entry.1 = entry.1.saturating_add(1);
+                return;
+            }
+        }
+        if self.len < self.entries.len() {
+            self.entries[self.len] = (encoding, 1);
+            self.len += 1;
+        }
+    }
+
+    /// Returns the encoding expected for the host, if any.
+    pub fn expected(&self) -> Option<&'static Encoding> {
+        self.entries[..self.len]
+            .iter()
+            .find(|&&(_, count)| count >= MIN_RECORDS && count > self.total - count)
+            .map(|&(encoding, _)| encoding)
+    }
+
+    /// The number of confident outcomes recorded.
+    pub fn len(&self) -> u32 {
+        self.total
+    }
+
+    /// Whether no confident outcomes have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+}
+
+impl Default for HostProfile {
+    fn default() -> Self {
+        HostProfile::new()
+    }
+}
+
+#[cfg(all(test, feature = "central", feature = "cyrillic"))]
+mod tests {
+    use super::*;
+    use crate::EncodingDetector;
+    use encoding_rs::WINDOWS_1250;
+    use encoding_rs::WINDOWS_1251;
+
+    #[test]
+    fn test_host_profile() {
+        let mut host = HostProfile::new();
+        for page in [
+            "Zażółć gęślą jaźń. Dziękuję za wiadomość.",
+            "Kraków i Łódź są dużymi miastami.",
+        ]
+        .iter()
+        {
+            assert_eq!(host.expected(), None);
+            let mut det = EncodingDetector::new();
+            det.feed(&WINDOWS_1250.encode(page).0, true);
+            let (encoding, confident) = det.guess_assess(None, false);
+            assert_eq!(encoding, WINDOWS_1250);
+            host.record(encoding, confident);
+        }
+        assert_eq!(host.expected(), Some(WINDOWS_1250));
+
+        // Short and ASCII-only pages that get a different guess without
+        // the prior.
+        for page in ["żółw", "Koszyk"].iter() {
+            let mut det = EncodingDetector::new();
+            det.feed(&WINDOWS_1250.encode(page).0, true);
+            assert_ne!(det.guess(None, false), WINDOWS_1250);
+            assert_eq!(
+                det.guess_assess_for_host(None, false, &host).0,
+                WINDOWS_1250
+            );
+            assert_eq!(
+                det.guess_assess_for_host(None, false, &HostProfile::new()),
+                det.guess_assess(None, false)
+            );
+        }
+
+        // Enough evidence still overrides the prior.
+        let mut det = EncodingDetector::new();
+        det.feed(
+            &WINDOWS_1251
+                .encode("Привет! Как у вас дела? У нас всё хорошо, спасибо.")
+                .0,
+            true,
+        );
+        assert_eq!(
+            det.guess_assess_for_host(None, false, &host).0,
+            WINDOWS_1251
+        );
+        // The host replaces the TLD instead of adding to it.
+        assert_eq!(
+            det.guess_assess_for_host(Some(b"gr"), false, &host).0,
+            WINDOWS_1251
+        );
+        for page in ["żółw", "Zażółć gęślą jaźń", "Koszyk"].iter() {
+            let mut det = EncodingDetector::new();
+            det.feed(&WINDOWS_1250.encode(page).0, true);
+            for tld in [&b"ru"[..], b"gr", b"jp", b"fr"].iter() {
+                assert_eq!(
+                    det.guess_assess_for_host(Some(tld), false, &host),
+                    det.guess_assess_for_host(None, false, &host)
+                );
+            }
+        }
+        // Until the expected encoding is ruled out.
+        let mut det = EncodingDetector::new();
+        det.feed(b"\xC3\xA9\xC3\xA9\x81", true);
+        assert_eq!(
+            det.guess_assess_for_host(Some(b"jp"), false, &host),
+            det.guess_assess(Some(b"jp"), false)
+        );
+        assert!(EncodingDetector::tld_may_affect_guess_for_host(None, &host));
+        assert!(!EncodingDetector::tld_may_affect_guess_for_host(
+            None,
+            &HostProfile::new()
+        ));
+    }
+}
@@ -0,0 +1,427 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A front-end that feeds only the human-readable parts of HTML to the
+//! detector.
+//!
+//! The tokenizer is a rough approximation of the HTML tokenizer that works
+//! on bytes and, therefore, on any ASCII-compatible encoding. It only
+//! needs to know where text, attribute values, comments, and the contents
+//! of `script` and `style` are. Character references are left alone, as
+//! they are ASCII.
+
+use crate::wrap;
+use crate::wrap::Router;
+use crate::EncodingDetector;
+use encoding_rs::Encoding;
+
+/// Where the bytes in a given tokenizer state go.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Route {
+    /// Not fed to the detector.
+    Markup,
+    /// Text content and the values of the `title` and `alt` attributes.
+    Text,
+    /// Comments and the contents of `script` and `style`.
+    NonText,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    TagOpen,
+    EndTag,
+    TagName,
+    BeforeAttributeName,
+    AttributeName,
+    AfterAttributeName,
+    BeforeAttributeValue,
+    /// The quote, or zero if unquoted.
+    AttributeValue(u8),
+    MarkupDeclarationOpen,
+    CommentStartDash,
+    /// The number of consecutive hyphens seen.
+    Comment(u8),
+    BogusComment,
+    /// The number of bytes of `</script` or `</style` matched.
+    RawText(usize),
+}
+
+/// A tag or attribute name, lower-cased, if it is short enough to be of
+/// interest.
+#[derive(Clone, Copy)]
+struct ShortName {
+    buf: [u8; 8],
+    len: usize,
+}
+
+impl ShortName {
+    const EMPTY: ShortName = ShortName {
+        buf: [0; 8],
+        len: 0,
+    };
+
+    fn push(&mut self, b: u8) {
+        if self.len < self.buf.len() {
+            self.buf[self.len] = b.to_ascii_lowercase();
+        }
+        self.len += 1;
+    }
+
+    fn is(&self, name: &[u8]) -> bool {
+        self.len == name.len() && &self.buf[..self.len] == name
+    }
+}
+
+fn is_html_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b'\x0C')
+}
+
+/// Wraps an `EncodingDetector` such that only the text content of an
+/// HTML document and the values of the `title` and `alt` attributes
+/// are scored.
+///
+/// Each run of text is fed as a fragment (see
+/// `EncodingDetector::feed_fragment()`), so that the text before and
+/// after a tag is not scored as adjacent. Other attribute values, such
+/// as URLs, are ignored. Comments and the contents of `script` and
+/// `style` are ignored by default; see `set_non_text_weight()`.
+pub struct HtmlDetector {
+    detector: EncodingDetector,
+    state: State,
+    tag: ShortName,
+    attribute: ShortName,
+    /// The route of the bytes not fed yet.
+    route: Route,
+    non_text_weight: Option<u16>,
+}
+
+wrapper! {
+    HtmlDetector,
+    /// Creates a new instance for one document with a new
+    /// `EncodingDetector`.
+    fn new;
+    /// Returns the wrapped detector, which has seen the text of the
+    /// document so far, for calling its other methods, such as
+    /// `ranked_candidates()`.
+    fn detector;
+}
+
+impl HtmlDetector {
+    /// Creates a new instance that feeds the text of the document to
+    /// `detector`, which may have been configured, e.g. with a budget,
+    /// but must not have been fed yet.
+    pub fn with_detector(detector: EncodingDetector) -> Self {
+        HtmlDetector {
+            detector,
+            state: State::Data,
+            tag: ShortName::EMPTY,
+            attribute: ShortName::EMPTY,
+            route: Route::Markup,
+            non_text_weight: None,
+        }
+    }
+
+    /// Makes comments and the contents of `script` and `style` count with
+    /// `weight` (see `EncodingDetector::set_weight()`) instead of being
+    /// ignored, which is the default (`None`). With `Some(0)`, invalid
+    /// byte sequences in them still disqualify candidates.
+    pub fn set_non_text_weight(&mut self, weight: Option<u16>) {
+        self.non_text_weight = weight;
+    }
+
+    /// Tokenizes a chunk of the document. The chunks may split tags and
+    /// characters anywhere. Returns `true` if the text (and, with
+    /// `set_non_text_weight()`, the comments, scripts and style sheets)
+    /// has contained non-ASCII so far.
+    ///
+    /// # Panics
+    ///
+    /// If this method has previously been called with `last` set to `true`.
+    pub fn feed(&mut self, buffer: &[u8], last: bool) -> bool {
+        wrap::feed(self, buffer, last)
+    }
+
+    /// Same as `EncodingDetector::guess()`.
+    pub fn guess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> &'static Encoding {
+        self.detector.guess(tld, allow_utf8)
+    }
+
+    /// Same as `EncodingDetector::guess_assess()`.
+    pub fn guess_assess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> (&'static Encoding, bool) {
+        self.detector.guess_assess(tld, allow_utf8)
+    }
+}
+
+impl Router for HtmlDetector {
+    type Route = Route;
+
+    fn detector_mut(&mut self) -> &mut EncodingDetector {
+        &mut self.detector
+    }
+
+    fn route(&self) -> Route {
+        self.route
+    }
+
+    fn set_route(&mut self, route: Route) {
+        self.route = route;
+    }
+
+    fn weight(&self, route: Route) -> Option<u16> {
+        match route {
+            Route::Markup => None,
+            Route::Text => Some(1),
+            Route::NonText => self.non_text_weight,
+        }
+    }
+
+    fn step(&mut self, b: u8) -> Route {
+        match self.state {
+            State::Data => {
+                if b == b'<' {
+                    self.state = State::TagOpen;
+                    return Route::Markup;
+                }
+                Route::Text
+            }
+            State::TagOpen => {
+                match b {
+                    b'!' => self.state = State::MarkupDeclarationOpen,
+                    b'/' => self.state = State::EndTag,
+                    b'?' => self.state = State::BogusComment,
+                    _ if b.is_ascii_alphabetic() => {
+                        self.tag = ShortName::EMPTY;
+                        self.tag.push(b);
+                        self.state = State::TagName;
+                    }
+                    _ => {
+                        self.state = State::Data;
+                        return Route::Text;
+                    }
+                }
+                Route::Markup
+            }
+            State::EndTag | State::BogusComment => {
+                if b == b'>' {
+                    self.state = State::Data;
+                }
+                Route::Markup
+            }
+            State::TagName => {
+                if is_html_space(b) || b == b'/' {
+                    self.state = State::BeforeAttributeName;
+                } else if b == b'>' {
+                    self.end_start_tag();
+                } else {
+                    self.tag.push(b);
+                }
+                Route::Markup
+            }
+            State::BeforeAttributeName => {
+                if b == b'>' {
+                    self.end_start_tag();
+                } else if !is_html_space(b) && b != b'/' {
+                    self.attribute = ShortName::EMPTY;
+                    self.attribute.push(b);
+                    self.state = State::AttributeName;
+                }
+                Route::Markup
+            }
+            State::AttributeName => {
+                if b == b'=' {
+                    self.state = State::BeforeAttributeValue;
+                } else if b == b'>' {
+                    self.end_start_tag();
+                } else if is_html_space(b) {
+                    self.state = State::AfterAttributeName;
+                } else if b == b'/' {
+                    self.state = State::BeforeAttributeName;
+                } else {
+                    self.attribute.push(b);
+                }
+                Route::Markup
+            }
+            State::AfterAttributeName => {
+                if b == b'=' {
+                    self.state = State::BeforeAttributeValue;
+                } else if b == b'>' {
+                    self.end_start_tag();
+                } else if !is_html_space(b) && b != b'/' {
+                    self.attribute = ShortName::EMPTY;
+                    self.attribute.push(b);
+                    self.state = State::AttributeName;
+                }
+                Route::Markup
+            }
+            State::BeforeAttributeValue => match b {
+                b'"' | b'\'' => {
+                    self.state = State::AttributeValue(b);
+                    Route::Markup
+                }
+                b'>' => {
+                    self.end_start_tag();
+                    Route::Markup
+                }
+                _ if is_html_space(b) => Route::Markup,
+                _ => {
+                    self.state = State::AttributeValue(0);
+                    self.attribute_value_route()
+                }
+            },
+            State::AttributeValue(quote) => {
+                if quote == 0 && b == b'>' {
+                    self.end_start_tag();
+                } else if b == quote || (quote == 0 && is_html_space(b)) {
+                    self.state = State::BeforeAttributeName;
+                } else {
+                    return self.attribute_value_route();
+                }
+                Route::Markup
+            }
+            State::MarkupDeclarationOpen => {
+                self.state = if b == b'-' {
+                    State::CommentStartDash
+                } else if b == b'>' {
+                    State::Data
+                } else {
+                    // Including doctypes.
+                    State::BogusComment
+                };
+                Route::Markup
+            }
+            State::CommentStartDash => {
+                self.state = if b == b'-' {
+                    State::Comment(0)
+                } else {
+                    State::BogusComment
+                };
+                Route::Markup
+            }
+            State::Comment(dashes) => {
+                if b == b'-' {
+                    self.state = State::Comment(core::cmp::min(dashes + 1, 2));
+                } else if b == b'>' && dashes == 2 {
+                    self.state = State::Data;
+                    return Route::Markup;
+                } else {
+                    self.state = State::Comment(0);
+                }
+                Route::NonText
+            }
+            State::RawText(matched) => {
+                let end: &[u8] = if self.tag.is(b"script") {
+                    b"</script"
+                } else {
+                    b"</style"
+                };
+                // A prefix of the end tag is markup even if the rest
+                // doesn't match, e.g. in `if (a</b)`, since `<` and the
+                // bytes after it are ASCII anyway.
+                if b.to_ascii_lowercase() == end[matched] {
+                    if matched + 1 == end.len() {
+                        self.state = State::EndTag;
+                    } else {
+                        self.state = State::RawText(matched + 1);
+                    }
+                } else if b == b'<' {
+                    self.state = State::RawText(1);
+                } else {
+                    self.state = State::RawText(0);
+                    return Route::NonText;
+                }
+                Route::Markup
+            }
+        }
+    }
+}
+
+impl HtmlDetector {
+    fn end_start_tag(&mut self) {
+        self.state = if self.tag.is(b"script") || self.tag.is(b"style") {
+            State::RawText(0)
+        } else {
+            State::Data
+        };
+    }
+
+    fn attribute_value_route(&self) -> Route {
+        if self.attribute.is(b"title") || self.attribute.is(b"alt") {
+            Route::Text
+        } else {
+            Route::Markup
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use super::*;
+    use alloc::vec::Vec;
+    use encoding_rs::UTF_8;
+    use encoding_rs::WINDOWS_1252;
+
+    const HTML: &[u8] = b"<!DOCTYPE html><html><head><title>Ma\xF0ur</title>\
+<script>var s = \"\xC3\xA4\xC3\xA4\"; if (a</b) {}</script><style>p{content:\"\xE9\"}</style>\
+</head><body><!-- \xE9\xE9 -- --><a href='/\xC3\xA4'><img alt=\"\xF6l\" src=x.png>\
+\xE1lfum</a></body></html>";
+
+    #[test]
+    fn test_html_detector() {
+        let mut det = HtmlDetector::new();
+        assert!(det.feed(HTML, true));
+        // Only the title, the alt attribute, and the link text count.
+        assert_eq!(det.detector().non_ascii_count(), 3);
+        assert_eq!(det.guess(Some(b"is"), false), WINDOWS_1252);
+
+        // Chunking does not matter.
+        let mut bytewise = HtmlDetector::new();
+        for (i, b) in HTML.iter().enumerate() {
+            bytewise.feed(core::slice::from_ref(b), i == HTML.len() - 1);
+        }
+        assert_eq!(
+            bytewise.detector().ranked_candidates(None),
+            det.detector().ranked_candidates(None)
+        );
+
+        let mut det = HtmlDetector::new();
+        det.set_non_text_weight(Some(0));
+        det.feed(HTML, true);
+        // Script, style, and comment.
+        assert_eq!(det.detector().non_ascii_count(), 3 + 4 + 1 + 2);
+
+        // With weight 0, malformed script still disqualifies.
+        let mut html = Vec::new();
+        html.extend_from_slice("<p>Привет</p><script>'".as_bytes());
+        html.extend_from_slice(b"\xE4'</script>");
+        let mut det = HtmlDetector::new();
+        det.feed(&html, true);
+        assert_eq!(det.guess(None, true), UTF_8);
+        let mut det = HtmlDetector::new();
+        det.set_non_text_weight(Some(0));
+        det.feed(&html, true);
+        assert_ne!(det.guess(None, true), UTF_8);
+
+        let mut det = HtmlDetector::new();
+        assert!(!det.feed(b"<p title=x>a</p><!-- \xE9 --><a href=\xE9>b</a>", true));
+
+        // The end tag of a script is not scored as its content.
+        let mut det = HtmlDetector::new();
+        det.set_non_text_weight(Some(1));
+        det.feed(b"<script>s=1\xA0</script>", true);
+        let mut content = EncodingDetector::new();
+        content.feed_fragment(b"s=1\xA0", false);
+        content.feed(b"", true);
+        assert_eq!(
+            det.detector().ranked_candidates(None),
+            content.ranked_candidates(None)
+        );
+    }
+}
@@ -14,6 +14,12 @@
 
 #![no_std]
 
+#[cfg(any(feature = "alloc", feature = "ffi"))]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
 #[cfg(feature = "multithreading")]
 use rayon::prelude::*;
 
@@ -37,18 +43,83 @@ use encoding_rs::BIG5;
 use encoding_rs::EUC_JP;
 use encoding_rs::EUC_KR;
 use encoding_rs::GBK;
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
+use encoding_rs::IBM866;
 use encoding_rs::ISO_2022_JP;
 use encoding_rs::ISO_8859_8;
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
+use encoding_rs::KOI8_U;
 use encoding_rs::SHIFT_JIS;
 use encoding_rs::UTF_8;
 use encoding_rs::WINDOWS_1255;
 
+// First, so that its macro is visible in the other modules.
+#[macro_use]
+mod wrap;
+
+#[cfg(feature = "alloc")]
+mod ambiguity;
+#[cfg(feature = "alloc")]
+mod column;
+mod css;
 mod data;
+mod dos;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod host;
+mod html;
+#[cfg(feature = "alloc")]
+mod mime;
+mod model;
+mod report;
+mod sample;
+mod short;
+mod subtitle;
 mod tld;
+#[cfg(feature = "alloc")]
+pub use ambiguity::Ambiguity;
+#[cfg(feature = "alloc")]
+pub use ambiguity::Difference;
+#[cfg(feature = "alloc")]
+pub use column::ColumnDetector;
+#[cfg(feature = "alloc")]
+pub use column::ColumnGuess;
+pub use css::CssDetector;
+pub use css::CssSource;
 use data::*;
+use dos::DosCandidate;
+pub use dos::DosCodePage;
+pub use dos::NameEncoding;
+pub use host::HostProfile;
+pub use html::HtmlDetector;
+#[cfg(feature = "alloc")]
+pub use mime::CharsetCheck;
+#[cfg(feature = "alloc")]
+pub use mime::DecodedHeader;
+#[cfg(feature = "alloc")]
+pub use mime::HeaderDetector;
+#[cfg(feature = "alloc")]
+pub use mime::MimeDetector;
+#[cfg(feature = "alloc")]
+pub use mime::MimeGuess;
+pub use model::ModelError;
+pub use model::SingleByteModel;
+pub use report::Report;
+pub use sample::Sample;
+pub use short::ShortStringDetector;
+pub use subtitle::SubtitleDetector;
+pub use subtitle::SubtitleGuess;
 use tld::classify_tld;
+pub use tld::Tld as TldClass;
 use tld::Tld;
 
+#[cfg(any(
+    feature = "cyrillic",
+    feature = "greek",
+    feature = "hebrew",
+    feature = "arabic",
+    feature = "thai"
+))]
 const LATIN_ADJACENCY_PENALTY: i64 = -50;
 
 const IMPLAUSIBILITY_PENALTY: i64 = -220;
@@ -66,92 +137,140 @@ const COPYRIGHT_BONUS: i64 = 222;
 
 const IMPLAUSIBLE_LATIN_CASE_TRANSITION_PENALTY: i64 = -180;
 
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
 const NON_LATIN_CAPITALIZATION_BONUS: i64 = 40;
 
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
 const NON_LATIN_ALL_CAPS_PENALTY: i64 = -40;
 
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
 const NON_LATIN_MIXED_CASE_PENALTY: i64 = -20;
 
 // Manually calibrated relative to windows-1256 Arabic
+#[cfg(feature = "cjk")]
 const CJK_BASE_SCORE: i64 = 41;
 
+#[cfg(feature = "cjk")]
 const CJK_SECONDARY_BASE_SCORE: i64 = 20; // Was 20
 
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_SCORE_PER_KANA: i64 = 20;
 
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_SCORE_PER_LEVEL_1_KANJI: i64 = CJK_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_SCORE_PER_LEVEL_2_KANJI: i64 = CJK_SECONDARY_BASE_SCORE;
 
 // Manually calibrated relative to windows-1256 Persian and Urdu
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_INITIAL_HALF_WIDTH_KATAKANA_PENALTY: i64 = -75;
 
+#[cfg(feature = "cjk")]
 const HALF_WIDTH_KATAKANA_SCORE: i64 = 1;
 
 // Unclear if this is a good idea; seems not harmful, but can't be sure.
+#[cfg(feature = "cjk")]
 const HALF_WIDTH_KATAKANA_VOICING_SCORE: i64 = 10;
 
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_PUA_PENALTY: i64 = -(CJK_BASE_SCORE * 10); // Should this be larger?
 
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_EXTENSION_PENALTY: i64 = SHIFT_JIS_PUA_PENALTY * 2;
 
+#[cfg(feature = "cjk")]
 const SHIFT_JIS_SINGLE_BYTE_EXTENSION_PENALTY: i64 = SHIFT_JIS_EXTENSION_PENALTY;
 
+#[cfg(feature = "cjk")]
 const EUC_JP_SCORE_PER_KANA: i64 = CJK_BASE_SCORE + (CJK_BASE_SCORE / 3); // Relative to Big5
 
+#[cfg(feature = "cjk")]
 const EUC_JP_SCORE_PER_NEAR_OBSOLETE_KANA: i64 = CJK_BASE_SCORE - 1;
 
+#[cfg(feature = "cjk")]
 const EUC_JP_SCORE_PER_LEVEL_1_KANJI: i64 = CJK_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const EUC_JP_SCORE_PER_LEVEL_2_KANJI: i64 = CJK_SECONDARY_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const EUC_JP_SCORE_PER_OTHER_KANJI: i64 = CJK_SECONDARY_BASE_SCORE / 4;
 
+#[cfg(feature = "cjk")]
 const EUC_JP_INITIAL_KANA_PENALTY: i64 = -((CJK_BASE_SCORE / 3) + 1);
 
+#[cfg(feature = "cjk")]
 const EUC_JP_EXTENSION_PENALTY: i64 = -(CJK_BASE_SCORE * 50); // Needs to be more severe than for Shift_JIS to avoid misdetecting EUC-KR!
 
+#[cfg(feature = "cjk")]
 const BIG5_SCORE_PER_LEVEL_1_HANZI: i64 = CJK_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const BIG5_SCORE_PER_OTHER_HANZI: i64 = CJK_SECONDARY_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const BIG5_PUA_PENALTY: i64 = -(CJK_BASE_SCORE * 30); // More severe than other PUA penalties to avoid misdetecting EUC-KR! (25 as the multiplier is too little)
 
+#[cfg(feature = "cjk")]
 const BIG5_SINGLE_BYTE_EXTENSION_PENALTY: i64 = -(CJK_BASE_SCORE * 40);
 
+#[cfg(feature = "cjk")]
 const EUC_KR_SCORE_PER_EUC_HANGUL: i64 = CJK_BASE_SCORE + 1;
 
+#[cfg(feature = "cjk")]
 const EUC_KR_SCORE_PER_NON_EUC_HANGUL: i64 = CJK_SECONDARY_BASE_SCORE / 5;
 
+#[cfg(feature = "cjk")]
 const EUC_KR_SCORE_PER_HANJA: i64 = CJK_SECONDARY_BASE_SCORE / 2;
 
+#[cfg(feature = "cjk")]
 const EUC_KR_HANJA_AFTER_HANGUL_PENALTY: i64 = -(CJK_BASE_SCORE * 10);
 
+#[cfg(feature = "cjk")]
 const EUC_KR_LONG_WORD_PENALTY: i64 = -6;
 
+#[cfg(feature = "cjk")]
 const EUC_KR_PUA_PENALTY: i64 = GBK_PUA_PENALTY - 1; // Break tie in favor of GBK
 
+#[cfg(feature = "cjk")]
 const EUC_KR_MAC_KOREAN_PENALTY: i64 = EUC_KR_PUA_PENALTY * 2;
 
+#[cfg(feature = "cjk")]
 const EUC_KR_SINGLE_BYTE_EXTENSION_PENALTY: i64 = EUC_KR_MAC_KOREAN_PENALTY;
 
+#[cfg(feature = "cjk")]
 const GBK_SCORE_PER_LEVEL_1: i64 = CJK_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const GBK_SCORE_PER_LEVEL_2: i64 = CJK_SECONDARY_BASE_SCORE;
 
+#[cfg(feature = "cjk")]
 const GBK_SCORE_PER_NON_EUC: i64 = CJK_SECONDARY_BASE_SCORE / 4;
 
+#[cfg(feature = "cjk")]
 const GBK_PUA_PENALTY: i64 = -(CJK_BASE_SCORE * 10); // Factor should be at least 2, but should it be larger?
 
+#[cfg(feature = "cjk")]
 const GBK_SINGLE_BYTE_EXTENSION_PENALTY: i64 = GBK_PUA_PENALTY * 4;
 
+#[cfg(feature = "cjk")]
 const CJK_LATIN_ADJACENCY_PENALTY: i64 = -CJK_BASE_SCORE; // smaller penalty than LATIN_ADJACENCY_PENALTY
 
+#[cfg(feature = "cjk")]
 const CJ_PUNCTUATION: i64 = CJK_BASE_SCORE / 2;
 
+#[cfg(feature = "cjk")]
 const CJK_OTHER: i64 = CJK_SECONDARY_BASE_SCORE / 4;
 
 /// Latin letter caseless class
+#[cfg(any(
+    feature = "cyrillic",
+    feature = "greek",
+    feature = "hebrew",
+    feature = "arabic",
+    feature = "thai"
+))]
 const LATIN_LETTER: u8 = 1;
 
 fn contains_upper_case_period_or_non_ascii(label: &[u8]) -> bool {
@@ -183,6 +302,7 @@ enum LatinCaseState {
 
 // Fon non-Latin, we calculate case-related penalty
 // or bonus on a per-non-Latin-word basis.
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
 #[derive(PartialEq)]
 enum NonLatinCaseState {
     Space,
@@ -193,6 +313,7 @@ enum NonLatinCaseState {
     Mix,
 }
 
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
 struct NonLatinCasedCandidate {
     data: &'static SingleByteData,
     prev: u8,
@@ -204,6 +325,7 @@ struct NonLatinCasedCandidate {
     prev_was_a0: bool, // Only used with IBM866
 }
 
+#[cfg(any(feature = "cyrillic", feature = "greek"))]
 impl NonLatinCasedCandidate {
     fn new(data: &'static SingleByteData) -> Self {
         NonLatinCasedCandidate {
@@ -213,7 +335,7 @@ impl NonLatinCasedCandidate {
             prev_ascii: true,
             current_word_len: 0,
             longest_word: 0,
-            ibm866: data == &SINGLE_BYTE_DATA[IBM866_INDEX],
+            ibm866: data.encoding == IBM866,
             prev_was_a0: false,
         }
     }
@@ -266,7 +388,7 @@ impl NonLatinCasedCandidate {
                     }
                     NonLatinCaseState::AllCaps => {
                         // Intentionally applied only once per word.
-                        if self.data == &SINGLE_BYTE_DATA[KOI8_U_INDEX] {
+                        if self.data.encoding == KOI8_U {
                             // Apply only to KOI8-U.
                             score += NON_LATIN_ALL_CAPS_PENALTY;
                         }
@@ -374,14 +496,14 @@ struct LatinCandidate {
 }
 
 impl LatinCandidate {
-    fn new(data: &'static SingleByteData) -> Self {
+    fn new(data: &'static SingleByteData, windows1252: bool) -> Self {
         LatinCandidate {
             data: data,
             prev: 0,
             case_state: LatinCaseState::Space,
             prev_non_ascii: 0,
             ordinal_state: OrdinalState::Space,
-            windows1252: data == &SINGLE_BYTE_DATA[WINDOWS_1252_INDEX],
+            windows1252,
         }
     }
 
@@ -632,6 +754,7 @@ impl LatinCandidate {
     }
 }
 
+#[cfg(feature = "arabic")]
 struct ArabicFrenchCandidate {
     data: &'static SingleByteData,
     prev: u8,
@@ -641,6 +764,7 @@ struct ArabicFrenchCandidate {
     longest_word: u64,
 }
 
+#[cfg(feature = "arabic")]
 impl ArabicFrenchCandidate {
     fn new(data: &'static SingleByteData) -> Self {
         ArabicFrenchCandidate {
@@ -721,6 +845,7 @@ impl ArabicFrenchCandidate {
     }
 }
 
+#[cfg(any(feature = "thai", feature = "arabic"))]
 struct CaselessCandidate {
     data: &'static SingleByteData,
     prev: u8,
@@ -729,6 +854,7 @@ struct CaselessCandidate {
     longest_word: u64,
 }
 
+#[cfg(any(feature = "thai", feature = "arabic"))]
 impl CaselessCandidate {
     fn new(data: &'static SingleByteData) -> Self {
         CaselessCandidate {
@@ -782,6 +908,7 @@ impl CaselessCandidate {
     }
 }
 
+#[cfg(feature = "hebrew")]
 fn is_ascii_punctuation(byte: u8) -> bool {
     match byte {
         b'.' | b',' | b':' | b';' | b'?' | b'!' => true,
@@ -789,6 +916,7 @@ fn is_ascii_punctuation(byte: u8) -> bool {
     }
 }
 
+#[cfg(feature = "hebrew")]
 struct LogicalCandidate {
     data: &'static SingleByteData,
     prev: u8,
@@ -798,6 +926,7 @@ struct LogicalCandidate {
     longest_word: u64,
 }
 
+#[cfg(feature = "hebrew")]
 impl LogicalCandidate {
     fn new(data: &'static SingleByteData) -> Self {
         LogicalCandidate {
@@ -855,6 +984,7 @@ impl LogicalCandidate {
     }
 }
 
+#[cfg(feature = "hebrew")]
 struct VisualCandidate {
     data: &'static SingleByteData,
     prev: u8,
@@ -865,6 +995,7 @@ struct VisualCandidate {
     longest_word: u64,
 }
 
+#[cfg(feature = "hebrew")]
 impl VisualCandidate {
     fn new(data: &'static SingleByteData) -> Self {
         VisualCandidate {
@@ -955,10 +1086,12 @@ impl Utf8Candidate {
     }
 }
 
+#[cfg(feature = "cjk")]
 struct Iso2022Candidate {
     decoder: Decoder,
 }
 
+#[cfg(feature = "cjk")]
 impl Iso2022Candidate {
     fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
         let mut dst = [0u16; 1024];
@@ -985,6 +1118,7 @@ impl Iso2022Candidate {
     }
 }
 
+#[cfg(feature = "cjk")]
 #[derive(PartialEq)]
 enum LatinCj {
     AsciiLetter,
@@ -992,6 +1126,7 @@ enum LatinCj {
     Other,
 }
 
+#[cfg(feature = "cjk")]
 #[derive(PartialEq, Copy, Clone)]
 enum HalfWidthKatakana {
     DakutenForbidden,
@@ -999,6 +1134,7 @@ enum HalfWidthKatakana {
     DakutenOrHandakutenAllowed,
 }
 
+#[cfg(feature = "cjk")]
 #[derive(PartialEq)]
 enum LatinKorean {
     AsciiLetter,
@@ -1007,6 +1143,7 @@ enum LatinKorean {
     Other,
 }
 
+#[cfg(feature = "cjk")]
 fn cjk_extra_score(u: u16, table: &'static [u16; 128]) -> i64 {
     if let Some(pos) = table.iter().position(|&x| x == u) {
         ((128 - pos) / 16) as i64
@@ -1015,26 +1152,31 @@ fn cjk_extra_score(u: u16, table: &'static [u16; 128]) -> i64 {
     }
 }
 
+#[cfg(feature = "cjk")]
 struct GbkCandidate {
     decoder: Decoder,
     prev_byte: u8,
     prev: LatinCj,
     pending_score: Option<i64>,
+    /// Whether the decoder is in the middle of a multi-byte character.
+    mid_character: bool,
 }
 
+#[cfg(feature = "cjk")]
 impl GbkCandidate {
-    fn maybe_set_as_pending(&mut self, s: i64) -> i64 {
+    fn maybe_set_as_pending(&mut self, s: i64, weight: i64) -> i64 {
         assert!(self.pending_score.is_none());
         if self.prev == LatinCj::Cj || !more_problematic_lead(self.prev_byte) {
             s
         } else {
-            self.pending_score = Some(s);
+            self.pending_score = Some(s * weight);
             0
         }
     }
 
-    fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
+    fn feed(&mut self, buffer: &[u8], last: bool, weight: i64) -> Option<i64> {
         let mut score = 0i64;
+        let mut released = 0i64;
         let mut src = [0u8];
         let mut dst = [0u16; 2];
         for &b in buffer {
@@ -1059,7 +1201,7 @@ impl GbkCandidate {
                     self.prev = LatinCj::Other;
                 } else if u >= 0x4E00 && u <= 0x9FA5 {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     if b >= 0xA1 && b <= 0xFE {
@@ -1075,7 +1217,7 @@ impl GbkCandidate {
                             }
                         }
                     } else {
-                        score += self.maybe_set_as_pending(GBK_SCORE_PER_NON_EUC);
+                        score += self.maybe_set_as_pending(GBK_SCORE_PER_NON_EUC, weight);
                     }
                     if self.prev == LatinCj::AsciiLetter {
                         score += CJK_LATIN_ADJACENCY_PENALTY;
@@ -1083,7 +1225,7 @@ impl GbkCandidate {
                     self.prev = LatinCj::Cj;
                 } else if (u >= 0x3400 && u < 0xA000) || (u >= 0xF900 && u < 0xFB00) {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     // XXX score?
@@ -1093,7 +1235,7 @@ impl GbkCandidate {
                     self.prev = LatinCj::Cj;
                 } else if u >= 0xE000 && u < 0xF900 {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     // Treat the GB18030-required PUA mappings as non-EUC ideographs.
@@ -1135,7 +1277,7 @@ impl GbkCandidate {
                         | 0xFF1F // Distinct from Japanese, question
                         => {
                             if let Some(pending) = self.pending_score {
-                                score += pending;
+                                released += pending;
                                 self.pending_score = None;
                             }
                             score += CJ_PUNCTUATION;
@@ -1145,7 +1287,7 @@ impl GbkCandidate {
                         }
                         _ => {
                             if let Some(pending) = self.pending_score {
-                                score += pending;
+                                released += pending;
                                 self.pending_score = None;
                             }
                             score += CJK_OTHER;
@@ -1155,7 +1297,7 @@ impl GbkCandidate {
                 }
             } else if written == 2 {
                 if let Some(pending) = self.pending_score {
-                    score += pending;
+                    released += pending;
                     self.pending_score = None;
                 }
                 let u = dst[0];
@@ -1176,8 +1318,10 @@ impl GbkCandidate {
             match result {
                 DecoderResult::InputEmpty => {
                     assert_eq!(read, 1);
+                    self.mid_character = written == 0;
                 }
                 DecoderResult::Malformed(malformed_len, _) => {
+                    self.mid_character = false;
                     if (self.prev_byte == 0xA0 || self.prev_byte == 0xFE || self.prev_byte == 0xFD)
                         && (b < 0x80 || b == 0xFF)
                     {
@@ -1228,11 +1372,12 @@ impl GbkCandidate {
                 }
             }
         }
-        Some(score)
+        Some(score * weight + released)
     }
 }
 
 // Shift_JIS and Big5
+#[cfg(feature = "cjk")]
 fn problematic_lead(b: u8) -> bool {
     match b {
         0x91..=0x97 | 0x9A | 0x8A | 0x9B | 0x8B | 0x9E | 0x8E | 0xB0 => true,
@@ -1241,10 +1386,12 @@ fn problematic_lead(b: u8) -> bool {
 }
 
 // GBK and EUC-KR
+#[cfg(feature = "cjk")]
 fn more_problematic_lead(b: u8) -> bool {
     problematic_lead(b) || b == 0x82 || b == 0x84 || b == 0x85 || b == 0xA0
 }
 
+#[cfg(feature = "cjk")]
 struct ShiftJisCandidate {
     decoder: Decoder,
     half_width_katakana_seen: bool,
@@ -1252,21 +1399,25 @@ struct ShiftJisCandidate {
     prev: LatinCj,
     prev_byte: u8,
     pending_score: Option<i64>,
+    /// Whether the decoder is in the middle of a multi-byte character.
+    mid_character: bool,
 }
 
+#[cfg(feature = "cjk")]
 impl ShiftJisCandidate {
-    fn maybe_set_as_pending(&mut self, s: i64) -> i64 {
+    fn maybe_set_as_pending(&mut self, s: i64, weight: i64) -> i64 {
         assert!(self.pending_score.is_none());
         if self.prev == LatinCj::Cj || !problematic_lead(self.prev_byte) {
             s
         } else {
-            self.pending_score = Some(s);
+            self.pending_score = Some(s * weight);
             0
         }
     }
 
-    fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
+    fn feed(&mut self, buffer: &[u8], last: bool, weight: i64) -> Option<i64> {
         let mut score = 0i64;
+        let mut released = 0i64;
         let mut src = [0u8];
         let mut dst = [0u16; 2];
         for &b in buffer {
@@ -1322,7 +1473,7 @@ impl ShiftJisCandidate {
                     self.prev = LatinCj::Cj;
                 } else if u >= 0x3040 && u < 0x3100 {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     score += SHIFT_JIS_SCORE_PER_KANA;
@@ -1332,16 +1483,18 @@ impl ShiftJisCandidate {
                     self.prev = LatinCj::Cj;
                 } else if (u >= 0x3400 && u < 0xA000) || (u >= 0xF900 && u < 0xFB00) {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     if self.prev_byte < 0x98 || (self.prev_byte == 0x98 && b < 0x73) {
                         score += self.maybe_set_as_pending(
                             SHIFT_JIS_SCORE_PER_LEVEL_1_KANJI
                                 + cjk_extra_score(u, &data::DETECTOR_DATA.frequent_kanji),
+                            weight,
                         );
                     } else {
-                        score += self.maybe_set_as_pending(SHIFT_JIS_SCORE_PER_LEVEL_2_KANJI);
+                        score +=
+                            self.maybe_set_as_pending(SHIFT_JIS_SCORE_PER_LEVEL_2_KANJI, weight);
                     }
                     if self.prev == LatinCj::AsciiLetter {
                         score += CJK_LATIN_ADJACENCY_PENALTY;
@@ -1349,7 +1502,7 @@ impl ShiftJisCandidate {
                     self.prev = LatinCj::Cj;
                 } else if u >= 0xE000 && u < 0xF900 {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     score += SHIFT_JIS_PUA_PENALTY;
@@ -1363,7 +1516,7 @@ impl ShiftJisCandidate {
                         | 0xFF09 // Distinct from Korean, parenthesis
                         => {
                             if let Some(pending) = self.pending_score {
-                                score += pending;
+                                released += pending;
                                 self.pending_score = None;
                             }
                             // Not really needed for CJK distinction
@@ -1383,7 +1536,7 @@ impl ShiftJisCandidate {
                         }
                         _ => {
                             if let Some(pending) = self.pending_score {
-                                score += pending;
+                                released += pending;
                                 self.pending_score = None;
                             }
                             score += CJK_OTHER;
@@ -1395,8 +1548,10 @@ impl ShiftJisCandidate {
             match result {
                 DecoderResult::InputEmpty => {
                     assert_eq!(read, 1);
+                    self.mid_character = written == 0;
                 }
                 DecoderResult::Malformed(malformed_len, _) => {
+                    self.mid_character = false;
                     if (((self.prev_byte >= 0x81 && self.prev_byte <= 0x9F)
                         || (self.prev_byte >= 0xE0 && self.prev_byte <= 0xFC))
                         && ((b >= 0x40 && b <= 0x7E) || (b >= 0x80 && b <= 0xFC)))
@@ -1408,7 +1563,7 @@ impl ShiftJisCandidate {
                     {
                         // Shift_JIS2004 or MacJapanese
                         if let Some(pending) = self.pending_score {
-                            score += pending;
+                            released += pending;
                             self.pending_score = None;
                         }
                         score += SHIFT_JIS_EXTENSION_PENALTY;
@@ -1449,10 +1604,11 @@ impl ShiftJisCandidate {
                 }
             }
         }
-        Some(score)
+        Some(score * weight + released)
     }
 }
 
+#[cfg(feature = "cjk")]
 struct EucJpCandidate {
     decoder: Decoder,
     non_ascii_seen: bool,
@@ -1460,8 +1616,11 @@ struct EucJpCandidate {
     prev: LatinCj,
     prev_byte: u8,
     prev_prev_byte: u8,
+    /// Whether the decoder is in the middle of a multi-byte character.
+    mid_character: bool,
 }
 
+#[cfg(feature = "cjk")]
 impl EucJpCandidate {
     fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
         let mut score = 0i64;
@@ -1571,8 +1730,10 @@ impl EucJpCandidate {
             match result {
                 DecoderResult::InputEmpty => {
                     assert_eq!(read, 1);
+                    self.mid_character = written == 0;
                 }
                 DecoderResult::Malformed(_, _) => {
+                    self.mid_character = false;
                     if b >= 0xA1
                         && b <= 0xFE
                         && self.prev_byte >= 0xA1
@@ -1625,26 +1786,31 @@ impl EucJpCandidate {
     }
 }
 
+#[cfg(feature = "cjk")]
 struct Big5Candidate {
     decoder: Decoder,
     prev: LatinCj,
     prev_byte: u8,
     pending_score: Option<i64>,
+    /// Whether the decoder is in the middle of a multi-byte character.
+    mid_character: bool,
 }
 
+#[cfg(feature = "cjk")]
 impl Big5Candidate {
-    fn maybe_set_as_pending(&mut self, s: i64) -> i64 {
+    fn maybe_set_as_pending(&mut self, s: i64, weight: i64) -> i64 {
         assert!(self.pending_score.is_none());
         if self.prev == LatinCj::Cj || !problematic_lead(self.prev_byte) {
             s
         } else {
-            self.pending_score = Some(s);
+            self.pending_score = Some(s * weight);
             0
         }
     }
 
-    fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
+    fn feed(&mut self, buffer: &[u8], last: bool, weight: i64) -> Option<i64> {
         let mut score = 0i64;
+        let mut released = 0i64;
         let mut src = [0u8];
         let mut dst = [0u16; 2];
         for &b in buffer {
@@ -1664,16 +1830,17 @@ impl Big5Candidate {
                     self.prev = LatinCj::AsciiLetter;
                 } else if (u >= 0x3400 && u < 0xA000) || (u >= 0xF900 && u < 0xFB00) {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     match self.prev_byte {
                         0xA4..=0xC6 => {
-                            score += self.maybe_set_as_pending(BIG5_SCORE_PER_LEVEL_1_HANZI);
+                            score +=
+                                self.maybe_set_as_pending(BIG5_SCORE_PER_LEVEL_1_HANZI, weight);
                             // score += cjk_extra_score(u, &data::DETECTOR_DATA.frequent_traditional);
                         }
                         _ => {
-                            score += self.maybe_set_as_pending(BIG5_SCORE_PER_OTHER_HANZI);
+                            score += self.maybe_set_as_pending(BIG5_SCORE_PER_OTHER_HANZI, weight);
                         }
                     }
                     if self.prev == LatinCj::AsciiLetter {
@@ -1693,7 +1860,7 @@ impl Big5Candidate {
                         | 0xFF1F // Distinct from Japanese, question
                         => {
                             if let Some(pending) = self.pending_score {
-                                score += pending;
+                                released += pending;
                                 self.pending_score = None;
                             }
                             // Not really needed for CJK distinction
@@ -1706,7 +1873,7 @@ impl Big5Candidate {
                         }
                         _ => {
                             if let Some(pending) = self.pending_score {
-                                score += pending;
+                                released += pending;
                                 self.pending_score = None;
                             }
                             score += CJK_OTHER;
@@ -1716,7 +1883,7 @@ impl Big5Candidate {
                 }
             } else if written == 2 {
                 if let Some(pending) = self.pending_score {
-                    score += pending;
+                    released += pending;
                     self.pending_score = None;
                 }
                 if dst[0] == 0xCA || dst[0] == 0xEA {
@@ -1724,7 +1891,7 @@ impl Big5Candidate {
                     self.prev = LatinCj::Other;
                 } else {
                     debug_assert!(dst[0] >= 0xD480 && dst[0] < 0xD880);
-                    score += self.maybe_set_as_pending(BIG5_SCORE_PER_OTHER_HANZI);
+                    score += self.maybe_set_as_pending(BIG5_SCORE_PER_OTHER_HANZI, weight);
                     if self.prev == LatinCj::AsciiLetter {
                         score += CJK_LATIN_ADJACENCY_PENALTY;
                     }
@@ -1734,8 +1901,10 @@ impl Big5Candidate {
             match result {
                 DecoderResult::InputEmpty => {
                     assert_eq!(read, 1);
+                    self.mid_character = written == 0;
                 }
                 DecoderResult::Malformed(malformed_len, _) => {
+                    self.mid_character = false;
                     if self.prev_byte >= 0x81
                         && self.prev_byte <= 0xFE
                         && ((b >= 0x40 && b <= 0x7E) || (b >= 0xA1 && b <= 0xFE))
@@ -1745,7 +1914,7 @@ impl Big5Candidate {
                         // We don't reprocess `b` even if ASCII, since it's
                         // logically part of the pair.
                         if let Some(pending) = self.pending_score {
-                            score += pending;
+                            released += pending;
                             self.pending_score = None;
                         }
                         score += BIG5_PUA_PENALTY;
@@ -1800,10 +1969,11 @@ impl Big5Candidate {
                 }
             }
         }
-        Some(score)
+        Some(score * weight + released)
     }
 }
 
+#[cfg(feature = "cjk")]
 struct EucKrCandidate {
     decoder: Decoder,
     prev_byte: u8,
@@ -1811,21 +1981,25 @@ struct EucKrCandidate {
     prev: LatinKorean,
     current_word_len: u64,
     pending_score: Option<i64>,
+    /// Whether the decoder is in the middle of a multi-byte character.
+    mid_character: bool,
 }
 
+#[cfg(feature = "cjk")]
 impl EucKrCandidate {
-    fn maybe_set_as_pending(&mut self, s: i64) -> i64 {
+    fn maybe_set_as_pending(&mut self, s: i64, weight: i64) -> i64 {
         assert!(self.pending_score.is_none());
         if self.prev == LatinKorean::Hangul || !more_problematic_lead(self.prev_byte) {
             s
         } else {
-            self.pending_score = Some(s);
+            self.pending_score = Some(s * weight);
             0
         }
     }
 
-    fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
+    fn feed(&mut self, buffer: &[u8], last: bool, weight: i64) -> Option<i64> {
         let mut score = 0i64;
+        let mut released = 0i64;
         let mut src = [0u8];
         let mut dst = [0u16; 2];
         for &b in buffer {
@@ -1850,14 +2024,14 @@ impl EucKrCandidate {
                     self.current_word_len = 0;
                 } else if u >= 0xAC00 && u <= 0xD7A3 {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     if self.prev_was_euc_range && in_euc_range {
                         score += EUC_KR_SCORE_PER_EUC_HANGUL;
                         score += cjk_extra_score(u, &data::DETECTOR_DATA.frequent_hangul);
                     } else {
-                        score += self.maybe_set_as_pending(EUC_KR_SCORE_PER_NON_EUC_HANGUL);
+                        score += self.maybe_set_as_pending(EUC_KR_SCORE_PER_NON_EUC_HANGUL, weight);
                     }
                     if self.prev == LatinKorean::AsciiLetter {
                         score += CJK_LATIN_ADJACENCY_PENALTY;
@@ -1869,7 +2043,7 @@ impl EucKrCandidate {
                     }
                 } else if (u >= 0x4E00 && u < 0xAC00) || (u >= 0xF900 && u <= 0xFA0B) {
                     if let Some(pending) = self.pending_score {
-                        score += pending;
+                        released += pending;
                         self.pending_score = None;
                     }
                     score += EUC_KR_SCORE_PER_HANJA;
@@ -1890,7 +2064,7 @@ impl EucKrCandidate {
                 } else {
                     if u >= 0x80 {
                         if let Some(pending) = self.pending_score {
-                            score += pending;
+                            released += pending;
                             self.pending_score = None;
                         }
                         score += CJK_OTHER;
@@ -1904,12 +2078,14 @@ impl EucKrCandidate {
             match result {
                 DecoderResult::InputEmpty => {
                     assert_eq!(read, 1);
+                    self.mid_character = written == 0;
                 }
                 DecoderResult::Malformed(malformed_len, _) => {
+                    self.mid_character = false;
                     if (self.prev_byte == 0xC9 || self.prev_byte == 0xFE) && b >= 0xA1 && b <= 0xFE
                     {
                         if let Some(pending) = self.pending_score {
-                            score += pending;
+                            released += pending;
                             self.pending_score = None;
                         }
                         // The byte pair is in code page 949 EUDC range
@@ -1935,7 +2111,7 @@ impl EucKrCandidate {
                         && (b >= 0x7B && b <= 0x7D)
                     {
                         if let Some(pending) = self.pending_score {
-                            score += pending;
+                            released += pending;
                             self.pending_score = None;
                         }
                         // MacKorean symbols in range not part of code page 949
@@ -1989,138 +2165,235 @@ impl EucKrCandidate {
                 }
             }
         }
-        Some(score)
+        Some(score * weight + released)
     }
 }
 
 enum InnerCandidate {
     Latin(LatinCandidate),
+    #[cfg(any(feature = "cyrillic", feature = "greek"))]
     NonLatinCased(NonLatinCasedCandidate),
+    #[cfg(any(feature = "thai", feature = "arabic"))]
     Caseless(CaselessCandidate),
+    #[cfg(feature = "arabic")]
     ArabicFrench(ArabicFrenchCandidate),
+    #[cfg(feature = "hebrew")]
     Logical(LogicalCandidate),
+    #[cfg(feature = "hebrew")]
     Visual(VisualCandidate),
     Utf8(Utf8Candidate),
+    #[cfg(feature = "cjk")]
     Iso2022(Iso2022Candidate),
+    #[cfg(feature = "cjk")]
     Shift(ShiftJisCandidate),
+    #[cfg(feature = "cjk")]
     EucJp(EucJpCandidate),
+    #[cfg(feature = "cjk")]
     EucKr(EucKrCandidate),
+    #[cfg(feature = "cjk")]
     Big5(Big5Candidate),
+    #[cfg(feature = "cjk")]
     Gbk(GbkCandidate),
+    /// A candidate whose encoding family has been compiled out.
+    #[cfg(not(all(
+        feature = "cjk",
+        feature = "cyrillic",
+        feature = "greek",
+        feature = "hebrew",
+        feature = "arabic",
+        feature = "thai",
+        feature = "baltic",
+        feature = "vietnamese",
+        feature = "central",
+        feature = "turkish"
+    )))]
+    Absent(&'static Encoding),
 }
 
 impl InnerCandidate {
-    fn feed(&mut self, buffer: &[u8], last: bool) -> Option<i64> {
+    /// Feeds `buffer` and returns the score it adds, scaled by `weight`.
+    /// The end of the stream doesn't belong to any chunk, so its score is
+    /// not scaled.
+    fn feed(&mut self, buffer: &[u8], last: bool, weight: i64) -> Option<i64> {
+        let new_score = match self {
+            InnerCandidate::Latin(c) => c.feed(buffer),
+            #[cfg(any(feature = "cyrillic", feature = "greek"))]
+            InnerCandidate::NonLatinCased(c) => c.feed(buffer),
+            #[cfg(any(feature = "thai", feature = "arabic"))]
+            InnerCandidate::Caseless(c) => c.feed(buffer),
+            #[cfg(feature = "arabic")]
+            InnerCandidate::ArabicFrench(c) => c.feed(buffer),
+            #[cfg(feature = "hebrew")]
+            InnerCandidate::Logical(c) => c.feed(buffer),
+            #[cfg(feature = "hebrew")]
+            InnerCandidate::Visual(c) => c.feed(buffer),
+            InnerCandidate::Utf8(c) => c.feed(buffer, last),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Iso2022(c) => c.feed(buffer, last),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Shift(c) => return c.feed(buffer, last, weight),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::EucJp(c) => c.feed(buffer, last),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::EucKr(c) => return c.feed(buffer, last, weight),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Big5(c) => return c.feed(buffer, last, weight),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Gbk(c) => return c.feed(buffer, last, weight),
+            #[cfg(not(all(
+                feature = "cjk",
+                feature = "cyrillic",
+                feature = "greek",
+                feature = "hebrew",
+                feature = "arabic",
+                feature = "thai",
+                feature = "baltic",
+                feature = "vietnamese",
+                feature = "central",
+                feature = "turkish"
+            )))]
+            InnerCandidate::Absent(_) => None,
+        }? * weight;
+        if !last {
+            return Some(new_score);
+        }
+        // Treat EOF as space-like
+        let additional_score = match self {
+            InnerCandidate::Latin(c) => c.feed(b" "),
+            #[cfg(any(feature = "cyrillic", feature = "greek"))]
+            InnerCandidate::NonLatinCased(c) => c.feed(b" "),
+            #[cfg(any(feature = "thai", feature = "arabic"))]
+            InnerCandidate::Caseless(c) => c.feed(b" "),
+            #[cfg(feature = "arabic")]
+            InnerCandidate::ArabicFrench(c) => c.feed(b" "),
+            #[cfg(feature = "hebrew")]
+            InnerCandidate::Logical(c) => c.feed(b" "),
+            #[cfg(feature = "hebrew")]
+            InnerCandidate::Visual(c) => c.feed(b" "),
+            _ => Some(0),
+        }?;
+        Some(new_score + additional_score)
+    }
+
+    /// Whether the last call to `feed()` ended in the middle of a character.
+    fn mid_character(&self) -> bool {
         match self {
-            InnerCandidate::Latin(c) => {
-                if let Some(new_score) = c.feed(buffer) {
-                    if last {
-                        // Treat EOF as space-like
-                        if let Some(additional_score) = c.feed(b" ") {
-                            Some(new_score + additional_score)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(new_score)
-                    }
-                } else {
-                    None
-                }
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Shift(c) => c.mid_character,
+            #[cfg(feature = "cjk")]
+            InnerCandidate::EucJp(c) => c.mid_character,
+            #[cfg(feature = "cjk")]
+            InnerCandidate::EucKr(c) => c.mid_character,
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Big5(c) => c.mid_character,
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Gbk(c) => c.mid_character,
+            _ => false,
+        }
+    }
+
+    /// Ends a fragment fed via `EncodingDetector::feed_fragment()`. The
+    /// boundary is space-like for the single-byte candidates. A character
+    /// split by the boundary disqualifies the others as at EOF, and their
+    /// decoders start afresh in the next fragment.
+    fn end_fragment(&mut self) -> Option<i64> {
+        match self {
+            InnerCandidate::Latin(c) => c.feed(b" "),
+            #[cfg(any(feature = "cyrillic", feature = "greek"))]
+            InnerCandidate::NonLatinCased(c) => c.feed(b" "),
+            #[cfg(any(feature = "thai", feature = "arabic"))]
+            InnerCandidate::Caseless(c) => c.feed(b" "),
+            #[cfg(feature = "arabic")]
+            InnerCandidate::ArabicFrench(c) => c.feed(b" "),
+            #[cfg(feature = "hebrew")]
+            InnerCandidate::Logical(c) => c.feed(b" "),
+            #[cfg(feature = "hebrew")]
+            InnerCandidate::Visual(c) => c.feed(b" "),
+            InnerCandidate::Utf8(c) => {
+                let score = c.feed(b"", true);
+                c.decoder = UTF_8.new_decoder_without_bom_handling();
+                score
             }
-            InnerCandidate::NonLatinCased(c) => {
-                if let Some(new_score) = c.feed(buffer) {
-                    if last {
-                        // Treat EOF as space-like
-                        if let Some(additional_score) = c.feed(b" ") {
-                            Some(new_score + additional_score)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(new_score)
-                    }
-                } else {
-                    None
-                }
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Iso2022(c) => {
+                let score = c.feed(b"", true);
+                c.decoder = ISO_2022_JP.new_decoder_without_bom_handling();
+                score
             }
-            InnerCandidate::Caseless(c) => {
-                if let Some(new_score) = c.feed(buffer) {
-                    if last {
-                        // Treat EOF as space-like
-                        if let Some(additional_score) = c.feed(b" ") {
-                            Some(new_score + additional_score)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(new_score)
-                    }
-                } else {
-                    None
-                }
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Shift(c) => {
+                // Without bytes, the weight makes no difference.
+                let score = c.feed(b"", true, 1);
+                c.decoder = SHIFT_JIS.new_decoder_without_bom_handling();
+                c.half_width_katakana_state = HalfWidthKatakana::DakutenForbidden;
+                c.prev = LatinCj::Other;
+                c.prev_byte = 0;
+                c.pending_score = None;
+                c.mid_character = false;
+                score
             }
-            InnerCandidate::ArabicFrench(c) => {
-                if let Some(new_score) = c.feed(buffer) {
-                    if last {
-                        // Treat EOF as space-like
-                        if let Some(additional_score) = c.feed(b" ") {
-                            Some(new_score + additional_score)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(new_score)
-                    }
-                } else {
-                    None
-                }
+            #[cfg(feature = "cjk")]
+            InnerCandidate::EucJp(c) => {
+                let score = c.feed(b"", true);
+                c.decoder = EUC_JP.new_decoder_without_bom_handling();
+                c.half_width_katakana_state = HalfWidthKatakana::DakutenForbidden;
+                c.prev = LatinCj::Other;
+                c.prev_byte = 0;
+                c.prev_prev_byte = 0;
+                c.mid_character = false;
+                score
             }
-            InnerCandidate::Logical(c) => {
-                if let Some(new_score) = c.feed(buffer) {
-                    if last {
-                        // Treat EOF as space-like
-                        if let Some(additional_score) = c.feed(b" ") {
-                            Some(new_score + additional_score)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(new_score)
-                    }
-                } else {
-                    None
-                }
+            #[cfg(feature = "cjk")]
+            InnerCandidate::EucKr(c) => {
+                let score = c.feed(b"", true, 1);
+                c.decoder = EUC_KR.new_decoder_without_bom_handling();
+                c.prev_byte = 0;
+                c.prev_was_euc_range = false;
+                c.prev = LatinKorean::Other;
+                c.current_word_len = 0;
+                c.pending_score = None;
+                c.mid_character = false;
+                score
             }
-            InnerCandidate::Visual(c) => {
-                if let Some(new_score) = c.feed(buffer) {
-                    if last {
-                        // Treat EOF as space-like
-                        if let Some(additional_score) = c.feed(b" ") {
-                            Some(new_score + additional_score)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(new_score)
-                    }
-                } else {
-                    None
-                }
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Big5(c) => {
+                let score = c.feed(b"", true, 1);
+                c.decoder = BIG5.new_decoder_without_bom_handling();
+                c.prev = LatinCj::Other;
+                c.prev_byte = 0;
+                c.pending_score = None;
+                c.mid_character = false;
+                score
             }
-            InnerCandidate::Utf8(c) => c.feed(buffer, last),
-            InnerCandidate::Iso2022(c) => c.feed(buffer, last),
-            InnerCandidate::Shift(c) => c.feed(buffer, last),
-            InnerCandidate::EucJp(c) => c.feed(buffer, last),
-            InnerCandidate::EucKr(c) => c.feed(buffer, last),
-            InnerCandidate::Big5(c) => c.feed(buffer, last),
-            InnerCandidate::Gbk(c) => c.feed(buffer, last),
+            #[cfg(feature = "cjk")]
+            InnerCandidate::Gbk(c) => {
+                let score = c.feed(b"", true, 1);
+                c.decoder = GBK.new_decoder_without_bom_handling();
+                c.prev = LatinCj::Other;
+                c.prev_byte = 0;
+                c.pending_score = None;
+                c.mid_character = false;
+                score
+            }
+            #[cfg(not(all(
+                feature = "cjk",
+                feature = "cyrillic",
+                feature = "greek",
+                feature = "hebrew",
+                feature = "arabic",
+                feature = "thai",
+                feature = "baltic",
+                feature = "vietnamese",
+                feature = "central",
+                feature = "turkish"
+            )))]
+            InnerCandidate::Absent(_) => None,
         }
     }
 }
 
 fn encoding_for_tld(tld: Tld) -> usize {
-    match tld {
+    let index = match tld {
         Tld::CentralWindows | Tld::CentralCyrillic => EncodingDetector::CENTRAL_WINDOWS_INDEX,
         Tld::Cyrillic => EncodingDetector::CYRILLIC_WINDOWS_INDEX,
         Tld::Generic | Tld::Western | Tld::WesternCyrillic | Tld::WesternArabic | Tld::Eu => {
@@ -2139,6 +2412,41 @@ fn encoding_for_tld(tld: Tld) -> usize {
         Tld::Japanese => EncodingDetector::SHIFT_JIS_INDEX,
         Tld::Korean => EncodingDetector::EUC_KR_INDEX,
         Tld::CentralIso => EncodingDetector::CENTRAL_ISO_INDEX,
+    };
+    // Without the TLD's own family, fall back to what generic domains get.
+    if EncodingDetector::is_compiled_in(index) {
+        index
+    } else {
+        EncodingDetector::WESTERN_INDEX
+    }
+}
+
+/// The class whose native encodings include the candidate at `encoding`,
+/// used for the encoding that a `HostProfile` expects.
+fn tld_for_candidate(encoding: usize) -> Tld {
+    match encoding {
+        EncodingDetector::CENTRAL_WINDOWS_INDEX => Tld::CentralWindows,
+        EncodingDetector::CENTRAL_ISO_INDEX => Tld::CentralIso,
+        EncodingDetector::CYRILLIC_WINDOWS_INDEX
+        | EncodingDetector::CYRILLIC_KOI_INDEX
+        | EncodingDetector::CYRILLIC_IBM_INDEX
+        | EncodingDetector::CYRILLIC_ISO_INDEX => Tld::Cyrillic,
+        EncodingDetector::WESTERN_INDEX => Tld::Western,
+        EncodingDetector::ICELANDIC_INDEX => Tld::IcelandicFaroese,
+        EncodingDetector::GREEK_WINDOWS_INDEX | EncodingDetector::GREEK_ISO_INDEX => Tld::Greek,
+        EncodingDetector::TURKISH_INDEX => Tld::TurkishAzeri,
+        EncodingDetector::LOGICAL_INDEX | EncodingDetector::VISUAL_INDEX => Tld::Hebrew,
+        EncodingDetector::ARABIC_WINDOWS_INDEX | EncodingDetector::ARABIC_ISO_INDEX => Tld::Arabic,
+        EncodingDetector::BALTIC_WINDOWS_INDEX
+        | EncodingDetector::BALTIC_ISO13_INDEX
+        | EncodingDetector::BALTIC_ISO4_INDEX => Tld::Baltic,
+        EncodingDetector::VIETNAMESE_INDEX => Tld::Vietnamese,
+        EncodingDetector::THAI_INDEX => Tld::Thai,
+        EncodingDetector::GBK_INDEX => Tld::Simplified,
+        EncodingDetector::BIG5_INDEX => Tld::Traditional,
+        EncodingDetector::SHIFT_JIS_INDEX | EncodingDetector::EUC_JP_INDEX => Tld::Japanese,
+        EncodingDetector::EUC_KR_INDEX => Tld::Korean,
+        _ => Tld::Generic,
     }
 }
 
@@ -2220,6 +2528,14 @@ fn encoding_is_native_to_tld(tld: Tld, encoding: usize) -> bool {
     }
 }
 
+/// Unless `score_adjustment()` says otherwise, the score of a candidate
+/// that the TLD doesn't favor is reduced by
+/// `score / DEFAULT_ADJUSTMENT_DIVISOR + DEFAULT_ADJUSTMENT_CONSTANT`.
+const DEFAULT_ADJUSTMENT_DIVISOR: i64 = 50;
+
+/// See `DEFAULT_ADJUSTMENT_DIVISOR`.
+const DEFAULT_ADJUSTMENT_CONSTANT: i64 = 60;
+
 fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
     if score < 1 {
         return 0;
@@ -2241,7 +2557,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Cyrillic => {
@@ -2262,7 +2578,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Western | Tld::WesternCyrillic | Tld::WesternArabic => {
@@ -2277,7 +2593,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Greek => {
@@ -2300,7 +2616,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::TurkishAzeri => {
@@ -2315,7 +2631,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Hebrew => {
@@ -2336,7 +2652,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Arabic => {
@@ -2363,7 +2679,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Baltic => {
@@ -2376,7 +2692,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Vietnamese => {
@@ -2391,7 +2707,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Thai => {
@@ -2420,7 +2736,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Simplified
@@ -2444,7 +2760,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::CentralCyrillic => {
@@ -2463,7 +2779,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
         Tld::Eu => {
@@ -2476,7 +2792,7 @@ fn score_adjustment(score: i64, encoding: usize, tld: Tld) -> i64 {
                     // XXX Tune this better instead of this kind of absolute.
                     return score;
                 }
-                _ => (50, 60),
+                _ => (DEFAULT_ADJUSTMENT_DIVISOR, DEFAULT_ADJUSTMENT_CONSTANT),
             }
         }
     };
@@ -2489,20 +2805,71 @@ cfg_if::cfg_if! {
         struct Candidate {
             inner: InnerCandidate,
             score: Option<i64>,
+            /// Whether the current fragment is invalid in the tolerant
+            /// mode. The fragment doesn't count, but the candidate stays.
+            rejected: bool,
+            /// The weight of the previous chunk, which still applies to
+            /// the rest of a character split from it.
+            weight: i64,
         }
     } else {
         struct Candidate {
             inner: InnerCandidate,
             score: Option<i64>,
+            /// Whether the current fragment is invalid in the tolerant
+            /// mode. The fragment doesn't count, but the candidate stays.
+            rejected: bool,
+            /// The weight of the previous chunk, which still applies to
+            /// the rest of a character split from it.
+            weight: i64,
         }
     }
 }
 
 impl Candidate {
-    fn feed(&mut self, buffer: &[u8], last: bool) {
+    fn feed(&mut self, buffer: &[u8], last: bool, weight: i64, tolerant: bool) {
+        let mut buffer = buffer;
+        if weight != self.weight {
+            // The rest of a character split from the previous chunk
+            // counts with the weight of that chunk.
+            while !buffer.is_empty() && self.inner.mid_character() {
+                let previous_weight = self.weight;
+                self.feed_weighted(&buffer[..1], false, previous_weight, tolerant);
+                buffer = &buffer[1..];
+            }
+            self.weight = weight;
+        }
+        self.feed_weighted(buffer, last, weight, tolerant);
+    }
+
+    fn feed_weighted(&mut self, buffer: &[u8], last: bool, weight: i64, tolerant: bool) {
+        if self.rejected {
+            return;
+        }
         if let Some(old_score) = self.score {
-            if let Some(new_score) = self.inner.feed(buffer, last) {
+            if let Some(new_score) = self.inner.feed(buffer, last, weight) {
                 self.score = Some(old_score + new_score);
+            } else if tolerant {
+                self.rejected = true;
+            } else {
+                self.score = None;
+            }
+        }
+    }
+
+    /// The boundary is not part of the fragment, so the weight doesn't
+    /// apply to it.
+    fn end_fragment(&mut self, tolerant: bool) {
+        if let Some(old_score) = self.score {
+            // Resets the state even after a rejection.
+            let new_score = self.inner.end_fragment();
+            if self.rejected {
+                return;
+            }
+            if let Some(new_score) = new_score {
+                self.score = Some(old_score + new_score);
+            } else if tolerant {
+                self.rejected = true;
             } else {
                 self.score = None;
             }
@@ -2516,43 +2883,93 @@ impl Candidate {
 
     fn new_latin(data: &'static SingleByteData) -> Self {
         Candidate {
-            inner: InnerCandidate::Latin(LatinCandidate::new(data)),
+            inner: InnerCandidate::Latin(LatinCandidate::new(data, false)),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    /// Latin with the windows-1252-specific ordinal handling.
+    fn new_windows_1252(data: &'static SingleByteData) -> Self {
+        Candidate {
+            inner: InnerCandidate::Latin(LatinCandidate::new(data, true)),
+            score: Some(0),
+            rejected: false,
+            weight: 1,
+        }
+    }
+
+    #[cfg(any(feature = "cyrillic", feature = "greek"))]
     fn new_non_latin_cased(data: &'static SingleByteData) -> Self {
         Candidate {
             inner: InnerCandidate::NonLatinCased(NonLatinCasedCandidate::new(data)),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(any(feature = "thai", feature = "arabic"))]
     fn new_caseless(data: &'static SingleByteData) -> Self {
         Candidate {
             inner: InnerCandidate::Caseless(CaselessCandidate::new(data)),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "arabic")]
     fn new_arabic_french(data: &'static SingleByteData) -> Self {
         Candidate {
             inner: InnerCandidate::ArabicFrench(ArabicFrenchCandidate::new(data)),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "hebrew")]
     fn new_logical(data: &'static SingleByteData) -> Self {
         Candidate {
             inner: InnerCandidate::Logical(LogicalCandidate::new(data)),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "hebrew")]
     fn new_visual(data: &'static SingleByteData) -> Self {
         Candidate {
             inner: InnerCandidate::Visual(VisualCandidate::new(data)),
             score: Some(0),
+            rejected: false,
+            weight: 1,
+        }
+    }
+
+    /// A candidate that is disqualified from the start, because its
+    /// encoding family has been compiled out.
+    #[cfg(not(all(
+        feature = "cjk",
+        feature = "cyrillic",
+        feature = "greek",
+        feature = "hebrew",
+        feature = "arabic",
+        feature = "thai",
+        feature = "baltic",
+        feature = "vietnamese",
+        feature = "central",
+        feature = "turkish"
+    )))]
+    fn new_absent(encoding: &'static Encoding) -> Self {
+        Candidate {
+            inner: InnerCandidate::Absent(encoding),
+            score: None,
+            rejected: false,
+            weight: 1,
         }
     }
 
@@ -2562,18 +2979,24 @@ impl Candidate {
                 decoder: UTF_8.new_decoder_without_bom_handling(),
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "cjk")]
     fn new_iso_2022_jp() -> Self {
         Candidate {
             inner: InnerCandidate::Iso2022(Iso2022Candidate {
                 decoder: ISO_2022_JP.new_decoder_without_bom_handling(),
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "cjk")]
     fn new_shift_jis() -> Self {
         Candidate {
             inner: InnerCandidate::Shift(ShiftJisCandidate {
@@ -2583,11 +3006,15 @@ impl Candidate {
                 prev: LatinCj::Other,
                 prev_byte: 0,
                 pending_score: None,
+                mid_character: false,
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "cjk")]
     fn new_euc_jp() -> Self {
         Candidate {
             inner: InnerCandidate::EucJp(EucJpCandidate {
@@ -2597,11 +3024,15 @@ impl Candidate {
                 prev: LatinCj::Other,
                 prev_byte: 0,
                 prev_prev_byte: 0,
+                mid_character: false,
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "cjk")]
     fn new_euc_kr() -> Self {
         Candidate {
             inner: InnerCandidate::EucKr(EucKrCandidate {
@@ -2611,11 +3042,15 @@ impl Candidate {
                 prev: LatinKorean::Other,
                 current_word_len: 0,
                 pending_score: None,
+                mid_character: false,
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "cjk")]
     fn new_big5() -> Self {
         Candidate {
             inner: InnerCandidate::Big5(Big5Candidate {
@@ -2623,11 +3058,15 @@ impl Candidate {
                 prev: LatinCj::Other,
                 prev_byte: 0,
                 pending_score: None,
+                mid_character: false,
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
+    #[cfg(feature = "cjk")]
     fn new_gbk() -> Self {
         Candidate {
             inner: InnerCandidate::Gbk(GbkCandidate {
@@ -2635,35 +3074,59 @@ impl Candidate {
                 prev: LatinCj::Other,
                 prev_byte: 0,
                 pending_score: None,
+                mid_character: false,
             }),
             score: Some(0),
+            rejected: false,
+            weight: 1,
         }
     }
 
-    fn score(&self, encoding: usize, tld: Tld, expectation_is_valid: bool) -> Option<i64> {
+    #[cfg_attr(
+        not(any(
+            feature = "cyrillic",
+            feature = "greek",
+            feature = "thai",
+            feature = "arabic",
+            feature = "hebrew"
+        )),
+        allow(unused_variables)
+    )]
+    fn score(
+        &self,
+        encoding: usize,
+        tld: Tld,
+        expectation_is_valid: bool,
+        min_word_len: u64,
+    ) -> Option<i64> {
         match &self.inner {
+            #[cfg(any(feature = "cyrillic", feature = "greek"))]
             InnerCandidate::NonLatinCased(c) => {
-                if c.longest_word < 2 {
+                if c.longest_word < min_word_len {
                     return None;
                 }
             }
+            #[cfg(any(feature = "thai", feature = "arabic"))]
             InnerCandidate::Caseless(c) => {
-                if c.longest_word < 2 && !encoding_is_native_to_tld(tld, encoding) {
+                if c.longest_word < min_word_len && !encoding_is_native_to_tld(tld, encoding) {
                     return None;
                 }
             }
+            #[cfg(feature = "arabic")]
             InnerCandidate::ArabicFrench(c) => {
-                if c.longest_word < 2 && !encoding_is_native_to_tld(tld, encoding) {
+                if c.longest_word < min_word_len && !encoding_is_native_to_tld(tld, encoding) {
                     return None;
                 }
             }
+            #[cfg(feature = "hebrew")]
             InnerCandidate::Logical(c) => {
-                if c.longest_word < 2 && !encoding_is_native_to_tld(tld, encoding) {
+                if c.longest_word < min_word_len && !encoding_is_native_to_tld(tld, encoding) {
                     return None;
                 }
             }
+            #[cfg(feature = "hebrew")]
             InnerCandidate::Visual(c) => {
-                if c.longest_word < 2 && !encoding_is_native_to_tld(tld, encoding) {
+                if c.longest_word < min_word_len && !encoding_is_native_to_tld(tld, encoding) {
                     return None;
                 }
             }
@@ -2692,9 +3155,11 @@ impl Candidate {
 
     fn plausible_punctuation(&self) -> u64 {
         match &self.inner {
+            #[cfg(feature = "hebrew")]
             InnerCandidate::Logical(c) => {
                 return c.plausible_punctuation;
             }
+            #[cfg(feature = "hebrew")]
             InnerCandidate::Visual(c) => {
                 return c.plausible_punctuation;
             }
@@ -2709,46 +3174,89 @@ impl Candidate {
             InnerCandidate::Latin(c) => {
                 return c.data.encoding;
             }
+            #[cfg(any(feature = "cyrillic", feature = "greek"))]
             InnerCandidate::NonLatinCased(c) => {
                 return c.data.encoding;
             }
+            #[cfg(any(feature = "thai", feature = "arabic"))]
             InnerCandidate::Caseless(c) => {
                 return c.data.encoding;
             }
+            #[cfg(feature = "arabic")]
             InnerCandidate::ArabicFrench(c) => {
                 return c.data.encoding;
             }
+            #[cfg(feature = "hebrew")]
             InnerCandidate::Logical(c) => {
                 return c.data.encoding;
             }
+            #[cfg(feature = "hebrew")]
             InnerCandidate::Visual(c) => {
                 return c.data.encoding;
             }
+            #[cfg(feature = "cjk")]
             InnerCandidate::Shift(_) => {
                 return SHIFT_JIS;
             }
+            #[cfg(feature = "cjk")]
             InnerCandidate::EucJp(_) => {
                 return EUC_JP;
             }
+            #[cfg(feature = "cjk")]
             InnerCandidate::Big5(_) => {
                 return BIG5;
             }
+            #[cfg(feature = "cjk")]
             InnerCandidate::EucKr(_) => {
                 return EUC_KR;
             }
+            #[cfg(feature = "cjk")]
             InnerCandidate::Gbk(_) => {
                 return GBK;
             }
             InnerCandidate::Utf8(_) => {
                 return UTF_8;
             }
+            #[cfg(feature = "cjk")]
             InnerCandidate::Iso2022(_) => {
                 return ISO_2022_JP;
             }
+            #[cfg(not(all(
+                feature = "cjk",
+                feature = "cyrillic",
+                feature = "greek",
+                feature = "hebrew",
+                feature = "arabic",
+                feature = "thai",
+                feature = "baltic",
+                feature = "vietnamese",
+                feature = "central",
+                feature = "turkish"
+            )))]
+            InnerCandidate::Absent(encoding) => encoding,
         }
     }
 }
 
+/// Constructs a candidate if its encoding family has been compiled in and
+/// an absent candidate otherwise.
+macro_rules! family {
+    ($feature:literal, $constructor:ident($data:expr)) => {{
+        #[cfg(feature = $feature)]
+        let candidate = Candidate::$constructor($data);
+        #[cfg(not(feature = $feature))]
+        let candidate = Candidate::new_absent($data.encoding);
+        candidate
+    }};
+    ($feature:literal, $constructor:ident(), $encoding:expr) => {{
+        #[cfg(feature = $feature)]
+        let candidate = Candidate::$constructor();
+        #[cfg(not(feature = $feature))]
+        let candidate = Candidate::new_absent($encoding);
+        candidate
+    }};
+}
+
 // LLVM doesn't autovectorize this properly for SSE2, so let's help manually.
 cfg_if::cfg_if! {
     if #[cfg(target_feature = "sse2")] {
@@ -2783,6 +3291,27 @@ cfg_if::cfg_if! {
     }
 }
 
+/// Returns the length of the shortest prefix of `buffer` that contains
+/// `count` non-ASCII bytes or `None` if `buffer` contains fewer.
+fn non_ascii_prefix_len(buffer: &[u8], count: u64) -> Option<usize> {
+    if count == 0 {
+        return Some(0);
+    }
+    if count_non_ascii(buffer) < count {
+        return None;
+    }
+    let mut seen = 0;
+    for (i, &b) in buffer.iter().enumerate() {
+        if b >= 0x80 {
+            seen += 1;
+            if seen == count {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
 #[derive(Clone, Copy)]
 enum BeforeNonAscii {
     None,
@@ -2823,6 +3352,67 @@ impl BeforeNonAscii {
     }
 }
 
+/// Candidate encodings and their scores as returned by
+/// `EncodingDetector::ranked_candidates()`, highest score first.
+#[derive(Clone, Copy)]
+pub struct RankedCandidates {
+    entries: [(&'static Encoding, i64); 27],
+    len: usize,
+}
+
+impl RankedCandidates {
+    fn insert(&mut self, encoding: &'static Encoding, score: i64) {
+        for entry in self.entries[..self.len].iter_mut() {
+            if entry.0 == encoding {
+                if score > entry.1 {
+                    entry.1 = score;
+                }
+                return;
+            }
+        }
+        self.entries[self.len] = (encoding, score);
+        self.len += 1;
+    }
+
+    /// The candidates as a slice of encoding and score pairs.
+    pub fn as_slice(&self) -> &[(&'static Encoding, i64)] {
+        &self.entries[..self.len]
+    }
+
+    /// The number of candidates that have not been ruled out.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether all candidates have been ruled out.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl PartialEq for RankedCandidates {
+    fn eq(&self, other: &RankedCandidates) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for RankedCandidates {}
+
+impl core::fmt::Debug for RankedCandidates {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a RankedCandidates {
+    type Item = &'a (&'static Encoding, i64);
+    type IntoIter = core::slice::Iter<'a, (&'static Encoding, i64)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
 /// A Web browser-oriented detector for guessing what character
 /// encoding a stream of bytes is encoded in.
 ///
@@ -2842,14 +3432,27 @@ pub struct EncodingDetector {
     last_before_non_ascii: BeforeNonAscii,
     esc_seen: bool,
     closed: bool,
+    bytes_seen: u64,
+    non_ascii_budget: Option<u64>,
+    byte_budget: Option<u64>,
+    budget_exhausted: bool,
+    weight: i64,
+    short_strings: bool,
+    tolerant: bool,
+    /// CP437 first, so that it wins ties with CP850.
+    dos: [DosCandidate; 2],
+    dos_code_pages: bool,
 }
 
 impl EncodingDetector {
     cfg_if::cfg_if! {
         if #[cfg(feature = "multithreading")] {
             fn feed_impl(&mut self, buffer: &[u8], last: bool) {
+                let weight = self.weight;
+                let tolerant = self.tolerant;
+                self.feed_dos(buffer, last);
                 if buffer.len() < 10 {
-                    self.candidates.iter_mut().for_each(|candidate| candidate.feed(buffer, last));
+                    self.candidates.iter_mut().for_each(|candidate| candidate.feed(buffer, last, weight, tolerant));
                     self.non_ascii_seen += count_non_ascii(buffer);
                     return;
                 }
@@ -2863,18 +3466,30 @@ impl EncodingDetector {
                         qualified.push(candidate);
                     }
                 }
-                let (_, non_ascii) = rayon::join(|| qualified.par_iter_mut().for_each(|candidate| candidate.feed(buffer, last)),
+                let (_, non_ascii) = rayon::join(|| qualified.par_iter_mut().for_each(|candidate| candidate.feed(buffer, last, weight, tolerant)),
                                                  || count_non_ascii(buffer));
                 self.non_ascii_seen += non_ascii;
             }
         } else {
             fn feed_impl(&mut self, buffer: &[u8], last: bool) {
-                self.candidates.iter_mut().for_each(|candidate| candidate.feed(buffer, last));
+                let weight = self.weight;
+                let tolerant = self.tolerant;
+                self.feed_dos(buffer, last);
+                self.candidates.iter_mut().for_each(|candidate| candidate.feed(buffer, last, weight, tolerant));
                 self.non_ascii_seen += count_non_ascii(buffer);
             }
         }
     }
 
+    fn feed_dos(&mut self, buffer: &[u8], last: bool) {
+        if self.dos_code_pages {
+            let weight = self.weight;
+            self.dos
+                .iter_mut()
+                .for_each(|candidate| candidate.feed(buffer, last, weight));
+        }
+    }
+
     /// Inform the detector of a chunk of input.
     ///
     /// The byte stream is represented as a sequence of calls to this
@@ -2898,6 +3513,9 @@ impl EncodingDetector {
     /// contained at least one non-ASCII byte and `false` if only
     /// ASCII has been seen so far.
     ///
+    /// If a budget has been set (see `set_non_ascii_budget()` and
+    /// `set_byte_budget()`), the bytes past the budget are ignored.
+    ///
     /// # Panics
     ///
     /// If this method has previously been called with `last` set to `true`.
@@ -2909,6 +3527,23 @@ impl EncodingDetector {
         if last {
             self.closed = true;
         }
+        if self.budget_exhausted {
+            return self.non_ascii_seen != 0;
+        }
+        let (buffer, last) = match self.budget_cutoff(buffer) {
+            Some(len) => {
+                self.budget_exhausted = true;
+                if len < buffer.len() {
+                    // The stream continues past the budget, so the
+                    // truncated buffer must not count as the end.
+                    (&buffer[..len], false)
+                } else {
+                    (buffer, last)
+                }
+            }
+            None => (buffer, last),
+        };
+        self.bytes_seen += buffer.len() as u64;
         let start = if self.non_ascii_seen == 0 && !self.esc_seen {
             let up_to = Encoding::ascii_valid_up_to(buffer);
             let start = if let Some(escape) = memchr::memchr(0x1B, &buffer[..up_to]) {
@@ -2936,6 +3571,168 @@ impl EncodingDetector {
         self.non_ascii_seen != 0
     }
 
+    /// Inform the detector of a fragment of input that is not contiguous
+    /// with the previous and the next fragment, such as a text node
+    /// extracted from an already-parsed document.
+    ///
+    /// Unlike with `feed()`, character pairs are not scored across the
+    /// boundary after `fragment`: the boundary is treated as a space, and
+    /// a multi-byte character must not be split across fragments.
+    /// Fragments may be freely interleaved with calls to `feed()`. The
+    /// arguments and the return value work the same way as for `feed()`.
+    ///
+    /// # Panics
+    ///
+    /// If `feed()` or this method has previously been called with `last`
+    /// set to `true`.
+    pub fn feed_fragment(&mut self, fragment: &[u8], last: bool) -> bool {
+        let non_ascii = self.feed(fragment, last);
+        if !last && !self.budget_exhausted {
+            if self.non_ascii_seen == 0 && !self.esc_seen {
+                // The candidates have not been fed yet.
+                self.last_before_non_ascii.push(b" ");
+            } else {
+                let tolerant = self.tolerant;
+                self.candidates
+                    .iter_mut()
+                    .for_each(|candidate| candidate.end_fragment(tolerant));
+                if self.dos_code_pages {
+                    self.dos
+                        .iter_mut()
+                        .for_each(|candidate| candidate.end_fragment());
+                }
+            }
+        }
+        non_ascii
+    }
+
+    /// Returns the length of the prefix of `buffer` after which a budget
+    /// is exhausted or `None` if the budgets extend past `buffer`.
+    fn budget_cutoff(&self, buffer: &[u8]) -> Option<usize> {
+        let by_bytes = self.byte_budget.and_then(|budget| {
+            let left = budget.saturating_sub(self.bytes_seen);
+            if buffer.len() as u64 >= left {
+                Some(left as usize)
+            } else {
+                None
+            }
+        });
+        self.non_ascii_budget
+            .and_then(|budget| {
+                let limit = by_bytes.unwrap_or(buffer.len());
+                non_ascii_prefix_len(&buffer[..limit], budget.saturating_sub(self.non_ascii_seen))
+            })
+            .or(by_bytes)
+    }
+
+    /// Makes the detector stop scoring once `budget` non-ASCII bytes have
+    /// been fed, so that subsequent calls to `feed()` are cheap no-ops.
+    /// `None` (the default) removes the limit.
+    ///
+    /// Browsers only look at the first few kilobytes of non-ASCII, so
+    /// the rest of a long stream seldom changes the guess. Once the
+    /// budget has been hit, the stream is treated as a prefix: as if the
+    /// rest of the stream had not been fed with `last` set to `true`.
+    pub fn set_non_ascii_budget(&mut self, budget: Option<u64>) {
+        self.non_ascii_budget = budget;
+    }
+
+    /// Makes the detector stop scoring once `budget` bytes in total have
+    /// been fed. Otherwise, works like `set_non_ascii_budget()`.
+    pub fn set_byte_budget(&mut self, budget: Option<u64>) {
+        self.byte_budget = budget;
+    }
+
+    /// Multiplies the score contributions of the bytes passed to
+    /// subsequent calls to `feed()` and `feed_fragment()` by `weight`
+    /// until the weight is set again. The default is 1. A character
+    /// split between calls counts with the weight of its first byte. The
+    /// end of the stream and the boundary after a fragment count as
+    /// spaces that belong to no chunk, so they are never weighted.
+    ///
+    /// This allows input that is particularly informative per byte, such
+    /// as the title of a document, to count more than boilerplate. A
+    /// weight of 0 makes the bytes count only for disqualifying
+    /// candidates: disqualification does not depend on the weight.
+    pub fn set_weight(&mut self, weight: u16) {
+        self.weight = i64::from(weight);
+    }
+
+    /// Optimizes the detector for very short inputs, such as file names,
+    /// when `enabled` is `true`. The default is `false`.
+    ///
+    /// By default, the non-Latin single-byte encodings only count if
+    /// the input has a word of at least two non-ASCII letters in them,
+    /// which guards against noise in longer text. In the short-string
+    /// mode, one letter suffices. For a batch of related strings, see
+    /// `ShortStringDetector`.
+    pub fn set_short_strings(&mut self, enabled: bool) {
+        self.short_strings = enabled;
+    }
+
+    /// Makes the detector score the input as CP437 and CP850, too, when
+    /// `enabled` is `true`. The default is `false`. Has to be called
+    /// before feeding.
+    ///
+    /// ZIP entry names without the UTF-8 flag are in the DOS code page of
+    /// the machine that created the archive, usually CP437, which
+    /// encoding_rs doesn't support. Only `guess_with_dos()`,
+    /// `dos_scores()` and `report()` take these candidates into account,
+    /// since the other methods return encoding_rs encodings.
+    pub fn set_dos_code_pages(&mut self, enabled: bool) {
+        self.dos_code_pages = enabled;
+    }
+
+    /// Makes invalid fragments not disqualify candidates. Instead, the
+    /// candidates that a fragment is invalid in are left out for that
+    /// fragment and reported by `take_rejections()`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn set_tolerant(&mut self) {
+        self.tolerant = true;
+    }
+
+    /// Returns the candidates, as bits by index, that rejected the
+    /// fragment fed last in the tolerant mode.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn take_rejections(&mut self) -> u32 {
+        let mut rejections = 0u32;
+        for (i, candidate) in self.candidates.iter_mut().enumerate() {
+            if candidate.rejected {
+                candidate.rejected = false;
+                rejections |= 1 << i;
+            }
+        }
+        rejections
+    }
+
+    /// Returns the candidates, as bits by index, for `encoding`.
+    pub(crate) fn candidate_mask(&self, encoding: &'static Encoding) -> u32 {
+        let mut mask = 0u32;
+        for (i, candidate) in self.candidates.iter().enumerate() {
+            if Self::is_compiled_in(i) && candidate.encoding() == encoding {
+                mask |= 1 << i;
+            }
+        }
+        mask
+    }
+
+    /// The number of non-ASCII letters that the longest word must have
+    /// for a non-Latin single-byte candidate to count.
+    fn min_word_len(&self) -> u64 {
+        if self.short_strings {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Returns `true` if the detector has stopped scoring, because the
+    /// budget set with `set_non_ascii_budget()` or `set_byte_budget()`
+    /// was hit.
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget_exhausted
+    }
+
     /// Guess the encoding given the bytes pushed to the detector so far
     /// (via `feed()`), the top-level domain name from which the bytes were
     /// loaded, and an indication of whether to consider UTF-8 as a permissible
@@ -2973,19 +3770,61 @@ impl EncodingDetector {
     /// one other candidate. If this method returns `false`, the
     /// guessed encoding is likely to be wrong.
     pub fn guess_assess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> (&'static Encoding, bool) {
-        let mut tld_type = tld.map_or(Tld::Generic, |tld| {
+        self.guess_assess_impl(tld, allow_utf8, 0, None)
+    }
+
+    /// Same as `guess_assess()`, but the encoding that `host` expects, if
+    /// any, takes the place of `tld` unless the expected encoding has been
+    /// ruled out: the other candidates' scores are reduced the way they
+    /// would be on a top-level domain native to the expected encoding,
+    /// and it is the fallback when no candidate scores positively. The
+    /// reductions for `tld` don't apply then. UTF-8 and ISO-2022-JP are
+    /// decided on validity, so expecting them has no effect.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `guess()`.)
+    pub fn guess_assess_for_host(
+        &self,
+        tld: Option<&[u8]>,
+        allow_utf8: bool,
+        host: &HostProfile,
+    ) -> (&'static Encoding, bool) {
+        self.guess_assess_impl(tld, allow_utf8, 0, host.expected())
+    }
+
+    /// Same as `guess_assess()`, but the candidates whose bits are set in
+    /// `excluded` are treated as disqualified, and the candidates for
+    /// `expected` are favored.
+    pub(crate) fn guess_assess_impl(
+        &self,
+        tld: Option<&[u8]>,
+        allow_utf8: bool,
+        excluded: u32,
+        expected: Option<&'static Encoding>,
+    ) -> (&'static Encoding, bool) {
+        let tld_type = tld.map_or(Tld::Generic, |tld| {
             assert!(!contains_upper_case_period_or_non_ascii(tld));
             classify_tld(tld)
         });
 
         if self.non_ascii_seen == 0
             && self.esc_seen
-            && self.candidates[Self::ISO_2022_JP_INDEX].score.is_some()
+            && self.is_qualified(Self::ISO_2022_JP_INDEX, excluded)
         {
             return (ISO_2022_JP, true);
         }
 
-        if self.candidates[Self::UTF_8_INDEX].score.is_some() {
+        let expected_mask = expected.map_or(0, |encoding| self.candidate_mask(encoding));
+        let fallback = match expected {
+            Some(encoding) if self.expected_candidate(expected_mask, excluded).is_some() => {
+                encoding
+            }
+            _ => self.candidates[encoding_for_tld(tld_type)].encoding(),
+        };
+
+        if self.is_qualified(Self::UTF_8_INDEX, excluded) {
             if allow_utf8 {
                 return (UTF_8, true);
             }
@@ -2993,15 +3832,258 @@ impl EncodingDetector {
             // see windows-1252 specifically. These tests run on generic
             // domains. However, if we returned windows-1252 on
             // some non-generic domains, we'd cause reloads.
-            return (self.candidates[encoding_for_tld(tld_type)].encoding(), true);
+            return (fallback, true);
         }
 
-        let mut encoding = self.candidates[encoding_for_tld(tld_type)].encoding();
+        let mut encoding = fallback;
         let mut max = 0i64;
+        let (tld_type, expectation_is_valid) =
+            self.tld_expectation(tld_type, expected_mask, excluded);
+        for (i, candidate) in self.candidates.iter().enumerate().skip(Self::FIRST_NORMAL) {
+            if excluded & (1 << i) != 0 {
+                continue;
+            }
+            if let Some(score) =
+                candidate.score(i, tld_type, expectation_is_valid, self.min_word_len())
+            {
+                if score > max {
+                    max = score;
+                    encoding = candidate.encoding();
+                }
+            }
+        }
+        let visual = &self.candidates[Self::VISUAL_INDEX];
+        let visual_score = if excluded & (1 << Self::VISUAL_INDEX) != 0 {
+            None
+        } else {
+            visual.score(
+                Self::VISUAL_INDEX,
+                tld_type,
+                expectation_is_valid,
+                self.min_word_len(),
+            )
+        };
+        if let Some(visual_score) = visual_score {
+            if (visual_score > max || encoding == WINDOWS_1255)
+                && visual.plausible_punctuation()
+                    > self.candidates[Self::LOGICAL_INDEX].plausible_punctuation()
+            {
+                // max = visual_score;
+                encoding = ISO_8859_8;
+            }
+        }
+        (encoding, max >= 0)
+    }
+
+    /// Whether the candidate at `index` has not been disqualified or
+    /// excluded.
+    fn is_qualified(&self, index: usize, excluded: u32) -> bool {
+        excluded & (1 << index) == 0 && self.candidates[index].score.is_some()
+    }
+
+    /// Returns the number of non-ASCII bytes that have been pushed to
+    /// the detector so far.
+    pub fn non_ascii_count(&self) -> u64 {
+        self.non_ascii_seen
+    }
+
+    /// Returns the candidate encodings that have not been ruled out
+    /// together with their scores given the bytes pushed to the detector
+    /// so far and the top-level domain name from which the bytes were
+    /// loaded. The `tld` argument works the same way as for `guess()`.
+    ///
+    /// The candidates are sorted from the highest score to the lowest.
+    /// When more than one internal model maps to the same encoding, the
+    /// encoding is listed once with the highest of the scores.
+    ///
+    /// UTF-8 and ISO-2022-JP are never listed, because they are decided
+    /// on validity alone instead of being scored. For this reason and
+    /// due to the special-casing of visual Hebrew and of the fallback
+    /// on non-generic domains, the first item is not necessarily the
+    /// encoding that `guess()` returns. This method is meant for
+    /// diagnostics.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `guess()`.)
+    pub fn ranked_candidates(&self, tld: Option<&[u8]>) -> RankedCandidates {
+        let tld_type = tld.map_or(Tld::Generic, |tld| {
+            assert!(!contains_upper_case_period_or_non_ascii(tld));
+            classify_tld(tld)
+        });
+        let (tld_type, expectation_is_valid) = self.tld_expectation(tld_type, 0, 0);
+        let mut ranked = RankedCandidates {
+            entries: [(UTF_8, 0); 27],
+            len: 0,
+        };
+        for (i, candidate) in self.candidates.iter().enumerate().skip(Self::VISUAL_INDEX) {
+            if let Some(score) =
+                candidate.score(i, tld_type, expectation_is_valid, self.min_word_len())
+            {
+                ranked.insert(candidate.encoding(), score);
+            }
+        }
+        ranked.entries[..ranked.len].sort_unstable_by_key(|&(_, score)| core::cmp::Reverse(score));
+        ranked
+    }
+
+    /// Returns the scores of CP437 and CP850, in that order, adjusted for
+    /// `tld` the way `ranked_candidates()` adjusts the scores of the
+    /// other candidates. For the adjustment, both count as windows-1252,
+    /// whose model scores them. `None` means that the code page has been
+    /// ruled out or that `set_dos_code_pages()` has not been enabled.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `guess()`.)
+    pub fn dos_scores(&self, tld: Option<&[u8]>) -> [Option<i64>; 2] {
+        if !self.dos_code_pages {
+            return [None, None];
+        }
+        let tld_type = EncodingDetector::tld_class(tld);
+        let (tld_type, expectation_is_valid) = self.tld_expectation(tld_type, 0, 0);
+        let adjust = |candidate: &DosCandidate| {
+            candidate.score().map(|score| {
+                if tld_type == Tld::Generic
+                    || !expectation_is_valid
+                    || encoding_is_native_to_tld(tld_type, Self::WESTERN_INDEX)
+                {
+                    score
+                } else {
+                    score - score_adjustment(score, Self::WESTERN_INDEX, tld_type)
+                }
+            })
+        };
+        [adjust(&self.dos[0]), adjust(&self.dos[1])]
+    }
+
+    /// Same as `guess_assess()`, except that a DOS code page is returned
+    /// if it scores higher than every encoding_rs candidate in
+    /// `ranked_candidates()`. Both sides are adjusted for `tld` (see
+    /// `dos_scores()`). Without `set_dos_code_pages()`, never returns a
+    /// DOS code page.
+    ///
+    /// Valid UTF-8 with `allow_utf8` and ASCII-only input are never
+    /// attributed to a DOS code page.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `guess()`.)
+    pub fn guess_with_dos(&self, tld: Option<&[u8]>, allow_utf8: bool) -> (NameEncoding, bool) {
+        let (encoding, confident) = self.guess_assess(tld, allow_utf8);
+        if !self.dos_code_pages || encoding == UTF_8 || self.non_ascii_seen == 0 {
+            return (NameEncoding::Encoding(encoding), confident);
+        }
+        let mut best = None;
+        let mut max = i64::MIN;
+        for (candidate, score) in self.dos.iter().zip(self.dos_scores(tld).iter()) {
+            if let Some(score) = *score {
+                if score > max {
+                    max = score;
+                    best = Some(candidate.code_page());
+                }
+            }
+        }
+        if let Some(code_page) = best {
+            let beats_others = match self.ranked_candidates(tld).as_slice().first() {
+                Some(&(_, score)) => max > score,
+                None => true,
+            };
+            if beats_others {
+                return (NameEncoding::Dos(code_page), max >= 0);
+            }
+        }
+        (NameEncoding::Encoding(encoding), confident)
+    }
+
+    /// Compares the decodings of `input` as the top two items of
+    /// `ranked_candidates()` to tell whether the choice between them
+    /// matters, e.g. for windows-1250 and ISO-8859-2. `input` must be
+    /// the bytes that were fed. Returns `None` if fewer than two
+    /// candidates remain.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `guess()`.)
+    #[cfg(feature = "alloc")]
+    pub fn ambiguity(&self, input: &[u8], tld: Option<&[u8]>) -> Option<Ambiguity> {
+        match self.ranked_candidates(tld).as_slice() {
+            [(first, _), (second, _), ..] => Some(Ambiguity::between(first, second, input)),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `encoding` is one of the candidates and all the
+    /// candidates for it have been disqualified by the bytes fed so far.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn rules_out(&self, encoding: &'static Encoding) -> bool {
+        let mut found = false;
+        for (i, candidate) in self.candidates.iter().enumerate() {
+            if Self::is_compiled_in(i) && candidate.encoding() == encoding {
+                if candidate.score.is_some() {
+                    return false;
+                }
+                found = true;
+            }
+        }
+        found
+    }
+
+    /// Returns the result of `guess_assess()` together with the class of
+    /// `tld`, the result of `ranked_candidates()`, and the DOS code page
+    /// results (see `set_dos_code_pages()`) for storing or comparing
+    /// outcomes. The arguments work the same way as for
+    /// `guess()`.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `guess()`.)
+    pub fn report(&self, tld: Option<&[u8]>, allow_utf8: bool) -> Report {
+        let (encoding, confident) = self.guess_assess(tld, allow_utf8);
+        let dos_code_page = match self.guess_with_dos(tld, allow_utf8).0 {
+            NameEncoding::Dos(code_page) => Some(code_page),
+            NameEncoding::Encoding(_) => None,
+        };
+        Report {
+            encoding,
+            confident,
+            tld_class: EncodingDetector::tld_class(tld),
+            candidates: self.ranked_candidates(tld),
+            dos_code_page,
+            dos_scores: self.dos_scores(tld),
+        }
+    }
+
+    /// Returns the first candidate, by index, whose bit is set in
+    /// `expected_mask` and that is still qualified. UTF-8 and ISO-2022-JP
+    /// are decided on validity and never count.
+    fn expected_candidate(&self, expected_mask: u32, excluded: u32) -> Option<usize> {
+        (Self::VISUAL_INDEX..self.candidates.len())
+            .find(|&i| expected_mask & (1 << i) != 0 && self.is_qualified(i, excluded))
+    }
+
+    /// Checks whether the encodings native to the TLD are still plausible
+    /// and, if not, whether the TLD should be treated as its Chinese or
+    /// Central European counterpart.
+    ///
+    /// `expected_mask` has the bits of the candidates for the encoding
+    /// that a `HostProfile` expects. While one of them is plausible, the
+    /// host's class replaces the TLD's, so the two are never applied on
+    /// top of each other.
+    fn tld_expectation(&self, tld_type: Tld, expected_mask: u32, excluded: u32) -> (Tld, bool) {
+        if let Some(i) = self.expected_candidate(expected_mask, excluded) {
+            return (tld_for_candidate(i), true);
+        }
+        let mut tld_type = tld_type;
         let mut expectation_is_valid = false;
         if tld_type != Tld::Generic {
-            for (i, candidate) in self.candidates.iter().enumerate().skip(Self::FIRST_NORMAL) {
-                if encoding_is_native_to_tld(tld_type, i) && candidate.score.is_some() {
+            for i in Self::FIRST_NORMAL..self.candidates.len() {
+                if encoding_is_native_to_tld(tld_type, i) && self.is_qualified(i, excluded) {
                     expectation_is_valid = true;
                     break;
                 }
@@ -3011,25 +4093,25 @@ impl EncodingDetector {
             // Flip Chinese and Central around
             match tld_type {
                 Tld::Simplified => {
-                    if self.candidates[Self::BIG5_INDEX].score.is_some() {
+                    if self.is_qualified(Self::BIG5_INDEX, excluded) {
                         tld_type = Tld::Traditional;
                         expectation_is_valid = true;
                     }
                 }
                 Tld::Traditional => {
-                    if self.candidates[Self::GBK_INDEX].score.is_some() {
+                    if self.is_qualified(Self::GBK_INDEX, excluded) {
                         tld_type = Tld::Simplified;
                         expectation_is_valid = true;
                     }
                 }
                 Tld::CentralWindows => {
-                    if self.candidates[Self::CENTRAL_ISO_INDEX].score.is_some() {
+                    if self.is_qualified(Self::CENTRAL_ISO_INDEX, excluded) {
                         tld_type = Tld::CentralIso;
                         expectation_is_valid = true;
                     }
                 }
                 Tld::CentralIso => {
-                    if self.candidates[Self::CENTRAL_WINDOWS_INDEX].score.is_some() {
+                    if self.is_qualified(Self::CENTRAL_WINDOWS_INDEX, excluded) {
                         tld_type = Tld::CentralWindows;
                         expectation_is_valid = true;
                     }
@@ -3037,26 +4119,7 @@ impl EncodingDetector {
                 _ => {}
             }
         }
-        for (i, candidate) in self.candidates.iter().enumerate().skip(Self::FIRST_NORMAL) {
-            if let Some(score) = candidate.score(i, tld_type, expectation_is_valid) {
-                if score > max {
-                    max = score;
-                    encoding = candidate.encoding();
-                }
-            }
-        }
-        let visual = &self.candidates[Self::VISUAL_INDEX];
-        if let Some(visual_score) = visual.score(Self::VISUAL_INDEX, tld_type, expectation_is_valid)
-        {
-            if (visual_score > max || encoding == WINDOWS_1255)
-                && visual.plausible_punctuation()
-                    > self.candidates[Self::LOGICAL_INDEX].plausible_punctuation()
-            {
-                // max = visual_score;
-                encoding = ISO_8859_8;
-            }
-        }
-        (encoding, max >= 0)
+        (tld_type, expectation_is_valid)
     }
 
     // XXX Test-only API
@@ -3104,7 +4167,7 @@ impl EncodingDetector {
         }
         for (i, candidate) in self.candidates.iter().enumerate() {
             if encoding == candidate.encoding() {
-                return candidate.score(i, tld_type, expectation_is_valid);
+                return candidate.score(i, tld_type, expectation_is_valid, self.min_word_len());
             }
         }
         Some(0)
@@ -3166,45 +4229,195 @@ impl EncodingDetector {
 
     const CYRILLIC_ISO_INDEX: usize = 26;
 
+    /// Whether the encoding family of the candidate at `index` has been
+    /// compiled in.
+    fn is_compiled_in(index: usize) -> bool {
+        match index {
+            Self::ISO_2022_JP_INDEX
+            | Self::GBK_INDEX
+            | Self::EUC_JP_INDEX
+            | Self::EUC_KR_INDEX
+            | Self::SHIFT_JIS_INDEX
+            | Self::BIG5_INDEX => cfg!(feature = "cjk"),
+            Self::CYRILLIC_WINDOWS_INDEX
+            | Self::CYRILLIC_KOI_INDEX
+            | Self::CYRILLIC_IBM_INDEX
+            | Self::CYRILLIC_ISO_INDEX => cfg!(feature = "cyrillic"),
+            Self::CENTRAL_WINDOWS_INDEX | Self::CENTRAL_ISO_INDEX => cfg!(feature = "central"),
+            Self::ARABIC_WINDOWS_INDEX | Self::ARABIC_ISO_INDEX => cfg!(feature = "arabic"),
+            Self::TURKISH_INDEX => cfg!(feature = "turkish"),
+            Self::THAI_INDEX => cfg!(feature = "thai"),
+            Self::VISUAL_INDEX | Self::LOGICAL_INDEX => cfg!(feature = "hebrew"),
+            Self::GREEK_WINDOWS_INDEX | Self::GREEK_ISO_INDEX => cfg!(feature = "greek"),
+            Self::BALTIC_WINDOWS_INDEX | Self::BALTIC_ISO13_INDEX | Self::BALTIC_ISO4_INDEX => {
+                cfg!(feature = "baltic")
+            }
+            Self::VIETNAMESE_INDEX => cfg!(feature = "vietnamese"),
+            _ => true,
+        }
+    }
+
     /// Creates a new instance of the detector.
     pub fn new() -> Self {
+        EncodingDetector::with_single_byte_data(&SINGLE_BYTE_DATA)
+    }
+
+    /// Creates a new instance of the detector that uses `model` instead of
+    /// the built-in single-byte models. The CJK and UTF-8 candidates are
+    /// not affected.
+    pub fn with_model(model: &'static SingleByteModel) -> Self {
+        EncodingDetector::with_single_byte_data(&model.data)
+    }
+
+    #[inline(always)]
+    fn with_single_byte_data(data: &'static [SingleByteData; 20]) -> Self {
         EncodingDetector {
             candidates: [
-                Candidate::new_utf_8(),                                                // 0
-                Candidate::new_iso_2022_jp(),                                          // 1
-                Candidate::new_visual(&SINGLE_BYTE_DATA[ISO_8859_8_INDEX]),            // 2
-                Candidate::new_gbk(),                                                  // 3
-                Candidate::new_euc_jp(),                                               // 4
-                Candidate::new_euc_kr(),                                               // 5
-                Candidate::new_shift_jis(),                                            // 6
-                Candidate::new_big5(),                                                 // 7
-                Candidate::new_latin(&SINGLE_BYTE_DATA[WINDOWS_1252_INDEX]),           // 8
-                Candidate::new_non_latin_cased(&SINGLE_BYTE_DATA[WINDOWS_1251_INDEX]), // 9
-                Candidate::new_latin(&SINGLE_BYTE_DATA[WINDOWS_1250_INDEX]),           // 10
-                Candidate::new_latin(&SINGLE_BYTE_DATA[ISO_8859_2_INDEX]),             // 11
-                Candidate::new_arabic_french(&SINGLE_BYTE_DATA[WINDOWS_1256_INDEX]),   // 12
-                Candidate::new_latin(&SINGLE_BYTE_DATA[WINDOWS_1252_ICELANDIC_INDEX]), // 13
-                Candidate::new_latin(&SINGLE_BYTE_DATA[WINDOWS_1254_INDEX]),           // 14
-                Candidate::new_caseless(&SINGLE_BYTE_DATA[WINDOWS_874_INDEX]),         // 15
-                Candidate::new_logical(&SINGLE_BYTE_DATA[WINDOWS_1255_INDEX]),         // 16
-                Candidate::new_non_latin_cased(&SINGLE_BYTE_DATA[WINDOWS_1253_INDEX]), // 17
-                Candidate::new_non_latin_cased(&SINGLE_BYTE_DATA[ISO_8859_7_INDEX]),   // 18
-                Candidate::new_latin(&SINGLE_BYTE_DATA[WINDOWS_1257_INDEX]),           // 19
-                Candidate::new_latin(&SINGLE_BYTE_DATA[ISO_8859_13_INDEX]),            // 20
-                Candidate::new_non_latin_cased(&SINGLE_BYTE_DATA[KOI8_U_INDEX]),       // 21
-                Candidate::new_non_latin_cased(&SINGLE_BYTE_DATA[IBM866_INDEX]),       // 22
-                Candidate::new_caseless(&SINGLE_BYTE_DATA[ISO_8859_6_INDEX]),          // 23
-                Candidate::new_latin(&SINGLE_BYTE_DATA[WINDOWS_1258_INDEX]),           // 24
-                Candidate::new_latin(&SINGLE_BYTE_DATA[ISO_8859_4_INDEX]),             // 25
-                Candidate::new_non_latin_cased(&SINGLE_BYTE_DATA[ISO_8859_5_INDEX]),   // 26
+                Candidate::new_utf_8(),                                              // 0
+                family!("cjk", new_iso_2022_jp(), ISO_2022_JP),                      // 1
+                family!("hebrew", new_visual(&data[ISO_8859_8_INDEX])),              // 2
+                family!("cjk", new_gbk(), GBK),                                      // 3
+                family!("cjk", new_euc_jp(), EUC_JP),                                // 4
+                family!("cjk", new_euc_kr(), EUC_KR),                                // 5
+                family!("cjk", new_shift_jis(), SHIFT_JIS),                          // 6
+                family!("cjk", new_big5(), BIG5),                                    // 7
+                Candidate::new_windows_1252(&data[WINDOWS_1252_INDEX]),              // 8
+                family!("cyrillic", new_non_latin_cased(&data[WINDOWS_1251_INDEX])), // 9
+                family!("central", new_latin(&data[WINDOWS_1250_INDEX])),            // 10
+                family!("central", new_latin(&data[ISO_8859_2_INDEX])),              // 11
+                family!("arabic", new_arabic_french(&data[WINDOWS_1256_INDEX])),     // 12
+                Candidate::new_latin(&data[WINDOWS_1252_ICELANDIC_INDEX]),           // 13
+                family!("turkish", new_latin(&data[WINDOWS_1254_INDEX])),            // 14
+                family!("thai", new_caseless(&data[WINDOWS_874_INDEX])),             // 15
+                family!("hebrew", new_logical(&data[WINDOWS_1255_INDEX])),           // 16
+                family!("greek", new_non_latin_cased(&data[WINDOWS_1253_INDEX])),    // 17
+                family!("greek", new_non_latin_cased(&data[ISO_8859_7_INDEX])),      // 18
+                family!("baltic", new_latin(&data[WINDOWS_1257_INDEX])),             // 19
+                family!("baltic", new_latin(&data[ISO_8859_13_INDEX])),              // 20
+                family!("cyrillic", new_non_latin_cased(&data[KOI8_U_INDEX])),       // 21
+                family!("cyrillic", new_non_latin_cased(&data[IBM866_INDEX])),       // 22
+                family!("arabic", new_caseless(&data[ISO_8859_6_INDEX])),            // 23
+                family!("vietnamese", new_latin(&data[WINDOWS_1258_INDEX])),         // 24
+                family!("baltic", new_latin(&data[ISO_8859_4_INDEX])),               // 25
+                family!("cyrillic", new_non_latin_cased(&data[ISO_8859_5_INDEX])),   // 26
             ],
             non_ascii_seen: 0,
             last_before_non_ascii: BeforeNonAscii::None,
             esc_seen: false,
             closed: false,
+            bytes_seen: 0,
+            non_ascii_budget: None,
+            byte_budget: None,
+            budget_exhausted: false,
+            weight: 1,
+            short_strings: false,
+            tolerant: false,
+            dos: [
+                DosCandidate::new(DosCodePage::Ibm437, &data[WINDOWS_1252_INDEX]),
+                DosCandidate::new(DosCodePage::Ibm850, &data[WINDOWS_1252_INDEX]),
+            ],
+            dos_code_pages: false,
+        }
+    }
+
+    /// Feeds windows of `window` bytes from the head, the middle, and the
+    /// tail of `data` (e.g. a memory-mapped file too large to feed in
+    /// full) as fragments and returns the guess. If `data` is not much
+    /// longer than three windows, it is fed in full. The window
+    /// boundaries are moved to avoid splitting multi-byte characters.
+    ///
+    /// This method feeds with `last` set to `true`, so the detector must
+    /// not have been fed that way already and can't be fed afterwards.
+    /// The `tld` and `allow_utf8` arguments work the same way as for
+    /// `guess()`.
+    ///
+    /// # Panics
+    ///
+    /// If `window` is zero, if the detector has been fed with `last` set
+    /// to `true`, or if `tld` contains non-ASCII, period, or upper-case
+    /// letters.
+    pub fn sample(
+        &mut self,
+        data: &[u8],
+        window: usize,
+        tld: Option<&[u8]>,
+        allow_utf8: bool,
+    ) -> Sample {
+        assert!(window != 0, "The window must not be empty.");
+        let total = data.len() as u64;
+        let examined = match sample::windows(total, window) {
+            None => {
+                self.feed(data, true);
+                total
+            }
+            Some(windows) => {
+                let mut examined = 0;
+                for (i, w) in windows.iter().enumerate() {
+                    let start = w.offset as usize;
+                    let part = sample::trim(&data[start..start + w.len], w, sample::slack(window));
+                    examined += part.len() as u64;
+                    self.feed_fragment(part, i == windows.len() - 1);
+                }
+                examined
+            }
+        };
+        let (encoding, confident) = self.guess_assess(tld, allow_utf8);
+        Sample {
+            encoding,
+            confident,
+            examined,
+            total,
         }
     }
 
+    /// Same as `sample()`, but reads the windows from a seekable source,
+    /// such as a file, instead of a slice. The position of `reader`
+    /// afterwards is unspecified. Available with the `std` feature.
+    ///
+    /// # Panics
+    ///
+    /// As with `sample()`.
+    #[cfg(feature = "std")]
+    pub fn sample_reader<R: std::io::Read + std::io::Seek>(
+        &mut self,
+        reader: &mut R,
+        window: usize,
+        tld: Option<&[u8]>,
+        allow_utf8: bool,
+    ) -> std::io::Result<Sample> {
+        use std::io::SeekFrom;
+        assert!(window != 0, "The window must not be empty.");
+        let total = reader.seek(SeekFrom::End(0))?;
+        let mut buf = std::vec::Vec::new();
+        let examined = match sample::windows(total, window) {
+            None => {
+                reader.seek(SeekFrom::Start(0))?;
+                reader.read_to_end(&mut buf)?;
+                self.feed(&buf, true);
+                buf.len() as u64
+            }
+            Some(windows) => {
+                let mut examined = 0;
+                for (i, w) in windows.iter().enumerate() {
+                    buf.resize(w.len, 0);
+                    reader.seek(SeekFrom::Start(w.offset))?;
+                    reader.read_exact(&mut buf)?;
+                    let part = sample::trim(&buf, w, sample::slack(window));
+                    examined += part.len() as u64;
+                    self.feed_fragment(part, i == windows.len() - 1);
+                }
+                examined
+            }
+        };
+        let (encoding, confident) = self.guess_assess(tld, allow_utf8);
+        Ok(Sample {
+            encoding,
+            confident,
+            examined,
+            total,
+        })
+    }
+
     /// Queries whether the TLD is considered non-generic and could affect the guess.
     ///
     /// # Panics
@@ -3215,12 +4428,37 @@ impl EncodingDetector {
     /// to lower-case it. Full DNS label validation is intentionally not performed
     /// to avoid panics when the reality doesn't match the specs.)
     pub fn tld_may_affect_guess(tld: Option<&[u8]>) -> bool {
-        if let Some(tld) = tld {
+        EncodingDetector::tld_class(tld) != TldClass::Generic
+    }
+
+    /// Same as `tld_may_affect_guess()`, but for `guess_assess_for_host()`,
+    /// where the encoding that `host` expects, if any, takes the place of
+    /// the TLD's class.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `tld_may_affect_guess()`.)
+    pub fn tld_may_affect_guess_for_host(tld: Option<&[u8]>, host: &HostProfile) -> bool {
+        EncodingDetector::tld_may_affect_guess(tld)
+            || match host.expected() {
+                Some(encoding) => encoding != UTF_8 && encoding != ISO_2022_JP,
+                None => false,
+            }
+    }
+
+    /// Returns the class that the detector puts the TLD into. `None` is
+    /// `TldClass::Generic`.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters. (See
+    /// `tld_may_affect_guess()`.)
+    pub fn tld_class(tld: Option<&[u8]>) -> TldClass {
+        tld.map_or(Tld::Generic, |tld| {
             assert!(!contains_upper_case_period_or_non_ascii(tld));
-            classify_tld(tld) != Tld::Generic
-        } else {
-            false
-        }
+            classify_tld(tld)
+        })
     }
 }
 
@@ -3231,21 +4469,35 @@ mod tests {
     use alloc::string::String;
     use alloc::vec::Vec;
     use detone::IterDecomposeVietnamese;
+    #[cfg(feature = "cyrillic")]
     use encoding_rs::IBM866;
+    #[cfg(feature = "central")]
     use encoding_rs::ISO_8859_2;
+    #[cfg(feature = "baltic")]
     use encoding_rs::ISO_8859_4;
+    #[cfg(feature = "cyrillic")]
     use encoding_rs::ISO_8859_5;
+    #[cfg(feature = "arabic")]
     use encoding_rs::ISO_8859_6;
+    #[cfg(feature = "greek")]
     use encoding_rs::ISO_8859_7;
+    #[cfg(feature = "cyrillic")]
     use encoding_rs::KOI8_U;
+    #[cfg(feature = "central")]
     use encoding_rs::WINDOWS_1250;
+    #[cfg(feature = "cyrillic")]
     use encoding_rs::WINDOWS_1251;
     use encoding_rs::WINDOWS_1252;
+    #[cfg(feature = "greek")]
     use encoding_rs::WINDOWS_1253;
+    #[cfg(feature = "turkish")]
     use encoding_rs::WINDOWS_1254;
+    #[cfg(feature = "arabic")]
     use encoding_rs::WINDOWS_1256;
+    #[cfg(feature = "baltic")]
     use encoding_rs::WINDOWS_1257;
     use encoding_rs::WINDOWS_1258;
+    #[cfg(feature = "thai")]
     use encoding_rs::WINDOWS_874;
 
     fn check_bytes(bytes: &[u8], encoding: &'static Encoding) {
@@ -3269,6 +4521,215 @@ mod tests {
         check_bytes(&bytes, encoding);
     }
 
+    #[test]
+    fn test_ranked_candidates() {
+        let mut det = EncodingDetector::new();
+        det.feed(b"Ma\xF0ur", true);
+        let ranked = det.ranked_candidates(None);
+        assert!(!ranked.is_empty());
+        assert_eq!(ranked.as_slice()[0].0, det.guess(None, false));
+        for pair in ranked.as_slice().windows(2) {
+            assert!(pair[0].1 >= pair[1].1);
+            assert_ne!(pair[0].0, pair[1].0);
+        }
+        assert!(ranked.into_iter().all(|&(enc, _)| enc != UTF_8));
+    }
+
+    #[test]
+    fn test_tld_fallback() {
+        let mut det = EncodingDetector::new();
+        det.feed(b"abc", true);
+        assert_eq!(det.guess(Some(b"fi"), false), WINDOWS_1252);
+        #[cfg(feature = "cyrillic")]
+        assert_eq!(det.guess(Some(b"ru"), false), WINDOWS_1251);
+        #[cfg(not(feature = "cyrillic"))]
+        assert_eq!(det.guess(Some(b"ru"), false), WINDOWS_1252);
+        let expected = if cfg!(feature = "cjk") {
+            SHIFT_JIS
+        } else {
+            WINDOWS_1252
+        };
+        assert_eq!(det.guess(Some(b"jp"), false), expected);
+    }
+
+    #[test]
+    #[cfg(not(feature = "cyrillic"))]
+    fn test_cyrillic_compiled_out() {
+        use encoding_rs::KOI8_U;
+        use encoding_rs::WINDOWS_1251;
+
+        let mut det = EncodingDetector::new();
+        let (bytes, _, _) = WINDOWS_1251.encode("Русский язык");
+        det.feed(&bytes, true);
+        assert_ne!(det.guess(Some(b"ru"), false), WINDOWS_1251);
+        assert!(det
+            .ranked_candidates(Some(b"ru"))
+            .into_iter()
+            .all(|&(enc, _)| enc != WINDOWS_1251 && enc != KOI8_U));
+    }
+
+    #[test]
+    #[cfg(not(feature = "cjk"))]
+    fn test_cjk_compiled_out() {
+        let mut det = EncodingDetector::new();
+        det.feed(b"\x1B$B$3$s$K$A$O\x1B(B", true);
+        assert_eq!(det.guess(None, false), WINDOWS_1252);
+        let mut det = EncodingDetector::new();
+        let (bytes, _, _) = SHIFT_JIS.encode("こんにちは、世界");
+        det.feed(&bytes, true);
+        assert!(det
+            .ranked_candidates(Some(b"jp"))
+            .into_iter()
+            .all(|&(enc, _)| enc != SHIFT_JIS && enc != EUC_JP && enc != GBK && enc != BIG5));
+    }
+
+    #[test]
+    fn test_non_ascii_count() {
+        let mut det = EncodingDetector::new();
+        det.feed(b"abc", false);
+        assert_eq!(det.non_ascii_count(), 0);
+        det.feed(b"\xE4bc\xF6", false);
+        det.feed(b"\xFC", true);
+        assert_eq!(det.non_ascii_count(), 3);
+    }
+
+    #[test]
+    fn test_non_ascii_budget() {
+        let mut det = EncodingDetector::new();
+        det.set_non_ascii_budget(Some(3));
+        // Cut in the middle of the second character, which must not
+        // count as malformed UTF-8.
+        assert!(det.feed("Привет".as_bytes(), false));
+        assert!(det.budget_exhausted());
+        assert_eq!(det.non_ascii_count(), 3);
+        det.feed(b"\xFF", true);
+        assert_eq!(det.non_ascii_count(), 3);
+        assert_eq!(det.guess(None, true), UTF_8);
+    }
+
+    #[test]
+    fn test_byte_budget() {
+        let mut det = EncodingDetector::new();
+        det.set_byte_budget(Some(4));
+        assert!(!det.feed(b"abc", false));
+        assert!(!det.budget_exhausted());
+        assert!(det.feed(b"\xE4\xFF", false));
+        assert!(det.budget_exhausted());
+        assert_eq!(det.non_ascii_count(), 1);
+        det.feed(b"\xFF", true);
+        assert_eq!(det.non_ascii_count(), 1);
+    }
+
+    #[test]
+    fn test_weight() {
+        let input = "Ma\u{F0}ur \u{F6}l \u{E1}lfum".as_bytes();
+        let mut plain = EncodingDetector::new();
+        plain.feed(b"Title: ", false);
+        plain.feed(input, true);
+        let mut weighted = EncodingDetector::new();
+        weighted.feed(b"Title: ", false);
+        weighted.set_weight(3);
+        weighted.feed(input, true);
+        for (p, w) in plain.candidates.iter().zip(weighted.candidates.iter()) {
+            assert_eq!(p.score.map(|score| score * 3), w.score);
+        }
+
+        // Disqualification does not depend on the weight.
+        let mut det = EncodingDetector::new();
+        det.set_weight(0);
+        det.feed(b"\xC3\x28", true);
+        assert!(det.candidates[EncodingDetector::UTF_8_INDEX]
+            .score
+            .is_none());
+        assert_eq!(
+            det.candidates[EncodingDetector::WESTERN_INDEX].score,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_weight_end_of_stream() {
+        fn western_score(weight: u16, fragment: bool) -> i64 {
+            let mut det = EncodingDetector::new();
+            det.feed(b"Title: ", false);
+            det.set_weight(weight);
+            if fragment {
+                det.feed_fragment(b"\xF6l \xE1lfum\xE1", false);
+                det.set_weight(1);
+                det.feed(b"", true);
+            } else {
+                det.feed(b"\xF6l \xE1lfum\xE1", true);
+            }
+            det.candidates[EncodingDetector::WESTERN_INDEX]
+                .score
+                .unwrap()
+        }
+
+        // The space-like end of the stream or of the fragment is not part
+        // of the weighted chunk, so only the rest of the score triples.
+        for &fragment in [false, true].iter() {
+            let end = western_score(0, fragment);
+            assert_ne!(end, 0);
+            let once = western_score(1, fragment);
+            assert_eq!(western_score(3, fragment), end + 3 * (once - end));
+        }
+    }
+
+    #[cfg(feature = "cjk")]
+    #[test]
+    fn test_weight_held_back() {
+        fn gbk_score(first_weight: u16, first: &[u8], second_weight: u16, second: &[u8]) -> i64 {
+            let mut det = EncodingDetector::new();
+            det.set_weight(first_weight);
+            det.feed(first, false);
+            det.set_weight(second_weight);
+            det.feed(second, true);
+            det.candidates[EncodingDetector::GBK_INDEX].score.unwrap()
+        }
+
+        // The second character of "中文" is split by the weight change, so
+        // it counts with the weight of the chunk where it starts.
+        assert_eq!(
+            gbk_score(1, b"\xD6\xD0\xCE", 3, b"\xC4"),
+            gbk_score(1, b"\xD6\xD0\xCE\xC4", 1, b"")
+        );
+
+        // The score of the non-EUC ideograph waits for the next ideograph,
+        // but still counts with the weight of its own chunk.
+        let held = b" \x81\x40";
+        let releasing = b"\xD6\xD0";
+        let both = gbk_score(1, held, 1, releasing);
+        let releasing_only = gbk_score(0, held, 1, releasing);
+        assert_ne!(releasing_only, both);
+        assert_eq!(gbk_score(1, held, 3, releasing), both + 2 * releasing_only);
+    }
+
+    #[test]
+    fn test_feed_fragment() {
+        // A boundary is like a space.
+        let mut det = EncodingDetector::new();
+        det.feed_fragment(b"Ma", false);
+        det.feed_fragment(b"\xF0ur", false);
+        det.feed_fragment(b"\xE4\xE4", true);
+        let mut contiguous = EncodingDetector::new();
+        contiguous.feed(b"Ma \xF0ur \xE4\xE4", true);
+        assert_eq!(
+            det.ranked_candidates(None),
+            contiguous.ranked_candidates(None)
+        );
+        assert_eq!(det.non_ascii_count(), 3);
+
+        // A character split across fragments is malformed.
+        let mut det = EncodingDetector::new();
+        det.feed_fragment(b"\xC3", false);
+        det.feed_fragment(b"\xA4", true);
+        assert_ne!(det.guess(None, true), UTF_8);
+        let mut det = EncodingDetector::new();
+        det.feed(b"\xC3", false);
+        det.feed(b"\xA4", true);
+        assert_eq!(det.guess(None, true), UTF_8);
+    }
+
     #[test]
     fn test_i_apostrophe() {
         let mut det = EncodingDetector::new();
@@ -3361,46 +4822,55 @@ mod tests {
         check("Þetta er kóðunarpróf á staf. Fyrir sum tungumál sem nota latneska stafi þurfum við meira inntak til að taka ákvörðunina.", WINDOWS_1252);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_ru_short() {
         check("Русский", WINDOWS_1251);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_ru() {
         check("Это тест кодировки символов.", WINDOWS_1251);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_ru_iso() {
         check("Это тест кодировки символов.", ISO_8859_5);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_ru_ibm() {
         check("Это тест кодировки символов.", IBM866);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_ru_koi() {
         check("Это тест кодировки символов.", KOI8_U);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_uk() {
         check("Це тест на кодування символів.", WINDOWS_1251);
     }
 
+    #[cfg(feature = "cyrillic")]
     #[test]
     fn test_uk_koi() {
         check("Це тест на кодування символів.", KOI8_U);
     }
 
+    #[cfg(feature = "greek")]
     #[test]
     fn test_el_short() {
         check("Ελληνικά", WINDOWS_1253);
     }
 
+    #[cfg(feature = "greek")]
     #[test]
     fn test_el() {
         check(
@@ -3409,6 +4879,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "greek")]
     #[test]
     fn test_el_iso() {
         check(
@@ -3428,76 +4899,91 @@ mod tests {
         check_bytes(&[68, 111, 110, 180, 116, 32], WINDOWS_1252);
     }
 
+    #[cfg(feature = "hebrew")]
     #[test]
     fn test_he() {
         check("\u{5E2}\u{5D1}\u{5E8}\u{5D9}\u{5EA}", WINDOWS_1255);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_2022() {
         check("日本語", ISO_2022_JP);
     }
 
+    #[cfg(feature = "thai")]
     #[test]
     fn test_th() {
         check("นี่คือการทดสอบการเข้ารหัสอักขระ", WINDOWS_874);
     }
 
+    #[cfg(feature = "vietnamese")]
     #[test]
     fn test_vi() {
         check("Đây là một thử nghiệm mã hóa ký tự.", WINDOWS_1258);
     }
 
+    #[cfg(feature = "turkish")]
     #[test]
     fn test_tr() {
         check("Bu bir karakter kodlama testidir. Latince karakterleri kullanan bazı dillerde karar vermek için daha fazla girdiye ihtiyacımız var.", WINDOWS_1254);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_simplified() {
         check("这是一个字符编码测试。", GBK);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_traditional() {
         check("這是一個字符編碼測試。", BIG5);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_ko() {
         check("이것은 문자 인코딩 테스트입니다.", EUC_KR);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_shift() {
         check("これは文字実験です。", SHIFT_JIS);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc() {
         check("これは文字実験です。", EUC_JP);
     }
 
+    #[cfg(feature = "arabic")]
     #[test]
     fn test_ar() {
         check("هذا هو اختبار ترميز الأحرف.", WINDOWS_1256);
     }
 
+    #[cfg(feature = "arabic")]
     #[test]
     fn test_ar_iso() {
         check("هذا هو اختبار ترميز الأحرف.", ISO_8859_6);
     }
 
+    #[cfg(feature = "arabic")]
     #[test]
     fn test_fa() {
         check("این یک تست رمزگذاری کاراکتر است.", WINDOWS_1256);
     }
 
+    #[cfg(feature = "hebrew")]
     #[test]
     fn test_visual() {
         check(".םיוות דודיק ןחבמ והז", ISO_8859_8);
     }
 
+    #[cfg(feature = "hebrew")]
     #[test]
     fn test_yi() {
         check("דאָס איז אַ טעסט פֿאַר קאָדירונג פון כאַראַקטער.", WINDOWS_1255);
@@ -3528,16 +5014,19 @@ mod tests {
         check("või", WINDOWS_1252);
     }
 
+    #[cfg(feature = "central")]
     #[test]
     fn test_pl_iso() {
         check("To jest test kodowania znaków. W przypadku niektórych języków, które używają znaków łacińskich, potrzebujemy więcej danych, aby podjąć decyzję.", ISO_8859_2);
     }
 
+    #[cfg(feature = "central")]
     #[test]
     fn test_pl() {
         check("To jest test kodowania znaków. W przypadku niektórych języków, które używają znaków łacińskich, potrzebujemy więcej danych, aby podjąć decyzję.", WINDOWS_1250);
     }
 
+    #[cfg(feature = "baltic")]
     #[test]
     fn test_lt() {
         check("Tai simbolių kodavimo testas. Kai kurioms kalboms, naudojančioms lotyniškus rašmenis, mums reikia daugiau informacijos, kad galėtume priimti sprendimą.", WINDOWS_1257);
@@ -3549,11 +5038,13 @@ mod tests {
     //     check("Tai simbolių kodavimo testas. Kai kurioms kalboms, naudojančioms lotyniškus rašmenis, mums reikia daugiau informacijos, kad galėtume priimti sprendimą.", ISO_8859_4);
     // }
 
+    #[cfg(feature = "baltic")]
     #[test]
     fn test_lv() {
         check("Šis ir rakstzīmju kodēšanas tests. Dažās valodās, kurās tiek izmantotas latīņu valodas burti, lēmuma pieņemšanai mums ir nepieciešams vairāk ieguldījuma.", WINDOWS_1257);
     }
 
+    #[cfg(feature = "baltic")]
     #[test]
     fn test_lv_iso_8859_4() {
         check("Šis ir rakstzīmju kodēšanas tests. Dažās valodās, kurās tiek izmantotas latīņu valodas burti, lēmuma pieņemšanai mums ir nepieciešams vairāk ieguldījuma.", ISO_8859_4);
@@ -3651,11 +5142,13 @@ mod tests {
         check(" €9", WINDOWS_1252);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_shift_jis_half_width_katakana() {
         check("ﾊｰﾄﾞｳｪｱﾊｰﾄﾞｳｪｱﾊｰﾄﾞｳｪｱﾊｰﾄﾞｳｪｱﾊｰﾄﾞｳｪｱ", SHIFT_JIS);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_big5_pua() {
         let mut v = Vec::new();
@@ -3666,6 +5159,7 @@ mod tests {
         check_bytes(&v, BIG5);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_big5_single_byte_a0() {
         let mut v = Vec::new();
@@ -3676,6 +5170,7 @@ mod tests {
         check_bytes(&v, BIG5);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_big5_single_byte_ff() {
         let mut v = Vec::new();
@@ -3686,6 +5181,7 @@ mod tests {
         check_bytes(&v, BIG5);
     }
 
+    #[cfg(all(feature = "cjk", feature = "cyrillic"))]
     #[test]
     fn test_not_big5() {
         let mut v = Vec::new();
@@ -3696,6 +5192,7 @@ mod tests {
         check_bytes(&v, IBM866);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_kr_pua() {
         let mut v = Vec::new();
@@ -3706,6 +5203,7 @@ mod tests {
         check_bytes(&v, EUC_KR);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_kr_pua_bis() {
         let mut v = Vec::new();
@@ -3716,6 +5214,7 @@ mod tests {
         check_bytes(&v, EUC_KR);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_kr_single_byte_ff() {
         let mut v = Vec::new();
@@ -3726,6 +5225,7 @@ mod tests {
         check_bytes(&v, EUC_KR);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_kr_single_byte_81() {
         let mut v = Vec::new();
@@ -3736,6 +5236,7 @@ mod tests {
         check_bytes(&v, EUC_KR);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_kr_single_byte_84() {
         let mut v = Vec::new();
@@ -3746,6 +5247,7 @@ mod tests {
         check_bytes(&v, EUC_KR);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_not_euc_kr() {
         let mut v = Vec::new();
@@ -3756,6 +5258,7 @@ mod tests {
         check_bytes(&v, GBK);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_shift_jis_x0213() {
         let mut v = Vec::new();
@@ -3766,6 +5269,7 @@ mod tests {
         check_bytes(&v, SHIFT_JIS);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_shift_jis_single_byte_fd() {
         let mut v = Vec::new();
@@ -3776,6 +5280,7 @@ mod tests {
         check_bytes(&v, SHIFT_JIS);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_not_shift_jis() {
         let mut v = Vec::new();
@@ -3786,6 +5291,7 @@ mod tests {
         check_bytes(&v, GBK);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_not_shift_jis_bis() {
         let mut v = Vec::new();
@@ -3796,6 +5302,7 @@ mod tests {
         check_bytes(&v, GBK);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_jp_x0213() {
         let mut v = Vec::new();
@@ -3806,6 +5313,7 @@ mod tests {
         check_bytes(&v, EUC_JP);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_euc_jp_x0213_other_plane() {
         let mut v = Vec::new();
@@ -3826,6 +5334,7 @@ mod tests {
         check_bytes(&v, WINDOWS_1252);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_not_euc_jp_bis() {
         let mut v = Vec::new();
@@ -3836,6 +5345,7 @@ mod tests {
         check_bytes(&v, BIG5);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_gbk_single_byte_ff() {
         let mut v = Vec::new();
@@ -3846,6 +5356,7 @@ mod tests {
         check_bytes(&v, GBK);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_gbk_single_byte_a0() {
         let mut v = Vec::new();
@@ -3856,6 +5367,7 @@ mod tests {
         check_bytes(&v, GBK);
     }
 
+    #[cfg(feature = "cjk")]
     #[test]
     fn test_gbk_single_byte_fe() {
         let mut v = Vec::new();
@@ -3866,6 +5378,7 @@ mod tests {
         check_bytes(&v, GBK);
     }
 
+    #[cfg(all(feature = "cjk", feature = "cyrillic"))]
     #[test]
     fn test_not_gbk_single_byte_fc() {
         let mut v = Vec::new();
@@ -0,0 +1,666 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Front-ends for email messages and headers, available with the `alloc`
+//! feature.
+//!
+//! The parsing is deliberately lenient, as archived mail is often not
+//! quite well-formed: header names and parameters are matched
+//! case-insensitively, malformed quoted-printable escapes are kept as-is,
+//! and non-alphabet bytes in base64 are skipped.
+
+use crate::EncodingDetector;
+use alloc::string::String;
+use alloc::vec::Vec;
+use encoding_rs::DecoderResult;
+use encoding_rs::Encoding;
+use encoding_rs::UTF_8;
+
+/// How deeply multipart entities are followed.
+const MAX_DEPTH: usize = 8;
+
+/// How the declared `charset` parameter relates to the body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharsetCheck {
+    /// There was no `charset` parameter or its label is unknown.
+    Undeclared,
+    /// The detector guessed the declared encoding.
+    Agrees,
+    /// The declared encoding is possible, but the detector guessed
+    /// another one.
+    Disagrees,
+    /// The body is malformed in the declared encoding, the detector ruled
+    /// the declared encoding out, or the body is non-ASCII UTF-8 but
+    /// declared as something else.
+    Contradicted,
+}
+
+/// The outcome of `MimeDetector::guess()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MimeGuess {
+    /// The encoding named by the `charset` parameter of the first text
+    /// part, if any.
+    pub declared: Option<&'static Encoding>,
+    /// The encoding guessed by the detector.
+    pub detected: &'static Encoding,
+    /// The Boolean returned by `guess_assess()`.
+    pub confident: bool,
+    /// How `declared` relates to the body.
+    pub check: CharsetCheck,
+}
+
+impl MimeGuess {
+    /// Returns the declared encoding unless `check` shows that it is
+    /// missing or wrong, and the detected encoding otherwise.
+    pub fn encoding(&self) -> &'static Encoding {
+        match (self.check, self.declared) {
+            (CharsetCheck::Agrees, Some(declared)) | (CharsetCheck::Disagrees, Some(declared)) => {
+                declared
+            }
+            _ => self.detected,
+        }
+    }
+}
+
+/// Wraps an `EncodingDetector` for email messages whose `charset`
+/// parameter is missing or may be wrong.
+///
+/// The quoted-printable and base64 transfer encodings of the text parts
+/// are undone, and each decoded part is fed as a fragment (see
+/// `EncodingDetector::feed_fragment()`). Non-text parts, such as
+/// attachments, are skipped.
+pub struct MimeDetector {
+    detector: EncodingDetector,
+    declared: Option<&'static Encoding>,
+    malformed_in_declared: bool,
+}
+
+impl MimeDetector {
+    /// Creates a new instance with a new `EncodingDetector`.
+    pub fn new() -> Self {
+        MimeDetector::with_detector(EncodingDetector::new())
+    }
+
+    /// Creates a new instance that feeds `detector`, which may have been
+    /// configured, e.g. with a budget, but must not have been fed yet.
+    pub fn with_detector(detector: EncodingDetector) -> Self {
+        MimeDetector {
+            detector,
+            declared: None,
+            malformed_in_declared: false,
+        }
+    }
+
+    /// Returns the wrapped detector for calling its other methods, such
+    /// as `ranked_candidates()`.
+    pub fn detector(&self) -> &EncodingDetector {
+        &self.detector
+    }
+
+    /// Feeds a whole message (headers and body) and ends the stream.
+    ///
+    /// # Panics
+    ///
+    /// If this method has previously been called.
+    pub fn feed_message(&mut self, message: &[u8]) {
+        self.feed_entity(message, 0);
+        self.detector.feed(b"", true);
+    }
+
+    /// Returns the guess and how it relates to the declared `charset`.
+    /// The arguments work the same way as for `EncodingDetector::guess()`.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters.
+    pub fn guess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> MimeGuess {
+        let (detected, confident) = self.detector.guess_assess(tld, allow_utf8);
+        let check = match self.declared {
+            None => CharsetCheck::Undeclared,
+            Some(declared) => {
+                let utf_8 = self.detector.non_ascii_count() != 0 && !self.detector.rules_out(UTF_8);
+                if self.malformed_in_declared
+                    || self.detector.rules_out(declared)
+                    || (utf_8 && declared != UTF_8)
+                {
+                    CharsetCheck::Contradicted
+                } else if declared == detected {
+                    CharsetCheck::Agrees
+                } else {
+                    CharsetCheck::Disagrees
+                }
+            }
+        };
+        MimeGuess {
+            declared: self.declared,
+            detected,
+            confident,
+            check,
+        }
+    }
+
+    fn feed_entity(&mut self, entity: &[u8], depth: usize) {
+        let (headers, body) = split_headers(entity);
+        let content_type = header_value(headers, b"content-type").unwrap_or_default();
+        let media_type = trim_ascii(
+            content_type
+                .split(|&b| b == b';')
+                .next()
+                .unwrap_or_default(),
+        )
+        .to_ascii_lowercase();
+        if media_type.starts_with(b"multipart/") {
+            if let Some(boundary) = parameter(&content_type, b"boundary") {
+                if depth < MAX_DEPTH {
+                    for part in multipart_parts(body, boundary) {
+                        self.feed_entity(part, depth + 1);
+                    }
+                }
+            }
+            return;
+        }
+        // Without `Content-Type`, the default is `text/plain`.
+        if !media_type.is_empty() && !media_type.starts_with(b"text/") {
+            return;
+        }
+        let transfer_encoding =
+            trim_ascii(&header_value(headers, b"content-transfer-encoding").unwrap_or_default())
+                .to_ascii_lowercase();
+        let decoded = match &transfer_encoding[..] {
+            b"quoted-printable" => decode_quoted_printable(body),
+            b"base64" => decode_base64(body),
+            _ => body.to_vec(),
+        };
+        let declared = parameter(&content_type, b"charset").and_then(Encoding::for_label);
+        if let Some(declared) = declared {
+            if self.declared.is_none() {
+                self.declared = Some(declared);
+            }
+            if !decodes_cleanly(declared, &decoded) {
+                self.malformed_in_declared = true;
+            }
+        }
+        self.detector.feed_fragment(&decoded, false);
+    }
+}
+
+impl Default for MimeDetector {
+    fn default() -> Self {
+        MimeDetector::new()
+    }
+}
+
+/// A header value decoded by `HeaderDetector::decode()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedHeader {
+    /// The decoded value.
+    pub text: String,
+    /// The encoding used for the raw 8-bit bytes and for the encoded
+    /// words whose labels are wrong.
+    pub detected: &'static Encoding,
+    /// The number of encoded words whose labels were wrong.
+    pub corrected_words: usize,
+}
+
+/// Detects the encoding of raw 8-bit bytes in email header values and
+/// corrects wrongly labeled RFC 2047 encoded words.
+///
+/// Header values are short, so evidence is pooled: feed all the values
+/// of a message (e.g. `Subject`, `From`, and `To`), which the same mail
+/// client is likely to have written in the same encoding, and then
+/// decode each one.
+pub struct HeaderDetector {
+    detector: EncodingDetector,
+}
+
+impl HeaderDetector {
+    /// Creates a new instance with a new `EncodingDetector` in the
+    /// short-string mode (see `EncodingDetector::set_short_strings()`),
+    /// since header values are often only a word or two.
+    pub fn new() -> Self {
+        let mut detector = EncodingDetector::new();
+        detector.set_short_strings(true);
+        HeaderDetector { detector }
+    }
+
+    /// Returns the wrapped detector for calling its other methods, such
+    /// as `ranked_candidates()`.
+    pub fn detector(&self) -> &EncodingDetector {
+        &self.detector
+    }
+
+    /// Feeds a header value (the unfolded bytes after the colon). The
+    /// raw runs and the decoded bytes of each encoded word are fed as
+    /// fragments (see `EncodingDetector::feed_fragment()`).
+    pub fn feed_header(&mut self, value: &[u8]) {
+        for piece in pieces(value) {
+            match piece {
+                Piece::Raw(raw) => self.detector.feed_fragment(raw, false),
+                Piece::Word(_, bytes) => self.detector.feed_fragment(&bytes, false),
+            };
+        }
+    }
+
+    /// Decodes a header value, which may or may not have been fed. Raw
+    /// bytes are decoded as the guessed encoding. So are encoded words if
+    /// their label is unknown or says ASCII for non-ASCII, if they are
+    /// malformed in the labeled encoding, or if they are non-ASCII UTF-8
+    /// labeled as something else. The arguments work the same way as for
+    /// `EncodingDetector::guess()`.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters.
+    pub fn decode(&self, value: &[u8], tld: Option<&[u8]>, allow_utf8: bool) -> DecodedHeader {
+        let detected = self.detector.guess(tld, allow_utf8);
+        let mut text = String::new();
+        let mut corrected_words = 0;
+        let pieces = pieces(value);
+        for (i, piece) in pieces.iter().enumerate() {
+            match piece {
+                Piece::Raw(raw) => {
+                    // Whitespace between encoded words is not part of the
+                    // text.
+                    let between_words = i != 0
+                        && matches!(pieces.get(i + 1), Some(Piece::Word(_, _)))
+                        && raw.iter().all(u8::is_ascii_whitespace);
+                    if !between_words {
+                        text.push_str(&detected.decode_without_bom_handling(raw).0);
+                    }
+                }
+                Piece::Word(label, bytes) => {
+                    let encoding = word_encoding(label, bytes).unwrap_or_else(|| {
+                        corrected_words += 1;
+                        detected
+                    });
+                    text.push_str(&encoding.decode_without_bom_handling(bytes).0);
+                }
+            }
+        }
+        DecodedHeader {
+            text,
+            detected,
+            corrected_words,
+        }
+    }
+}
+
+impl Default for HeaderDetector {
+    fn default() -> Self {
+        HeaderDetector::new()
+    }
+}
+
+/// `<[u8]>::trim_ascii()` without requiring Rust 1.80.
+fn trim_ascii(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
+
+/// Splits an entity at the first empty line.
+fn split_headers(entity: &[u8]) -> (&[u8], &[u8]) {
+    let mut line_start = 0;
+    for (i, &b) in entity.iter().enumerate() {
+        if b == b'\n' {
+            let line = &entity[line_start..i];
+            if line.is_empty() || line == b"\r" {
+                return (&entity[..line_start], &entity[i + 1..]);
+            }
+            line_start = i + 1;
+        }
+    }
+    (entity, b"")
+}
+
+/// Returns the unfolded value of the first header called `name`, which
+/// must be lower-case.
+fn header_value(headers: &[u8], name: &[u8]) -> Option<Vec<u8>> {
+    let mut value: Option<Vec<u8>> = None;
+    for line in headers.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            if let Some(value) = value.as_mut() {
+                value.extend_from_slice(line);
+                continue;
+            }
+        } else if value.is_some() {
+            break;
+        }
+        if let Some(colon) = memchr::memchr(b':', line) {
+            if trim_ascii(&line[..colon]).eq_ignore_ascii_case(name) {
+                value = Some(line[colon + 1..].to_vec());
+            }
+        }
+    }
+    value
+}
+
+/// Returns the value of the parameter called `name`, which must be
+/// lower-case, without quotes.
+fn parameter<'a>(value: &'a [u8], name: &[u8]) -> Option<&'a [u8]> {
+    value.split(|&b| b == b';').skip(1).find_map(|param| {
+        let eq = memchr::memchr(b'=', param)?;
+        if !trim_ascii(&param[..eq]).eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value = trim_ascii(&param[eq + 1..]);
+        Some(
+            value
+                .strip_prefix(b"\"")
+                .and_then(|v| v.strip_suffix(b"\""))
+                .unwrap_or(value),
+        )
+    })
+}
+
+/// Returns the parts between the delimiter lines of a multipart body.
+fn multipart_parts<'a>(body: &'a [u8], boundary: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    let mut part_start = None;
+    let mut line_start = 0;
+    while line_start < body.len() {
+        let line_end =
+            memchr::memchr(b'\n', &body[line_start..]).map_or(body.len(), |i| line_start + i + 1);
+        let line = &body[line_start..line_end];
+        if let Some(rest) = line
+            .strip_prefix(b"--")
+            .and_then(|rest| rest.strip_prefix(boundary))
+        {
+            if let Some(start) = part_start {
+                parts.push(&body[start..line_start]);
+            }
+            if rest.starts_with(b"--") {
+                break;
+            }
+            part_start = Some(line_end);
+        }
+        line_start = line_end;
+    }
+    parts
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        _ => None,
+    }
+}
+
+fn decode_quoted_printable(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] == b'=' {
+            match input.get(i + 1..i + 3) {
+                Some(b"\r\n") => {
+                    i += 3;
+                    continue;
+                }
+                Some(&[high, low]) => {
+                    if let (Some(high), Some(low)) = (hex_value(high), hex_value(low)) {
+                        output.push(high << 4 | low);
+                        i += 3;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            // Soft line break
+            if input.get(i + 1) == Some(&b'\n') {
+                i += 2;
+                continue;
+            }
+        }
+        output.push(input[i]);
+        i += 1;
+    }
+    output
+}
+
+fn decode_base64(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &b in input {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+        acc = acc << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    output
+}
+
+fn decodes_cleanly(encoding: &'static Encoding, bytes: &[u8]) -> bool {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut dst = [0u8; 1024];
+    let mut total_read = 0;
+    loop {
+        let (result, read, _) =
+            decoder.decode_to_utf8_without_replacement(&bytes[total_read..], &mut dst, true);
+        total_read += read;
+        match result {
+            DecoderResult::InputEmpty => return true,
+            DecoderResult::Malformed(_, _) => return false,
+            DecoderResult::OutputFull => {}
+        }
+    }
+}
+
+/// A run of a header value outside encoded words or the label and the
+/// decoded bytes of an encoded word.
+enum Piece<'a> {
+    Raw(&'a [u8]),
+    Word(&'a [u8], Vec<u8>),
+}
+
+/// Splits a header value into raw runs and encoded words.
+fn pieces(value: &[u8]) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut raw_start = 0;
+    let mut i = 0;
+    while let Some(offset) = memchr::memchr(b'=', &value[i..]) {
+        i += offset;
+        if let Some((label, bytes, len)) = encoded_word(&value[i..]) {
+            if raw_start < i {
+                pieces.push(Piece::Raw(&value[raw_start..i]));
+            }
+            pieces.push(Piece::Word(label, bytes));
+            i += len;
+            raw_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    if raw_start < value.len() {
+        pieces.push(Piece::Raw(&value[raw_start..]));
+    }
+    pieces
+}
+
+/// Parses an encoded word (`=?charset?B?...?=` or `=?charset?Q?...?=`) at
+/// the start of `input` and returns the label, the decoded bytes, and the
+/// length of the word.
+fn encoded_word(input: &[u8]) -> Option<(&[u8], Vec<u8>, usize)> {
+    let rest = input.strip_prefix(b"=?")?;
+    let mut parts = rest.splitn(3, |&b| b == b'?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let text_and_rest = parts.next()?;
+    let end = text_and_rest.windows(2).position(|w| w == b"?=")?;
+    let text = &text_and_rest[..end];
+    if charset.is_empty() || [charset, text].iter().any(|s| s.iter().any(|&b| b <= b' ')) {
+        return None;
+    }
+    // RFC 2231 allows a language after an asterisk.
+    let label = charset.split(|&b| b == b'*').next()?;
+    let bytes = match encoding {
+        b"B" | b"b" => decode_base64(text),
+        b"Q" | b"q" => {
+            let mut text = text.to_vec();
+            for b in text.iter_mut() {
+                if *b == b'_' {
+                    *b = b' ';
+                }
+            }
+            decode_quoted_printable(&text)
+        }
+        _ => return None,
+    };
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    Some((label, bytes, len))
+}
+
+/// Returns the encoding that the label of an encoded word names unless
+/// the decoded bytes show that the label is wrong.
+fn word_encoding(label: &[u8], bytes: &[u8]) -> Option<&'static Encoding> {
+    let encoding = Encoding::for_label(label)?;
+    if !bytes.is_ascii() {
+        // encoding_rs maps ASCII labels to windows-1252.
+        if label.eq_ignore_ascii_case(b"us-ascii") || label.eq_ignore_ascii_case(b"ascii") {
+            return None;
+        }
+        if encoding != UTF_8 && decodes_cleanly(UTF_8, bytes) {
+            return None;
+        }
+    }
+    if decodes_cleanly(encoding, bytes) {
+        Some(encoding)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "greek")]
+    use encoding_rs::ISO_8859_7;
+    #[cfg(feature = "cyrillic")]
+    use encoding_rs::WINDOWS_1251;
+    use encoding_rs::WINDOWS_1252;
+
+    #[test]
+    fn test_mime_detector() {
+        let guess = |message: &[u8]| {
+            let mut det = MimeDetector::new();
+            det.feed_message(message);
+            det.guess(None, true)
+        };
+
+        let french = b"Subject: test\r\nContent-Transfer-Encoding: quoted-printable\r\n\r\n\
+Caf=E9 cr=E8me =E0 la fran=E7aise, c'est l'=E9t=E9.=\r\n Voil=E0.\r\n";
+        let g = guess(french);
+        assert_eq!(g.check, CharsetCheck::Undeclared);
+        assert_eq!(g.encoding(), WINDOWS_1252);
+
+        // Base64 of UTF-8 mislabeled as ISO-8859-1.
+        let g = guess(
+            b"Content-Type: text/plain;\r\n charset=\"ISO-8859-1\"\r\n\
+Content-Transfer-Encoding: base64\r\n\r\n0J/RgNC40LLQtdGCLCDQvNC4\r\n0YAh\r\n",
+        );
+        assert_eq!(g.declared, Some(WINDOWS_1252));
+        assert_eq!(g.check, CharsetCheck::Contradicted);
+        assert_eq!(g.encoding(), UTF_8);
+
+        // windows-1252 mislabeled as UTF-8.
+        let g = guess(b"Content-Type: text/plain; charset=utf-8\n\nCaf\xE9 cr\xE8me\n");
+        assert_eq!(g.check, CharsetCheck::Contradicted);
+        assert_eq!(g.encoding(), WINDOWS_1252);
+
+        // The attachment does not count.
+        let g = guess(
+            b"Content-Type: multipart/mixed; boundary=xyz\n\npreamble\n--xyz\n\
+Content-Type: text/plain; charset=windows-1252\n\
+Content-Transfer-Encoding: quoted-printable\n\nCaf=E9 cr=E8me =E0 la fran=E7aise\n\
+--xyz\nContent-Type: application/octet-stream\nContent-Transfer-Encoding: base64\n\n\
+0J/RgNC40LLQtdGC\n--xyz--\n",
+        );
+        assert_eq!(g.check, CharsetCheck::Agrees);
+        assert_eq!(g.encoding(), WINDOWS_1252);
+    }
+
+    #[cfg(feature = "cyrillic")]
+    #[test]
+    fn test_header_detector() {
+        let (subject, _, _) = WINDOWS_1251.encode("Привет, как дела?");
+        let (from, _, _) = WINDOWS_1251.encode("Иван Петров <ivan@example.com>");
+        let mut det = HeaderDetector::new();
+        det.feed_header(&subject);
+        det.feed_header(&from);
+        let decoded = det.decode(&subject, Some(b"ru"), false);
+        assert_eq!(decoded.text, "Привет, как дела?");
+        assert_eq!(decoded.detected, WINDOWS_1251);
+        assert_eq!(decoded.corrected_words, 0);
+
+        // Whitespace between encoded words is dropped.
+        let det = HeaderDetector::new();
+        let value = b"Re: =?utf-8?B?0J/RgNC4?= =?UTF-8?q?=D0=B2=D0=B5=D1=82_!?=";
+        let decoded = det.decode(value, None, true);
+        assert_eq!(decoded.text, "Re: Привет !");
+        assert_eq!(decoded.corrected_words, 0);
+
+        // UTF-8 labeled as ISO-8859-1.
+        let value = b"=?iso-8859-1?Q?=D0=9F=D1=80=D0=B8=D0=B2=D0=B5=D1=82?=";
+        let mut det = HeaderDetector::new();
+        det.feed_header(value);
+        let decoded = det.decode(value, None, true);
+        assert_eq!(decoded.corrected_words, 1);
+        assert_eq!(decoded.detected, UTF_8);
+        assert_eq!(decoded.text, "Привет");
+
+        // 8-bit labeled as US-ASCII.
+        let value = b"=?us-ascii?q?Caf=E9_cr=E8me?=";
+        let mut det = HeaderDetector::new();
+        det.feed_header(value);
+        let decoded = det.decode(value, None, false);
+        assert_eq!(decoded.corrected_words, 1);
+        assert_eq!(decoded.text, "Café crème");
+
+        // Not an encoded word.
+        let decoded = HeaderDetector::new().decode(b"=?x y?Q?a?= =?", None, true);
+        assert_eq!(decoded.text, "=?x y?Q?a?= =?");
+    }
+
+    #[cfg(feature = "greek")]
+    #[test]
+    fn test_header_detector_short_subject() {
+        // One letter per word is too little without the short-string mode.
+        let (subject, _, _) = ISO_8859_7.encode("Re: Ok, ο ή η");
+        let mut det = HeaderDetector::new();
+        det.feed_header(&subject);
+        let decoded = det.decode(&subject, Some(b"gr"), false);
+        assert_eq!(decoded.detected, ISO_8859_7);
+        assert_eq!(decoded.text, "Re: Ok, ο ή η");
+
+        let mut plain = EncodingDetector::new();
+        plain.feed(&subject, true);
+        assert_ne!(plain.guess(Some(b"gr"), false), ISO_8859_7);
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Single-byte models loaded at run time.
+//!
+//! The binary format (integers little-endian) is:
+//!
+//! * The magic number `CDNGMODL`.
+//! * Format version as `u16`, currently 1.
+//! * Number of tables as `u16`, currently 20.
+//! * The tables in the order of `SINGLE_BYTE_DATA`, each consisting of:
+//!   * Length of the encoding name as `u8` followed by the WHATWG name of
+//!     the encoding, which must match the built-in table at the same
+//!     position.
+//!   * Number of ASCII classes (including the space-like class 0) as `u8`.
+//!   * Number of non-ASCII classes as `u8`.
+//!   * 128 bytes of classes for bytes 0x00...0x7F.
+//!   * 128 bytes of classes for bytes 0x80...0xFF.
+//!   * Length of the pair scores as `u32` followed by the pair scores in
+//!     the layout of `data.rs`.
+//!
+//! `tools/train` writes this format with `chardetng_train binary`. A model
+//! always has all the tables, even if the crate is built with families
+//! compiled out. The built-in tables of such families are empty stand-ins
+//! with zero class counts, which this format rejects.
+
+use crate::data::SingleByteData;
+use crate::data::ASCII_DIGIT;
+use crate::data::SINGLE_BYTE_DATA;
+use core::convert::TryFrom;
+use core::fmt;
+
+const MAGIC: &[u8; 8] = b"CDNGMODL";
+
+const VERSION: u16 = 1;
+
+/// The number of unstored classes that follow the stored ones. See the
+/// constants at the top of `data.rs`.
+const UNSTORED_CLASSES: usize = 6;
+
+/// An error from `SingleByteModel::from_bytes`. The `usize` payloads are
+/// the index of the offending table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelError {
+    /// The data doesn't start with the magic number.
+    BadMagic,
+    /// The format version is not supported.
+    UnsupportedVersion(u16),
+    /// The number of tables isn't the number of built-in tables.
+    BadTableCount(u16),
+    /// The data ends in the middle of a table.
+    Truncated,
+    /// The encoding of the table doesn't match the built-in table.
+    EncodingMismatch(usize),
+    /// The class counts leave no room for the unstored classes.
+    BadClassCounts(usize),
+    /// A byte is classified into a class that doesn't exist.
+    BadClass(usize),
+    /// The length of the pair scores doesn't match the class counts.
+    BadScoreLength(usize),
+    /// There is data after the last table.
+    TrailingData,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ModelError::BadMagic => f.write_str("not a chardetng model"),
+            ModelError::UnsupportedVersion(v) => write!(f, "unsupported model version {}", v),
+            ModelError::BadTableCount(n) => write!(f, "wrong number of tables: {}", n),
+            ModelError::Truncated => f.write_str("truncated model"),
+            ModelError::EncodingMismatch(i) => write!(f, "table {}: wrong encoding", i),
+            ModelError::BadClassCounts(i) => write!(f, "table {}: bad class counts", i),
+            ModelError::BadClass(i) => write!(f, "table {}: class out of range", i),
+            ModelError::BadScoreLength(i) => write!(f, "table {}: bad pair score length", i),
+            ModelError::TrailingData => f.write_str("trailing data after the last table"),
+        }
+    }
+}
+
+/// Replacement single-byte models for `EncodingDetector::with_model`.
+///
+/// The model borrows its tables from the bytes it was loaded from without
+/// copying. Both need to live for the rest of the program, which, when
+/// the bytes are read at run time, means leaking them, e.g. with
+/// `Box::leak`.
+pub struct SingleByteModel {
+    pub(crate) data: [SingleByteData; 20],
+}
+
+struct Reader {
+    bytes: &'static [u8],
+}
+
+impl Reader {
+    fn take(&mut self, len: usize) -> Result<&'static [u8], ModelError> {
+        if len > self.bytes.len() {
+            return Err(ModelError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ModelError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ModelError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ModelError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn classes(&mut self) -> Result<&'static [u8; 128], ModelError> {
+        Ok(<&[u8; 128]>::try_from(self.take(128)?).unwrap())
+    }
+
+    fn table(&mut self, index: usize) -> Result<SingleByteData, ModelError> {
+        let builtin = &SINGLE_BYTE_DATA[index];
+        let name_len = usize::from(self.u8()?);
+        if self.take(name_len)? != builtin.encoding.name().as_bytes() {
+            return Err(ModelError::EncodingMismatch(index));
+        }
+        let ascii = usize::from(self.u8()?);
+        let non_ascii = usize::from(self.u8()?);
+        let stored = ascii + non_ascii;
+        // The unstored classes must stay below `ASCII_DIGIT`.
+        if ascii == 0 || non_ascii == 0 || stored + UNSTORED_CLASSES > ASCII_DIGIT {
+            return Err(ModelError::BadClassCounts(index));
+        }
+        let lower = self.classes()?;
+        let upper = self.classes()?;
+        let valid = |&class: &u8| {
+            class == 255
+                || usize::from(class) == ASCII_DIGIT
+                || usize::from(class & 0x7F) < stored + UNSTORED_CLASSES
+        };
+        if !lower.iter().all(valid) || !upper.iter().all(valid) {
+            return Err(ModelError::BadClass(index));
+        }
+        let len = self.u32()? as usize;
+        // See `compute_index` in `data.rs`.
+        if len != ascii * non_ascii + stored * non_ascii {
+            return Err(ModelError::BadScoreLength(index));
+        }
+        Ok(SingleByteData {
+            encoding: builtin.encoding,
+            lower,
+            upper,
+            probabilities: self.take(len)?,
+            ascii,
+            non_ascii,
+        })
+    }
+}
+
+impl SingleByteModel {
+    /// Validates and loads a model in the binary format documented in
+    /// `src/model.rs`.
+    ///
+    /// The validation guarantees that the detector can't panic or read
+    /// out of bounds with the model, but not that the model is any good.
+    pub fn from_bytes(bytes: &'static [u8]) -> Result<SingleByteModel, ModelError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len()).map_err(|_| ModelError::BadMagic)? != MAGIC {
+            return Err(ModelError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(ModelError::UnsupportedVersion(version));
+        }
+        let count = reader.u16()?;
+        if usize::from(count) != SINGLE_BYTE_DATA.len() {
+            return Err(ModelError::BadTableCount(count));
+        }
+        let model = SingleByteModel {
+            data: [
+                reader.table(0)?,
+                reader.table(1)?,
+                reader.table(2)?,
+                reader.table(3)?,
+                reader.table(4)?,
+                reader.table(5)?,
+                reader.table(6)?,
+                reader.table(7)?,
+                reader.table(8)?,
+                reader.table(9)?,
+                reader.table(10)?,
+                reader.table(11)?,
+                reader.table(12)?,
+                reader.table(13)?,
+                reader.table(14)?,
+                reader.table(15)?,
+                reader.table(16)?,
+                reader.table(17)?,
+                reader.table(18)?,
+                reader.table(19)?,
+            ],
+        };
+        if !reader.bytes.is_empty() {
+            return Err(ModelError::TrailingData);
+        }
+        Ok(model)
+    }
+}
+
+// The built-in tables of compiled-out families are empty stand-ins,
+// which the model format doesn't allow.
+#[cfg(all(
+    test,
+    feature = "cyrillic",
+    feature = "greek",
+    feature = "hebrew",
+    feature = "arabic",
+    feature = "thai",
+    feature = "baltic",
+    feature = "vietnamese",
+    feature = "central",
+    feature = "turkish"
+))]
+mod tests {
+    extern crate alloc;
+    use super::*;
+    use crate::data::WINDOWS_1252_INDEX;
+    use crate::EncodingDetector;
+    use alloc::vec::Vec;
+    use encoding_rs::Encoding;
+    use encoding_rs::ISO_8859_2;
+    use encoding_rs::KOI8_U;
+    use encoding_rs::WINDOWS_1252;
+    use encoding_rs::WINDOWS_1253;
+    use encoding_rs::WINDOWS_1254;
+    use encoding_rs::WINDOWS_1255;
+
+    fn builtin_model_bytes() -> &'static [u8] {
+        let mut v = Vec::new();
+        v.extend_from_slice(b"CDNGMODL");
+        v.extend_from_slice(&1u16.to_le_bytes());
+        v.extend_from_slice(&20u16.to_le_bytes());
+        for data in SINGLE_BYTE_DATA.iter() {
+            v.push(data.encoding.name().len() as u8);
+            v.extend_from_slice(data.encoding.name().as_bytes());
+            v.push(data.ascii as u8);
+            v.push(data.non_ascii as u8);
+            v.extend_from_slice(data.lower);
+            v.extend_from_slice(data.upper);
+            v.extend_from_slice(&(data.probabilities.len() as u32).to_le_bytes());
+            v.extend_from_slice(data.probabilities);
+        }
+        v.leak()
+    }
+
+    #[test]
+    fn test_with_model_matches_builtin() {
+        use alloc::boxed::Box;
+
+        let model = Box::leak(Box::new(
+            SingleByteModel::from_bytes(builtin_model_bytes()).unwrap(),
+        ));
+        let inputs: [(&str, &'static Encoding); 6] = [
+            ("Straße über Ödön", WINDOWS_1252),
+            ("Мама мыла раму", KOI8_U),
+            ("Příliš žluťoučký kůň", ISO_8859_2),
+            ("Καλημέρα κόσμε", WINDOWS_1253),
+            ("שלום עולם", WINDOWS_1255),
+            ("Ağaç ışığı", WINDOWS_1254),
+        ];
+        for &(text, encoding) in inputs.iter() {
+            let (bytes, _, _) = encoding.encode(text);
+            let mut builtin = EncodingDetector::new();
+            builtin.feed(&bytes, true);
+            let mut loaded = EncodingDetector::with_model(model);
+            loaded.feed(&bytes, true);
+            assert_eq!(
+                builtin.ranked_candidates(None).as_slice(),
+                loaded.ranked_candidates(None).as_slice()
+            );
+        }
+
+        // The DOS code pages are scored with the loaded windows-1252
+        // model, too.
+        let mut bytes = builtin_model_bytes().to_vec();
+        let table = SINGLE_BYTE_DATA[..WINDOWS_1252_INDEX]
+            .iter()
+            .fold(12, |offset, data| {
+                offset + 1 + data.encoding.name().len() + 2 + 256 + 4 + data.probabilities.len()
+            });
+        let probabilities = table + 1 + "windows-1252".len() + 2 + 256 + 4;
+        let len = SINGLE_BYTE_DATA[WINDOWS_1252_INDEX].probabilities.len();
+        bytes[probabilities..probabilities + len]
+            .iter_mut()
+            .for_each(|b| *b = 0);
+        let zeroed = Box::leak(Box::new(SingleByteModel::from_bytes(bytes.leak()).unwrap()));
+        let name = [0x81, 0x62, 0x65, 0x72, 0x20, 0x82, 0x6C, 0x6C, 0x65];
+        let dos_scores = |mut det: EncodingDetector| {
+            det.set_dos_code_pages(true);
+            det.feed(&name, true);
+            det.dos_scores(None)
+        };
+        assert_eq!(
+            dos_scores(EncodingDetector::with_model(model)),
+            dos_scores(EncodingDetector::new())
+        );
+        assert_ne!(
+            dos_scores(EncodingDetector::with_model(zeroed)),
+            dos_scores(EncodingDetector::new())
+        );
+    }
+
+    #[test]
+    fn test_model_validation() {
+        let bytes = builtin_model_bytes();
+        let load = |v: Vec<u8>| SingleByteModel::from_bytes(v.leak()).err();
+        assert_eq!(load(b"CDNG".to_vec()), Some(ModelError::BadMagic));
+        let mut v = bytes.to_vec();
+        v[8] = 2;
+        assert_eq!(load(v), Some(ModelError::UnsupportedVersion(2)));
+        let mut v = bytes.to_vec();
+        v[10] = 19;
+        assert_eq!(load(v), Some(ModelError::BadTableCount(19)));
+        assert_eq!(
+            load(bytes[..bytes.len() - 1].to_vec()),
+            Some(ModelError::Truncated)
+        );
+        let mut v = bytes.to_vec();
+        v.push(0);
+        assert_eq!(load(v), Some(ModelError::TrailingData));
+        // The first table is windows-1258: name length, 12 bytes of name,
+        // class counts, lower classes, upper classes, score length.
+        let mut v = bytes.to_vec();
+        v[13] = b'9';
+        assert_eq!(load(v), Some(ModelError::EncodingMismatch(0)));
+        let mut v = bytes.to_vec();
+        v[25] = 90;
+        assert_eq!(load(v), Some(ModelError::BadClassCounts(0)));
+        let mut v = bytes.to_vec();
+        v[27 + 128 + 5] = 99;
+        assert_eq!(load(v), Some(ModelError::BadClass(0)));
+        let mut v = bytes.to_vec();
+        v[27 + 256] ^= 1;
+        assert_eq!(load(v), Some(ModelError::BadScoreLength(0)));
+    }
+}
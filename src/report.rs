@@ -0,0 +1,185 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detection results that can be stored and compared later.
+//!
+//! With the `serde` feature, the types in this module implement
+//! `Serialize` and `Deserialize`. Encodings are represented by their
+//! WHATWG names.
+
+use crate::DosCodePage;
+use crate::RankedCandidates;
+use crate::TldClass;
+use encoding_rs::Encoding;
+
+/// The outcome of `EncodingDetector::guess_assess()` together with the
+/// TLD class it was computed for, the ranked candidates, and the DOS code
+/// page results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The guessed encoding.
+    #[cfg_attr(feature = "serde", serde(with = "encoding_name"))]
+    pub encoding: &'static Encoding,
+    /// The Boolean returned by `guess_assess()`.
+    pub confident: bool,
+    /// The class of the TLD that was passed to the detector.
+    pub tld_class: TldClass,
+    /// The candidates as returned by `ranked_candidates()`.
+    pub candidates: RankedCandidates,
+    /// The DOS code page that `guess_with_dos()` returns instead of
+    /// `encoding`, if any.
+    pub dos_code_page: Option<DosCodePage>,
+    /// The scores of CP437 and CP850 as returned by `dos_scores()`.
+    pub dos_scores: [Option<i64>; 2],
+}
+
+/// Serializes an encoding as its WHATWG name.
+#[cfg(feature = "serde")]
+pub(crate) mod encoding_name {
+    use core::fmt;
+    use encoding_rs::Encoding;
+    use serde::de::Error;
+    use serde::de::Unexpected;
+    use serde::de::Visitor;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(
+        encoding: &&'static Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(encoding.name())
+    }
+
+    struct NameVisitor;
+
+    impl<'de> Visitor<'de> for NameVisitor {
+        type Value = &'static Encoding;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("the WHATWG name of an encoding")
+        }
+
+        fn visit_str<E: Error>(self, name: &str) -> Result<&'static Encoding, E> {
+            // Labels are not accepted, only names.
+            match Encoding::for_label(name.as_bytes()) {
+                Some(encoding) if encoding.name() == name => Ok(encoding),
+                _ => Err(E::invalid_value(Unexpected::Str(name), &self)),
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<&'static Encoding, D::Error> {
+        deserializer.deserialize_str(NameVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod ranked {
+    use super::encoding_name;
+    use crate::RankedCandidates;
+    use core::fmt;
+    use encoding_rs::Encoding;
+    use encoding_rs::UTF_8;
+    use serde::de::Error;
+    use serde::de::SeqAccess;
+    use serde::de::Visitor;
+    use serde::ser::SerializeSeq;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        #[serde(with = "encoding_name")]
+        encoding: &'static Encoding,
+        score: i64,
+    }
+
+    /// A sequence of encoding and score objects, highest score first.
+    impl Serialize for RankedCandidates {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.len()))?;
+            for &(encoding, score) in self {
+                seq.serialize_element(&Entry { encoding, score })?;
+            }
+            seq.end()
+        }
+    }
+
+    struct RankedVisitor;
+
+    impl<'de> Visitor<'de> for RankedVisitor {
+        type Value = RankedCandidates;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a sequence of encoding and score objects")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RankedCandidates, A::Error> {
+            let mut ranked = RankedCandidates {
+                entries: [(UTF_8, 0); 27],
+                len: 0,
+            };
+            while let Some(entry) = seq.next_element::<Entry>()? {
+                if ranked.len == ranked.entries.len() {
+                    return Err(A::Error::invalid_length(ranked.len + 1, &self));
+                }
+                ranked.entries[ranked.len] = (entry.encoding, entry.score);
+                ranked.len += 1;
+            }
+            Ok(ranked)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for RankedCandidates {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_seq(RankedVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EncodingDetector;
+
+    #[test]
+    fn test_report() {
+        let mut det = EncodingDetector::new();
+        det.feed(b"Ma\xF0ur", true);
+        let report = det.report(Some(b"is"), false);
+        assert_eq!(
+            (report.encoding, report.confident),
+            det.guess_assess(Some(b"is"), false)
+        );
+        assert_eq!(report.tld_class, TldClass::IcelandicFaroese);
+        assert_eq!(report.candidates, det.ranked_candidates(Some(b"is")));
+        assert_eq!(EncodingDetector::tld_class(None), TldClass::Generic);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_report_serde() {
+        let mut det = EncodingDetector::new();
+        det.feed(b"Ma\xF0ur", true);
+        let report = det.report(Some(b"is"), false);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.starts_with(
+            "{\"encoding\":\"windows-1252\",\"confident\":true,\"tld_class\":\"IcelandicFaroese\",\"candidates\":[{\"encoding\":\"windows-1252\",\"score\":"
+        ));
+        assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report);
+        let label = json.replace("\"windows-1252\"", "\"latin1\"");
+        assert!(serde_json::from_str::<Report>(&label).is_err());
+    }
+}
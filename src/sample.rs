@@ -0,0 +1,198 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sampling of inputs that are too large to feed in full.
+//!
+//! The head, the middle, and the tail of the input are fed to the
+//! detector as fragments. Each window is moved to start and end after a
+//! line break, or failing that, after a space or a control character
+//! other than ESC, which starts ISO-2022-JP escape sequences. Those bytes
+//! are never part of a multi-byte character in UTF-8 or in the legacy CJK
+//! encodings.
+//!
+//! Dense CJK text may have none of those bytes near the edge of a window.
+//! Then the window is moved to after ASCII punctuation below 0x40, which
+//! the legacy CJK encodings don't use as trail bytes, and failing that, to
+//! the start of a UTF-8 sequence. Legacy CJK text without any ASCII near
+//! the edge can still be split mid-character.
+
+use encoding_rs::Encoding;
+
+/// The outcome of `EncodingDetector::sample()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// The guessed encoding.
+    pub encoding: &'static Encoding,
+    /// The Boolean returned by `guess_assess()`.
+    pub confident: bool,
+    /// The number of bytes that were fed to the detector.
+    pub examined: u64,
+    /// The length of the input.
+    pub total: u64,
+}
+
+impl Sample {
+    /// Returns `true` if the input was short enough to be fed in full.
+    pub fn is_complete(&self) -> bool {
+        self.examined == self.total
+    }
+}
+
+/// `len` bytes of the input at `offset`. Up to `slack()` bytes may be
+/// trimmed from the start and, when aligning the end, `slack()` bytes
+/// are already included in `len`.
+pub(crate) struct Window {
+    pub offset: u64,
+    pub len: usize,
+    pub align_start: bool,
+    pub align_end: bool,
+}
+
+/// How far a window may be moved to find a boundary.
+pub(crate) fn slack(window: usize) -> usize {
+    window / 4
+}
+
+/// Returns the head, middle, and tail windows or `None` if `total` is
+/// small enough for the input to be fed in full.
+pub(crate) fn windows(total: u64, window: usize) -> Option<[Window; 3]> {
+    let span = window + slack(window);
+    if total <= 3 * span as u64 {
+        return None;
+    }
+    Some([
+        Window {
+            offset: 0,
+            len: span,
+            align_start: false,
+            align_end: true,
+        },
+        Window {
+            offset: total / 2 - window as u64 / 2,
+            len: span,
+            align_start: true,
+            align_end: true,
+        },
+        Window {
+            offset: total - window as u64,
+            len: window,
+            align_start: true,
+            align_end: false,
+        },
+    ])
+}
+
+/// Returns the position of the first boundary in the `slack` bytes at
+/// `pos` or `pos` if there is none.
+fn boundary(buf: &[u8], pos: usize, slack: usize) -> usize {
+    let region = &buf[pos..core::cmp::min(pos + slack, buf.len())];
+    if let Some(i) = memchr::memchr(b'\n', region) {
+        return pos + i + 1;
+    }
+    if let Some(i) = region.iter().position(|&b| b <= b' ' && b != 0x1B) {
+        return pos + i + 1;
+    }
+    // The digits are excluded, since they are the second and the fourth
+    // byte of the four-byte sequences of GB18030.
+    if let Some(i) = region
+        .iter()
+        .position(|&b| b < 0x40 && b != 0x1B && !b.is_ascii_digit())
+    {
+        return pos + i + 1;
+    }
+    if let Some(i) = region.iter().position(|&b| b & 0xC0 != 0x80) {
+        return pos + i;
+    }
+    pos
+}
+
+/// Returns the part of `buf`, which holds the bytes of `window`, to feed.
+pub(crate) fn trim<'a>(buf: &'a [u8], window: &Window, slack: usize) -> &'a [u8] {
+    let start = if window.align_start {
+        boundary(buf, 0, slack)
+    } else {
+        0
+    };
+    let end = if window.align_end {
+        boundary(buf, buf.len() - slack, slack)
+    } else {
+        buf.len()
+    };
+    &buf[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use crate::EncodingDetector;
+    use alloc::vec::Vec;
+    #[cfg(feature = "cjk")]
+    use encoding_rs::GBK;
+    use encoding_rs::UTF_8;
+
+    fn sample_input() -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..200 {
+            data.extend_from_slice(alloc::format!("{} Привет, мир!\n", i).as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_sample() {
+        let data = sample_input();
+        let sample = EncodingDetector::new().sample(&data, 256, None, true);
+        assert_eq!(sample.encoding, UTF_8);
+        assert!(sample.examined < sample.total);
+        assert_eq!(sample.total, data.len() as u64);
+        assert!(!sample.is_complete());
+
+        let short = &data[..=data[..500].iter().rposition(|&b| b == b'\n').unwrap()];
+        let sample = EncodingDetector::new().sample(short, 256, None, true);
+        assert_eq!(sample.encoding, UTF_8);
+        assert!(sample.is_complete());
+    }
+
+    #[test]
+    fn test_sample_dense_cjk() {
+        // No spaces or line breaks, and the middle and tail windows start
+        // inside a character.
+        let text = "中文字符编码检测".repeat(200);
+        let data = text.as_bytes();
+        assert_ne!((data.len() / 2 - 128) % 3, 0);
+        assert_ne!((data.len() - 256) % 3, 0);
+        let sample = EncodingDetector::new().sample(data, 256, None, true);
+        assert!(!sample.is_complete());
+        assert_eq!((sample.encoding, sample.confident), (UTF_8, true));
+
+        #[cfg(feature = "cjk")]
+        {
+            // ASCII punctuation but no spaces.
+            let text = "中文字符编码检测(简体):".repeat(150);
+            let (gbk, _, _) = GBK.encode(&text);
+            let sample = EncodingDetector::new().sample(&gbk, 256, None, false);
+            assert!(!sample.is_complete());
+            assert_eq!(sample.encoding, GBK);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sample_reader() {
+        let data = sample_input();
+        let mut reader = std::io::Cursor::new(&data);
+        let sample = EncodingDetector::new()
+            .sample_reader(&mut reader, 256, None, true)
+            .unwrap();
+        assert_eq!(
+            sample,
+            EncodingDetector::new().sample(&data, 256, None, true)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detection for batches of short strings.
+
+use crate::dos::NameEncoding;
+use crate::EncodingDetector;
+use encoding_rs::Encoding;
+
+/// Wraps an `EncodingDetector` in the short-string mode (see
+/// `EncodingDetector::set_short_strings()`) for a batch of related
+/// strings that are likely in the same encoding, such as the entry names
+/// of one ZIP or tar archive or the tags of one MP3 file.
+///
+/// Each string on its own is usually too short for a guess, so the
+/// evidence is pooled: every string is fed as a fragment (see
+/// `EncodingDetector::feed_fragment()`), and the guess is made from the
+/// whole batch.
+///
+/// For archive entry names, the DOS code pages that encoding_rs doesn't
+/// support can be considered, too; see `set_dos_code_pages()`.
+pub struct ShortStringDetector {
+    detector: EncodingDetector,
+}
+
+wrapper! {
+    ShortStringDetector,
+    /// Creates a new instance for one batch with a new `EncodingDetector`.
+    fn new;
+    /// Returns the wrapped detector, which has seen every string of the
+    /// batch as a fragment, for calling its other methods, such as
+    /// `ranked_candidates()`.
+    fn detector;
+}
+
+impl ShortStringDetector {
+    /// Creates a new instance that pools the batch in `detector`, which
+    /// may have been configured but must not have been fed yet, and
+    /// switches it to the short-string mode.
+    pub fn with_detector(mut detector: EncodingDetector) -> Self {
+        detector.set_short_strings(true);
+        ShortStringDetector { detector }
+    }
+
+    /// Makes the batch be scored as CP437 and CP850, too, which is off by
+    /// default. Has to be called before feeding. Same as
+    /// `EncodingDetector::set_dos_code_pages()` on the wrapped detector.
+    pub fn set_dos_code_pages(&mut self, enabled: bool) {
+        self.detector.set_dos_code_pages(enabled);
+    }
+
+    /// Adds a string to the batch. Returns `true` if the batch has
+    /// contained at least one non-ASCII byte so far.
+    pub fn feed(&mut self, string: &[u8]) -> bool {
+        self.detector.feed_fragment(string, false)
+    }
+
+    /// Same as `EncodingDetector::guess()` for the whole batch.
+    pub fn guess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> &'static Encoding {
+        self.detector.guess(tld, allow_utf8)
+    }
+
+    /// Same as `EncodingDetector::guess_assess()` for the whole batch.
+    pub fn guess_assess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> (&'static Encoding, bool) {
+        self.detector.guess_assess(tld, allow_utf8)
+    }
+
+    /// Same as `EncodingDetector::guess_with_dos()` for the whole batch.
+    pub fn guess_with_dos(&self, tld: Option<&[u8]>, allow_utf8: bool) -> (NameEncoding, bool) {
+        self.detector.guess_with_dos(tld, allow_utf8)
+    }
+}
+
+#[cfg(all(test, feature = "cyrillic"))]
+mod tests {
+    use super::*;
+    use encoding_rs::WINDOWS_1251;
+
+    #[test]
+    fn test_short_strings() {
+        let names = ["Отчёт.doc", "фото 1.jpg", "Мама.png", "Новая папка", "ёж"];
+        let mut batch = ShortStringDetector::new();
+        for name in names.iter() {
+            let (bytes, _, _) = WINDOWS_1251.encode(name);
+            batch.feed(&bytes);
+        }
+        assert_eq!(batch.guess(None, false), WINDOWS_1251);
+        // Individually, some names are too short.
+        let mut det = EncodingDetector::new();
+        det.feed(&WINDOWS_1251.encode("Мама.png").0, true);
+        assert_ne!(det.guess(None, false), WINDOWS_1251);
+
+        // One-letter words count in the short-string mode.
+        let ranks_1251 = |short_strings| {
+            let mut det = EncodingDetector::new();
+            det.set_short_strings(short_strings);
+            det.feed(&WINDOWS_1251.encode("я.txt").0, true);
+            det.ranked_candidates(None)
+                .into_iter()
+                .any(|&(encoding, _)| encoding == WINDOWS_1251)
+        };
+        assert!(!ranks_1251(false));
+        assert!(ranks_1251(true));
+    }
+}
@@ -0,0 +1,378 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A front-end for SubRip (SRT) and SubStation Alpha (SSA/ASS) subtitles.
+//!
+//! The file is processed line by line on bytes, so it works for any
+//! ASCII-compatible encoding. Lines that consist of digits and timecode
+//! punctuation only, i.e. SRT cue numbers and timings, are dropped. Of
+//! SSA `Dialogue:` and `Comment:` lines, only the text field, which
+//! follows the ninth comma, is kept. Other SSA lines, such as section
+//! headers and styles, are ASCII in practice and are fed as they are.
+
+use crate::wrap;
+use crate::wrap::Router;
+use crate::EncodingDetector;
+use encoding_rs::Encoding;
+
+/// How many bytes of a line are kept before it is known whether the
+/// line is text.
+const PENDING_LEN: usize = 32;
+
+/// The outcome of `SubtitleDetector::guess()` for one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubtitleGuess {
+    /// The encoding of the file.
+    pub encoding: &'static Encoding,
+    /// The Boolean returned by `guess_assess()` or `true` if the encoding
+    /// comes from a byte order mark.
+    pub confident: bool,
+    /// The length of the byte order mark to skip when decoding, e.g.
+    /// with `Encoding::decode_without_bom_handling()`, or zero.
+    pub bom_length: usize,
+    /// The number of SRT timing lines and SSA `Dialogue:` lines. Zero
+    /// suggests that the file isn't a subtitle file (or is UTF-16).
+    pub cues: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    /// Only digits and timecode punctuation so far.
+    Pending,
+    /// The given number of bytes of `Dialogue:` or `Comment:` matched.
+    Keyword(&'static [u8], usize),
+    /// The number of commas seen in the fields before the text.
+    Fields(u8),
+    Text,
+    /// Inside `<...>` or `{...}`, with the closing byte.
+    Tag(u8),
+    /// After a backslash in text.
+    Backslash,
+    /// The rest of a timing line.
+    Skip,
+}
+
+fn is_timecode_byte(b: u8) -> bool {
+    matches!(
+        b,
+        b'0'..=b'9' | b':' | b',' | b'.' | b'-' | b'>' | b' ' | b'\t' | b'\r'
+    )
+}
+
+/// Wraps an `EncodingDetector` for subtitle files.
+///
+/// Cue numbers, timecodes, the fields of SSA events, and formatting,
+/// i.e. `<...>` tags, `{...}` override blocks, and the `\N`, `\n` and
+/// `\h` escapes, are not fed to the detector, so that they don't dilute
+/// the evidence. Each run of text is fed as a fragment (see
+/// `EncodingDetector::feed_fragment()`).
+///
+/// Use one instance per file. `guess()` returns a `SubtitleGuess` that
+/// has what's needed for converting the file.
+pub struct SubtitleDetector {
+    detector: EncodingDetector,
+    line: Line,
+    /// The bytes of the line so far while it is `Pending` or `Keyword`,
+    /// fed if the line turns out to be text. Beyond `PENDING_LEN`, the
+    /// digits and punctuation at the start of a text line are dropped.
+    pending: [u8; PENDING_LEN],
+    pending_len: usize,
+    /// Whether the bytes not fed yet are text.
+    in_text: bool,
+    cues: u64,
+    prefix: [u8; 3],
+    prefix_len: usize,
+}
+
+wrapper! {
+    SubtitleDetector,
+    /// Creates a new instance for one subtitle file with a new
+    /// `EncodingDetector`.
+    fn new;
+    /// Returns the wrapped detector, which has seen the text of the cues
+    /// so far, for calling its other methods, such as
+    /// `ranked_candidates()`. It knows nothing about a byte order mark.
+    fn detector;
+}
+
+impl SubtitleDetector {
+    /// Creates a new instance that feeds the text of the cues to
+    /// `detector`, which may have been configured, e.g. with a budget,
+    /// but must not have been fed yet.
+    pub fn with_detector(detector: EncodingDetector) -> Self {
+        SubtitleDetector {
+            detector,
+            line: Line::Pending,
+            pending: [0; PENDING_LEN],
+            pending_len: 0,
+            in_text: false,
+            cues: 0,
+            prefix: [0; 3],
+            prefix_len: 0,
+        }
+    }
+
+    /// Parses a chunk of the file line by line and counts the cues. The
+    /// chunks may split lines anywhere. Returns `true` if the text of the
+    /// cues has contained non-ASCII so far.
+    ///
+    /// # Panics
+    ///
+    /// If this method has previously been called with `last` set to `true`.
+    pub fn feed(&mut self, buffer: &[u8], last: bool) -> bool {
+        let copy = core::cmp::min(buffer.len(), self.prefix.len() - self.prefix_len);
+        self.prefix[self.prefix_len..self.prefix_len + copy].copy_from_slice(&buffer[..copy]);
+        self.prefix_len += copy;
+        wrap::feed(self, buffer, last)
+    }
+
+    /// Returns the outcome for the file. A byte order mark takes
+    /// precedence over the detector. Otherwise, the arguments work the
+    /// same way as for `EncodingDetector::guess()`.
+    ///
+    /// # Panics
+    ///
+    /// If `tld` contains non-ASCII, period, or upper-case letters.
+    pub fn guess(&self, tld: Option<&[u8]>, allow_utf8: bool) -> SubtitleGuess {
+        if let Some((encoding, bom_length)) = Encoding::for_bom(&self.prefix[..self.prefix_len]) {
+            return SubtitleGuess {
+                encoding,
+                confident: true,
+                bom_length,
+                cues: self.cues,
+            };
+        }
+        let (encoding, confident) = self.detector.guess_assess(tld, allow_utf8);
+        SubtitleGuess {
+            encoding,
+            confident,
+            bom_length: 0,
+            cues: self.cues,
+        }
+    }
+}
+
+/// The route is whether the bytes are text.
+impl Router for SubtitleDetector {
+    type Route = bool;
+
+    fn detector_mut(&mut self) -> &mut EncodingDetector {
+        &mut self.detector
+    }
+
+    fn route(&self) -> bool {
+        self.in_text
+    }
+
+    fn set_route(&mut self, in_text: bool) {
+        if in_text && self.pending_len != 0 {
+            self.detector.feed(&self.pending[..self.pending_len], false);
+            self.pending_len = 0;
+        }
+        self.in_text = in_text;
+    }
+
+    fn weight(&self, in_text: bool) -> Option<u16> {
+        if in_text {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    fn step(&mut self, b: u8) -> bool {
+        if b == b'\n' {
+            self.line = Line::Pending;
+            self.pending_len = 0;
+            return false;
+        }
+        match self.line {
+            Line::Pending => {
+                if is_timecode_byte(b) {
+                    if b == b'>'
+                        && self.pending_len >= 2
+                        && &self.pending[self.pending_len - 2..self.pending_len] == b"--"
+                    {
+                        self.cues += 1;
+                        self.line = Line::Skip;
+                        self.pending_len = 0;
+                    } else {
+                        self.push_pending(b);
+                    }
+                    return false;
+                }
+                if self.pending_len == 0 {
+                    let keyword: &'static [u8] = match b {
+                        b'D' => b"Dialogue:",
+                        b'C' => b"Comment:",
+                        _ => b"",
+                    };
+                    if !keyword.is_empty() {
+                        self.push_pending(b);
+                        self.line = Line::Keyword(keyword, 1);
+                        return false;
+                    }
+                }
+                self.text(b)
+            }
+            Line::Keyword(keyword, matched) => {
+                if b != keyword[matched] {
+                    return self.text(b);
+                }
+                if matched + 1 == keyword.len() {
+                    if keyword == b"Dialogue:" {
+                        self.cues += 1;
+                    }
+                    self.line = Line::Fields(0);
+                    self.pending_len = 0;
+                } else {
+                    self.push_pending(b);
+                    self.line = Line::Keyword(keyword, matched + 1);
+                }
+                false
+            }
+            Line::Fields(commas) => {
+                if b == b',' {
+                    self.line = if commas == 8 {
+                        Line::Text
+                    } else {
+                        Line::Fields(commas + 1)
+                    };
+                }
+                false
+            }
+            Line::Text => self.text(b),
+            Line::Tag(close) => {
+                if b == close {
+                    self.line = Line::Text;
+                }
+                false
+            }
+            Line::Backslash => {
+                if matches!(b, b'N' | b'n' | b'h') {
+                    self.line = Line::Text;
+                    return false;
+                }
+                self.text(b)
+            }
+            Line::Skip => false,
+        }
+    }
+}
+
+impl SubtitleDetector {
+    /// Handles `b` as part of text.
+    fn text(&mut self, b: u8) -> bool {
+        self.line = match b {
+            b'<' => Line::Tag(b'>'),
+            b'{' => Line::Tag(b'}'),
+            b'\\' => Line::Backslash,
+            _ => {
+                self.line = Line::Text;
+                return true;
+            }
+        };
+        false
+    }
+
+    fn push_pending(&mut self, b: u8) {
+        if self.pending_len < PENDING_LEN {
+            self.pending[self.pending_len] = b;
+            self.pending_len += 1;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "central", feature = "cyrillic"))]
+mod tests {
+    extern crate alloc;
+    use super::*;
+    use alloc::vec::Vec;
+    use encoding_rs::UTF_8;
+    use encoding_rs::WINDOWS_1250;
+    use encoding_rs::WINDOWS_1251;
+
+    const SRT: &str = "1\n00:00:01,000 --> 00:00:04,000\n<i>Příliš žluťoučký kůň</i>\n\n\
+2\n00:00:05,000 --> 00:00:07,500 X1:100 X2:600\n{\\an8}úpěl ďábelské ódy.\n";
+
+    const ASS: &str = "[Script Info]\nScriptType: v4.00+\n\n[Events]\n\
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+Dialogue: 0,0:00:01.00,0:00:04.00,Default,,0,0,0,,{\\i1}Привет, как дела?{\\i0}\\NВсё хорошо.\n";
+
+    fn check_subtitles(file: &[u8], runs: &[&[u8]], cues: u64, encoding: &'static Encoding) {
+        let mut det = SubtitleDetector::new();
+        assert!(det.feed(file, true));
+        let guess = det.guess(None, false);
+        assert_eq!(guess.encoding, encoding);
+        assert_eq!(guess.cues, cues);
+        assert_eq!(guess.bom_length, 0);
+
+        // Only the text runs are fed.
+        let mut expected = EncodingDetector::new();
+        for run in runs.iter() {
+            expected.feed_fragment(run, false);
+        }
+        expected.feed(b"", true);
+        assert_eq!(
+            det.detector().ranked_candidates(None),
+            expected.ranked_candidates(None)
+        );
+
+        // Chunking does not matter.
+        let mut bytewise = SubtitleDetector::new();
+        for (i, b) in file.iter().enumerate() {
+            bytewise.feed(core::slice::from_ref(b), i == file.len() - 1);
+        }
+        assert_eq!(bytewise.guess(None, false), guess);
+        assert_eq!(
+            bytewise.detector().ranked_candidates(None),
+            expected.ranked_candidates(None)
+        );
+    }
+
+    #[test]
+    fn test_subtitle_detector() {
+        let srt = WINDOWS_1250.encode(SRT).0;
+        let runs = [
+            WINDOWS_1250.encode("Příliš žluťoučký kůň").0,
+            WINDOWS_1250.encode("úpěl ďábelské ódy.").0,
+        ];
+        check_subtitles(&srt, &[&runs[0], &runs[1]], 2, WINDOWS_1250);
+
+        let ass = WINDOWS_1251.encode(ASS).0;
+        let runs = [
+            WINDOWS_1251.encode("Привет, как дела?").0,
+            WINDOWS_1251.encode("Всё хорошо.").0,
+        ];
+        check_subtitles(
+            &ass,
+            &[
+                b"[Script Info]",
+                b"ScriptType: v4.00+",
+                b"[Events]",
+                b"Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text",
+                &runs[0],
+                &runs[1],
+            ],
+            1,
+            WINDOWS_1251,
+        );
+
+        // A byte order mark takes precedence.
+        let mut with_bom = Vec::from(&b"\xEF\xBB\xBF"[..]);
+        with_bom.extend_from_slice(SRT.as_bytes());
+        let mut det = SubtitleDetector::new();
+        det.feed(&with_bom, true);
+        let guess = det.guess(None, false);
+        assert_eq!(
+            (guess.encoding, guess.bom_length, guess.cues),
+            (UTF_8, 3, 2)
+        );
+    }
+}
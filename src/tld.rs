@@ -1,8 +1,14 @@
 /* Any copyright is dedicated to the Public Domain.
  * https://creativecommons.org/publicdomain/zero/1.0/ */
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// The class of a top-level domain, i.e. the legacy encodings that the
+/// detector expects for content from the domain. Exported as `TldClass`.
+///
+/// When a variant names two classes, the first one is preferred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Tld {
+    /// Central European, windows-1250 first.
     CentralWindows,
     Cyrillic,
     Western,
@@ -19,12 +25,15 @@ pub enum Tld {
     Korean,
     SimplifiedTraditional,
     TraditionalSimplified,
+    /// Central European, ISO-8859-2 first.
     CentralIso,
     IcelandicFaroese,
     WesternCyrillic,
     CentralCyrillic,
     WesternArabic,
+    /// No particular expectation, e.g. `com`.
     Generic,
+    /// The European Union.
     Eu,
 }
 
@@ -0,0 +1,107 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Plumbing shared by the front-ends that wrap an `EncodingDetector`.
+
+use crate::EncodingDetector;
+
+/// Implements `new()`, `detector()` and `Default` for a front-end that
+/// has the wrapped detector in the field `detector` and a
+/// `with_detector()` constructor. The doc comments of `new()` and
+/// `detector()` are given before `fn new;` and `fn detector;`.
+macro_rules! wrapper {
+    (
+        $name:ident,
+        $(#[$new_doc:meta])*
+        fn new;
+        $(#[$detector_doc:meta])*
+        fn detector;
+    ) => {
+        impl $name {
+            $(#[$new_doc])*
+            pub fn new() -> Self {
+                $name::with_detector(crate::EncodingDetector::new())
+            }
+
+            $(#[$detector_doc])*
+            pub fn detector(&self) -> &crate::EncodingDetector {
+                &self.detector
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::new()
+            }
+        }
+    };
+}
+
+/// A tokenizer that splits the input of a front-end into runs by where
+/// the bytes go: to the detector, with some weight, or nowhere.
+pub(crate) trait Router {
+    type Route: Copy + PartialEq;
+
+    fn detector_mut(&mut self) -> &mut EncodingDetector;
+
+    /// The route of the bytes not fed yet.
+    fn route(&self) -> Self::Route;
+
+    /// Switches to `route` after the run of the previous route has been
+    /// fed.
+    fn set_route(&mut self, route: Self::Route);
+
+    /// The weight of the bytes on `route` or `None` if they are not fed.
+    /// A weight other than 1 is set with `EncodingDetector::set_weight()`
+    /// for the run and reset to 1 after it.
+    fn weight(&self, route: Self::Route) -> Option<u16>;
+
+    /// Advances the tokenizer by `b` and returns the route of `b`.
+    fn step(&mut self, b: u8) -> Self::Route;
+}
+
+/// Feeds `buffer` through `router`. Each run that ends in `buffer` is fed
+/// as a fragment; the run at the end of `buffer` may continue in the next
+/// call. Returns `true` if the detector has seen non-ASCII so far.
+pub(crate) fn feed<R: Router>(router: &mut R, buffer: &[u8], last: bool) -> bool {
+    let mut start = 0;
+    for (i, &b) in buffer.iter().enumerate() {
+        let route = router.step(b);
+        if route != router.route() {
+            feed_run(router, &buffer[start..i], |detector, run| {
+                detector.feed_fragment(run, false);
+            });
+            router.set_route(route);
+            start = i;
+        }
+    }
+    let rest = &buffer[start..];
+    if last && router.weight(router.route()).is_none() {
+        router.detector_mut().feed(b"", true);
+    } else {
+        // Feeding the end of the run with `last` makes EOF the boundary.
+        feed_run(router, rest, |detector, run| {
+            detector.feed(run, last);
+        });
+    }
+    router.detector_mut().non_ascii_count() != 0
+}
+
+fn feed_run<R: Router, F: FnOnce(&mut EncodingDetector, &[u8])>(router: &mut R, run: &[u8], f: F) {
+    match router.weight(router.route()) {
+        None => {}
+        Some(1) => f(router.detector_mut(), run),
+        Some(weight) => {
+            let detector = router.detector_mut();
+            detector.set_weight(weight);
+            f(detector, run);
+            detector.set_weight(1);
+        }
+    }
+}
@@ -0,0 +1,446 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Accuracy evaluation harness for `chardetng`.
+//!
+//! Takes a directory of UTF-8 text samples labeled by language, encodes
+//! each sample into the legacy encodings relevant to its language,
+//! truncates it to various lengths, runs `EncodingDetector` with and
+//! without the TLD hint for the language, and reports per-length
+//! accuracy and confusion matrices.
+//!
+//! The samples are either files directly in the directory named after
+//! the language (`ru.txt`, `ru.2.txt`) or files in subdirectories named
+//! after the language (`ru/anything`).
+
+use chardetng::EncodingDetector;
+use detone::IterDecomposeVietnamese;
+use encoding_rs::Encoding;
+use encoding_rs::BIG5_INIT;
+use encoding_rs::EUC_JP_INIT;
+use encoding_rs::EUC_KR_INIT;
+use encoding_rs::GBK_INIT;
+use encoding_rs::IBM866_INIT;
+use encoding_rs::ISO_2022_JP_INIT;
+use encoding_rs::ISO_8859_13_INIT;
+use encoding_rs::ISO_8859_2_INIT;
+use encoding_rs::ISO_8859_4_INIT;
+use encoding_rs::ISO_8859_5_INIT;
+use encoding_rs::ISO_8859_6_INIT;
+use encoding_rs::ISO_8859_7_INIT;
+use encoding_rs::KOI8_U_INIT;
+use encoding_rs::SHIFT_JIS_INIT;
+use encoding_rs::WINDOWS_1250_INIT;
+use encoding_rs::WINDOWS_1251_INIT;
+use encoding_rs::WINDOWS_1252_INIT;
+use encoding_rs::WINDOWS_1253_INIT;
+use encoding_rs::WINDOWS_1254_INIT;
+use encoding_rs::WINDOWS_1255_INIT;
+use encoding_rs::WINDOWS_1256_INIT;
+use encoding_rs::WINDOWS_1257_INIT;
+use encoding_rs::WINDOWS_1258;
+use encoding_rs::WINDOWS_1258_INIT;
+use encoding_rs::WINDOWS_874_INIT;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: chardetng_evaluate [--lengths N,N,...] DIRECTORY
+
+Evaluates chardetng on the UTF-8 samples in DIRECTORY. Samples are named
+after their language (e.g. ru.txt) or placed in subdirectories named after
+their language (e.g. ru/sample.txt).
+
+Options:
+      --lengths <N,N,...>
+                      Lengths in characters to truncate the samples to
+                      (default: 10,20,50,100,200,500,1000)
+  -h, --help          Print this help
+";
+
+const DEFAULT_LENGTHS: [usize; 7] = [10, 20, 50, 100, 200, 500, 1000];
+
+struct Language {
+    code: &'static str,
+    tld: &'static str,
+    encodings: &'static [&'static Encoding],
+}
+
+const WESTERN: &[&Encoding] = &[&WINDOWS_1252_INIT];
+const CENTRAL: &[&Encoding] = &[&WINDOWS_1250_INIT, &ISO_8859_2_INIT];
+const CYRILLIC: &[&Encoding] = &[&WINDOWS_1251_INIT, &ISO_8859_5_INIT];
+const BALTIC: &[&Encoding] = &[&WINDOWS_1257_INIT, &ISO_8859_13_INIT, &ISO_8859_4_INIT];
+
+/// The legacy encodings that are evaluated for each language. Sorted by
+/// language code.
+#[rustfmt::skip]
+static LANGUAGES: [Language; 48] = [
+    Language { code: "af", tld: "za", encodings: WESTERN },
+    Language { code: "ar", tld: "sa", encodings: &[&WINDOWS_1256_INIT, &ISO_8859_6_INIT] },
+    Language { code: "be", tld: "by", encodings: CYRILLIC },
+    Language { code: "bg", tld: "bg", encodings: CYRILLIC },
+    Language { code: "bs", tld: "ba", encodings: CENTRAL },
+    Language { code: "ca", tld: "es", encodings: WESTERN },
+    Language { code: "cs", tld: "cz", encodings: CENTRAL },
+    Language { code: "da", tld: "dk", encodings: WESTERN },
+    Language { code: "de", tld: "de", encodings: WESTERN },
+    Language { code: "el", tld: "gr", encodings: &[&WINDOWS_1253_INIT, &ISO_8859_7_INIT] },
+    Language { code: "en", tld: "uk", encodings: WESTERN },
+    Language { code: "es", tld: "es", encodings: WESTERN },
+    Language { code: "et", tld: "ee", encodings: WESTERN },
+    Language { code: "eu", tld: "es", encodings: WESTERN },
+    Language { code: "fa", tld: "ir", encodings: &[&WINDOWS_1256_INIT] },
+    Language { code: "fi", tld: "fi", encodings: WESTERN },
+    Language { code: "fo", tld: "fo", encodings: WESTERN },
+    Language { code: "fr", tld: "fr", encodings: WESTERN },
+    Language { code: "ga", tld: "ie", encodings: WESTERN },
+    Language { code: "gl", tld: "es", encodings: WESTERN },
+    Language { code: "he", tld: "il", encodings: &[&WINDOWS_1255_INIT] },
+    Language { code: "hr", tld: "hr", encodings: CENTRAL },
+    Language { code: "hu", tld: "hu", encodings: CENTRAL },
+    Language { code: "is", tld: "is", encodings: WESTERN },
+    Language { code: "it", tld: "it", encodings: WESTERN },
+    Language { code: "ja", tld: "jp", encodings: &[&SHIFT_JIS_INIT, &EUC_JP_INIT, &ISO_2022_JP_INIT] },
+    Language { code: "ko", tld: "kr", encodings: &[&EUC_KR_INIT] },
+    Language { code: "lt", tld: "lt", encodings: BALTIC },
+    Language { code: "lv", tld: "lv", encodings: BALTIC },
+    Language { code: "mk", tld: "mk", encodings: CYRILLIC },
+    Language { code: "nl", tld: "nl", encodings: WESTERN },
+    Language { code: "no", tld: "no", encodings: WESTERN },
+    Language { code: "pl", tld: "pl", encodings: CENTRAL },
+    Language { code: "pt", tld: "pt", encodings: WESTERN },
+    Language { code: "ro", tld: "ro", encodings: CENTRAL },
+    Language { code: "ru", tld: "ru", encodings: &[&WINDOWS_1251_INIT, &KOI8_U_INIT, &ISO_8859_5_INIT, &IBM866_INIT] },
+    Language { code: "sk", tld: "sk", encodings: CENTRAL },
+    Language { code: "sl", tld: "si", encodings: CENTRAL },
+    Language { code: "sr", tld: "rs", encodings: CYRILLIC },
+    Language { code: "sv", tld: "se", encodings: WESTERN },
+    Language { code: "th", tld: "th", encodings: &[&WINDOWS_874_INIT] },
+    Language { code: "tr", tld: "tr", encodings: &[&WINDOWS_1254_INIT] },
+    Language { code: "uk", tld: "ua", encodings: &[&WINDOWS_1251_INIT, &KOI8_U_INIT, &ISO_8859_5_INIT] },
+    Language { code: "ur", tld: "pk", encodings: &[&WINDOWS_1256_INIT] },
+    Language { code: "vi", tld: "vn", encodings: &[&WINDOWS_1258_INIT] },
+    Language { code: "yi", tld: "il", encodings: &[&WINDOWS_1255_INIT] },
+    Language { code: "zh-hans", tld: "cn", encodings: &[&GBK_INIT] },
+    Language { code: "zh-hant", tld: "tw", encodings: &[&BIG5_INIT] },
+];
+
+fn language(code: &str) -> Option<&'static Language> {
+    LANGUAGES
+        .binary_search_by(|probe| probe.code.cmp(code))
+        .ok()
+        .map(|i| &LANGUAGES[i])
+}
+
+#[derive(Default)]
+struct LengthStats {
+    cases: u64,
+    correct_without_tld: u64,
+    correct_with_tld: u64,
+}
+
+#[derive(Default)]
+struct Stats {
+    by_length: BTreeMap<usize, LengthStats>,
+    // (actual, guess) -> count
+    confusion_without_tld: BTreeMap<(&'static str, &'static str), u64>,
+    confusion_with_tld: BTreeMap<(&'static str, &'static str), u64>,
+    // Sample and encoding pairs skipped due to unmappable characters
+    unmappable: u64,
+}
+
+impl Stats {
+    fn merge(mut self, other: Stats) -> Stats {
+        for (length, s) in other.by_length {
+            let entry = self.by_length.entry(length).or_default();
+            entry.cases += s.cases;
+            entry.correct_without_tld += s.correct_without_tld;
+            entry.correct_with_tld += s.correct_with_tld;
+        }
+        for (key, count) in other.confusion_without_tld {
+            *self.confusion_without_tld.entry(key).or_default() += count;
+        }
+        for (key, count) in other.confusion_with_tld {
+            *self.confusion_with_tld.entry(key).or_default() += count;
+        }
+        self.unmappable += other.unmappable;
+        self
+    }
+}
+
+/// Returns the prefix of `text` that is at most `length` characters long.
+fn truncate(text: &str, length: usize) -> &str {
+    match text.char_indices().nth(length) {
+        Some((i, _)) => &text[..i],
+        None => text,
+    }
+}
+
+/// Counts a guess as correct if it is the encoding the text was encoded
+/// in or if it decodes the bytes to the same text. (For example, KOI8-U
+/// in place of KOI8-R or windows-1255 in place of ISO-8859-8 for text
+/// that doesn't use the differing characters.) Returns the encoding to
+/// record in the confusion matrix.
+fn judge(
+    guess: &'static Encoding,
+    actual: &'static Encoding,
+    bytes: &[u8],
+    text: &str,
+) -> (bool, &'static Encoding) {
+    if guess == actual {
+        return (true, actual);
+    }
+    match guess.decode_without_bom_handling_and_without_replacement(bytes) {
+        Some(decoded) if decoded == text => (true, actual),
+        _ => (false, guess),
+    }
+}
+
+fn evaluate_sample(language: &Language, text: &str, lengths: &[usize]) -> Stats {
+    let mut stats = Stats::default();
+    let total_chars = text.chars().count();
+    for &length in lengths {
+        if length > total_chars {
+            continue;
+        }
+        let truncated = truncate(text, length);
+        if truncated.is_ascii() {
+            // Nothing to detect.
+            continue;
+        }
+        for &encoding in language.encodings {
+            let decomposed;
+            let text = if encoding == WINDOWS_1258 {
+                decomposed = truncated
+                    .chars()
+                    .decompose_vietnamese_tones(true)
+                    .collect::<String>();
+                &decomposed[..]
+            } else {
+                truncated
+            };
+            let (bytes, _, unmappable) = encoding.encode(text);
+            if unmappable {
+                stats.unmappable += 1;
+                continue;
+            }
+            let mut detector = EncodingDetector::new();
+            detector.feed(&bytes, true);
+            let (without_correct, without_tld) =
+                judge(detector.guess(None, false), encoding, &bytes, text);
+            let (with_correct, with_tld) = judge(
+                detector.guess(Some(language.tld.as_bytes()), false),
+                encoding,
+                &bytes,
+                text,
+            );
+            let entry = stats.by_length.entry(length).or_default();
+            entry.cases += 1;
+            entry.correct_without_tld += u64::from(without_correct);
+            entry.correct_with_tld += u64::from(with_correct);
+            *stats
+                .confusion_without_tld
+                .entry((encoding.name(), without_tld.name()))
+                .or_default() += 1;
+            *stats
+                .confusion_with_tld
+                .entry((encoding.name(), with_tld.name()))
+                .or_default() += 1;
+        }
+    }
+    stats
+}
+
+fn collect_samples(directory: &Path) -> std::io::Result<Vec<(&'static Language, PathBuf)>> {
+    let mut samples = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path.is_dir() {
+            if let Some(language) = language(name) {
+                for inner in fs::read_dir(&path)? {
+                    let inner = inner?.path();
+                    if inner.is_file() {
+                        samples.push((language, inner));
+                    }
+                }
+            } else {
+                eprintln!(
+                    "Skipping directory with unknown language: {}",
+                    path.display()
+                );
+            }
+        } else {
+            let code = name.split('.').next().unwrap_or("");
+            if let Some(language) = language(code) {
+                samples.push((language, path));
+            } else {
+                eprintln!("Skipping file with unknown language: {}", path.display());
+            }
+        }
+    }
+    samples.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(samples)
+}
+
+fn print_confusion(title: &str, confusion: &BTreeMap<(&'static str, &'static str), u64>) {
+    let actuals: BTreeSet<&str> = confusion.keys().map(|&(actual, _)| actual).collect();
+    let guesses: BTreeSet<&str> = confusion.keys().map(|&(_, guess)| guess).collect();
+    println!();
+    println!(
+        "# Confusion matrix {} (rows: actual, columns: guess)",
+        title
+    );
+    print!("actual\\guess");
+    for guess in guesses.iter() {
+        print!("\t{}", guess);
+    }
+    println!("\taccuracy");
+    for actual in actuals.iter() {
+        print!("{}", actual);
+        let mut total = 0u64;
+        for guess in guesses.iter() {
+            let count = confusion.get(&(*actual, *guess)).copied().unwrap_or(0);
+            total += count;
+            print!("\t{}", count);
+        }
+        let correct = confusion.get(&(*actual, *actual)).copied().unwrap_or(0);
+        println!("\t{}", percentage(correct, total));
+    }
+}
+
+fn percentage(part: u64, whole: u64) -> String {
+    if whole == 0 {
+        return String::from("-");
+    }
+    format!("{:.2}%", (part as f64) * 100.0 / (whole as f64))
+}
+
+fn parse_lengths(value: &str) -> Result<Vec<usize>, String> {
+    let mut lengths = Vec::new();
+    for item in value.split(',') {
+        match item.trim().parse::<usize>() {
+            Ok(length) if length > 0 => lengths.push(length),
+            _ => return Err(format!("invalid length: {}", item)),
+        }
+    }
+    lengths.sort_unstable();
+    lengths.dedup();
+    Ok(lengths)
+}
+
+fn main() {
+    let mut lengths = DEFAULT_LENGTHS.to_vec();
+    let mut directory = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return;
+            }
+            "--lengths" => {
+                let value = args.next().unwrap_or_default();
+                lengths = parse_lengths(&value).unwrap_or_else(|err| {
+                    eprintln!("chardetng_evaluate: {}", err);
+                    process::exit(2);
+                });
+            }
+            _ if directory.is_none() && !arg.starts_with('-') => {
+                directory = Some(PathBuf::from(arg));
+            }
+            _ => {
+                eprint!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+    let directory = directory.unwrap_or_else(|| {
+        eprint!("{}", USAGE);
+        process::exit(2);
+    });
+    let samples = collect_samples(&directory).unwrap_or_else(|err| {
+        eprintln!("chardetng_evaluate: {}: {}", directory.display(), err);
+        process::exit(1);
+    });
+    let stats = samples
+        .par_iter()
+        .map(|(language, path)| match fs::read_to_string(path) {
+            Ok(text) => evaluate_sample(language, &text, &lengths),
+            Err(err) => {
+                eprintln!("Skipping {}: {}", path.display(), err);
+                Stats::default()
+            }
+        })
+        .reduce(Stats::default, Stats::merge);
+
+    println!("# Accuracy by length in characters");
+    println!("length\tcases\twithout_tld\twith_tld");
+    for (length, s) in stats.by_length.iter() {
+        println!(
+            "{}\t{}\t{}\t{}",
+            length,
+            s.cases,
+            percentage(s.correct_without_tld, s.cases),
+            percentage(s.correct_with_tld, s.cases)
+        );
+    }
+    print_confusion("without TLD", &stats.confusion_without_tld);
+    print_confusion("with TLD", &stats.confusion_with_tld);
+    if stats.unmappable != 0 {
+        println!();
+        println!(
+            "# Skipped {} cases with characters unmappable in the legacy encoding",
+            stats.unmappable
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_sorted() {
+        for pair in LANGUAGES.windows(2) {
+            assert!(pair[0].code < pair[1].code);
+        }
+        for language in LANGUAGES.iter() {
+            assert!(EncodingDetector::tld_may_affect_guess(Some(
+                language.tld.as_bytes()
+            )));
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("äbc", 2), "äb");
+        assert_eq!(truncate("äbc", 5), "äbc");
+    }
+
+    #[test]
+    fn test_evaluate_sample() {
+        let stats = evaluate_sample(
+            language("ru").unwrap(),
+            "Москва является столицей России и городом федерального значения.",
+            &[30],
+        );
+        let s = &stats.by_length[&30];
+        assert_eq!(s.cases, 4);
+        assert_eq!(s.correct_with_tld, 4);
+    }
+}
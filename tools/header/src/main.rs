@@ -0,0 +1,59 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generates `include/chardetng.h` from `src/ffi.rs` with cbindgen using
+//! `cbindgen.toml` at the root of the repository.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn generate(root: &Path) -> Result<cbindgen::Bindings, String> {
+    let config = cbindgen::Config::from_file(root.join("cbindgen.toml"))?;
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src/ffi.rs"))
+        .generate()
+        .map_err(|e| e.to_string())
+}
+
+fn main() {
+    let root = root();
+    match generate(&root) {
+        Ok(bindings) => {
+            bindings.write_to_file(root.join("include/chardetng.h"));
+        }
+        Err(e) => {
+            eprintln!("chardetng_header: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_header_is_up_to_date() {
+        let root = root();
+        let mut generated = Vec::new();
+        generate(&root).unwrap().write(&mut generated);
+        let current = fs::read_to_string(root.join("include/chardetng.h")).unwrap();
+        assert!(
+            String::from_utf8(generated).unwrap() == current,
+            "include/chardetng.h differs from src/ffi.rs; run `cargo run -p chardetng_header`"
+        );
+    }
+}
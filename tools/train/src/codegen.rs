@@ -0,0 +1,349 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Writes `data.rs`.
+
+use crate::model::Model;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+const HEADER: &str = "\
+/* Any copyright is dedicated to the Public Domain.
+ * https://creativecommons.org/publicdomain/zero/1.0/ */
+
+use super::IMPLAUSIBILITY_PENALTY;
+use encoding_rs::Encoding;
+";
+
+/// The constants for the unstored categories, which `model.rs` refers to
+/// by name.
+const CONSTS: &str = include_str!("data_consts.rs.in");
+
+/// `compute_index` and the `SingleByteData` implementation.
+const IMPL: &str = include_str!("data_impl.rs.in");
+
+/// The names of the frequent CJK character lists in `DetectorData`.
+pub const FREQUENT: [&str; 3] = ["simplified", "kanji", "hangul"];
+
+/// The cargo feature of `chardetng` that compiles the CJK candidates and
+/// the frequent character lists in.
+const CJK_FEATURE: &str = "cjk";
+
+/// Writes a `#[cfg]` attribute for `predicate` (if any) at `indent`,
+/// wrapping an `any` that doesn't fit on a line the way rustfmt does.
+fn write_cfg(out: &mut String, indent: &str, predicate: Option<&str>) {
+    let predicate = match predicate {
+        Some(predicate) => predicate,
+        None => return,
+    };
+    let line = format!("{}#[cfg({})]", indent, predicate);
+    match predicate
+        .strip_prefix("any(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        Some(list) if line.len() > 100 => {
+            let _ = writeln!(out, "{}#[cfg(any(", indent);
+            let items: Vec<&str> = list.split(", ").collect();
+            for (i, item) in items.iter().enumerate() {
+                let comma = if i + 1 < items.len() { "," } else { "" };
+                let _ = writeln!(out, "{}    {}{}", indent, item, comma);
+            }
+            let _ = writeln!(out, "{}))]", indent);
+        }
+        _ => {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+}
+
+fn feature_predicate(feature: Option<&str>) -> Option<String> {
+    feature.map(|feature| format!("feature = \"{}\"", feature))
+}
+
+/// The predicate for a table shared by models: none if any of them is
+/// always compiled in.
+fn shared_predicate(features: &[Option<&str>]) -> Option<String> {
+    if features.iter().any(Option::is_none) {
+        return None;
+    }
+    let mut predicates: Vec<String> = Vec::new();
+    for predicate in features.iter().filter_map(|&f| feature_predicate(f)) {
+        if !predicates.contains(&predicate) {
+            predicates.push(predicate);
+        }
+    }
+    if predicates.len() == 1 {
+        predicates.pop()
+    } else {
+        Some(format!("any({})", predicates.join(", ")))
+    }
+}
+
+/// An ASCII half shared between the models of the same kind.
+struct AsciiTable {
+    name: &'static str,
+    table: [u8; 128],
+    /// The features of the models that use the table.
+    features: Vec<Option<&'static str>>,
+}
+
+fn write_u16_table(out: &mut String, name: &str, table: &[u16]) {
+    let _ = writeln!(out, "    {}: [", name);
+    for row in table.chunks(16) {
+        out.push_str("        ");
+        for u in row {
+            let _ = write!(out, "0x{:04X}, ", u);
+        }
+        out.push('\n');
+    }
+    out.push_str("    ],\n");
+}
+
+fn write_class_table(out: &mut String, name: &str, table: &[u8; 128]) {
+    let _ = writeln!(out, "    {}: [", name);
+    for row in table.chunks(16) {
+        out.push_str("        ");
+        for b in row {
+            let _ = write!(out, "{:3},", b);
+        }
+        out.push('\n');
+    }
+    out.push_str("    ],\n");
+}
+
+fn write_scores(out: &mut String, model: &Model) {
+    let ascii = model.spec.ascii.classes();
+    let labels = &model.classes.labels;
+    let stored = labels.len();
+    let predicate = feature_predicate(model.spec.feature);
+    write_cfg(out, "    ", predicate.as_deref());
+    let _ = writeln!(out, "    {}: [", model.spec.name);
+    let mut scores = model.scores.iter();
+    for (current, label) in labels.iter().enumerate() {
+        out.push_str("        ");
+        let first = if current < ascii {
+            for _ in 0..ascii {
+                out.push_str("    ");
+            }
+            ascii
+        } else {
+            0
+        };
+        for _ in first..stored {
+            let _ = write!(out, "{:3},", scores.next().unwrap());
+        }
+        let _ = writeln!(out, " // {},", label);
+    }
+    out.push_str("      //");
+    for label in labels.iter() {
+        let _ = write!(out, "{:>3},", label);
+    }
+    out.push_str("\n    ],\n");
+}
+
+/// Generates `data.rs` for `models` (in `SINGLE_BYTE_DATA` order) and the
+/// frequent CJK character lists (in `FREQUENT` order).
+///
+/// The tables of each model (and of the CJK candidates) are compiled in
+/// only if the model's cargo feature is enabled. `SINGLE_BYTE_DATA` keeps
+/// an entry without tables for the models that are compiled out so that
+/// the indices stay the same.
+pub fn generate(models: &[Model], frequent: &[Vec<u16>]) -> Result<String, String> {
+    let cjk = feature_predicate(Some(CJK_FEATURE));
+    // The ASCII halves are shared between the models of the same kind.
+    let mut ascii_tables: Vec<AsciiTable> = Vec::new();
+    for model in models.iter() {
+        let name = model.spec.ascii.table();
+        let table = model.classes.ascii_table();
+        match ascii_tables.iter_mut().find(|t| t.name == name) {
+            Some(other) if other.table != table => {
+                return Err(format!(
+                    "{}: ASCII classes differ from other models using {}",
+                    model.spec.name, name
+                ));
+            }
+            Some(other) => other.features.push(model.spec.feature),
+            None => ascii_tables.push(AsciiTable {
+                name,
+                table,
+                features: vec![model.spec.feature],
+            }),
+        }
+    }
+    ascii_tables.sort_by_key(|t| match t.name {
+        "latin_ascii" => 0,
+        "non_latin_ascii" => 1,
+        _ => 2,
+    });
+
+    let mut out = String::from(HEADER);
+    let constants: BTreeSet<&str> = models
+        .iter()
+        .flat_map(|model| model.spec.tables.iter().map(|t| t.constant))
+        .collect();
+    for constant in constants.iter() {
+        let _ = writeln!(out, "use encoding_rs::{};", constant);
+    }
+    out.push('\n');
+    out.push_str(CONSTS);
+    out.push('\n');
+
+    out.push_str("#[repr(align(64))] // Align to cache lines\n");
+    out.push_str("pub struct DetectorData {\n");
+    for name in FREQUENT.iter() {
+        write_cfg(&mut out, "    ", cjk.as_deref());
+        let _ = writeln!(out, "    pub frequent_{}: [u16; 128],", name);
+    }
+    for t in ascii_tables.iter() {
+        write_cfg(&mut out, "    ", shared_predicate(&t.features).as_deref());
+        let _ = writeln!(out, "    {}: [u8; 128],", t.name);
+    }
+    for model in models.iter() {
+        let predicate = feature_predicate(model.spec.feature);
+        for table in model.spec.tables.iter() {
+            write_cfg(&mut out, "    ", predicate.as_deref());
+            let _ = writeln!(out, "    {}: [u8; 128],", table.table);
+        }
+    }
+    for model in models.iter() {
+        write_cfg(
+            &mut out,
+            "    ",
+            feature_predicate(model.spec.feature).as_deref(),
+        );
+        let _ = writeln!(
+            out,
+            "    {}: [u8; {}],",
+            model.spec.name,
+            model.scores.len()
+        );
+    }
+    out.push_str("}\n\n");
+
+    out.push_str("#[rustfmt::skip]\n");
+    out.push_str("pub static DETECTOR_DATA: DetectorData = DetectorData {\n");
+    for (name, table) in FREQUENT.iter().zip(frequent.iter()) {
+        write_cfg(&mut out, "    ", cjk.as_deref());
+        write_u16_table(&mut out, &format!("frequent_{}", name), table);
+    }
+    for t in ascii_tables.iter() {
+        write_cfg(&mut out, "    ", shared_predicate(&t.features).as_deref());
+        write_class_table(&mut out, t.name, &t.table);
+    }
+    for model in models.iter() {
+        let predicate = feature_predicate(model.spec.feature);
+        for (i, table) in model.spec.tables.iter().enumerate() {
+            write_cfg(&mut out, "    ", predicate.as_deref());
+            let upper = model.classes.upper_table(i, table.encoding);
+            write_class_table(&mut out, table.table, &upper);
+        }
+    }
+    for model in models.iter() {
+        write_scores(&mut out, model);
+    }
+    out.push_str("};\n\n");
+
+    for model in models.iter() {
+        let upper_name = model.spec.name.to_ascii_uppercase();
+        let predicate = feature_predicate(model.spec.feature);
+        write_cfg(&mut out, "", predicate.as_deref());
+        let _ = writeln!(
+            out,
+            "const {}_ASCII: usize = {};",
+            upper_name,
+            model.spec.ascii.classes()
+        );
+        write_cfg(&mut out, "", predicate.as_deref());
+        let _ = writeln!(
+            out,
+            "const {}_NON_ASCII: usize = {};",
+            upper_name,
+            model.classes.stored() - model.spec.ascii.classes()
+        );
+    }
+    out.push_str(IMPL);
+
+    let count: usize = models.iter().map(|model| model.spec.tables.len()).sum();
+    let _ = writeln!(
+        out,
+        "pub static SINGLE_BYTE_DATA: [SingleByteData; {}] = [",
+        count
+    );
+    for model in models.iter() {
+        let upper_name = model.spec.name.to_ascii_uppercase();
+        let predicate = feature_predicate(model.spec.feature);
+        for table in model.spec.tables.iter() {
+            write_cfg(&mut out, "    ", predicate.as_deref());
+            out.push_str("    SingleByteData {\n");
+            let _ = writeln!(out, "        encoding: &{},", table.constant);
+            let _ = writeln!(
+                out,
+                "        lower: &DETECTOR_DATA.{},",
+                model.spec.ascii.table()
+            );
+            let _ = writeln!(out, "        upper: &DETECTOR_DATA.{},", table.table);
+            let _ = writeln!(
+                out,
+                "        probabilities: &DETECTOR_DATA.{},",
+                model.spec.name
+            );
+            let _ = writeln!(out, "        ascii: {}_ASCII,", upper_name);
+            let _ = writeln!(out, "        non_ascii: {}_NON_ASCII,", upper_name);
+            out.push_str("    },\n");
+            if let Some(predicate) = predicate.as_deref() {
+                let _ = writeln!(out, "    #[cfg(not({}))]", predicate);
+                out.push_str("    SingleByteData {\n");
+                let _ = writeln!(out, "        encoding: &{},", table.constant);
+                out.push_str("        lower: &[255; 128],\n");
+                out.push_str("        upper: &[255; 128],\n");
+                out.push_str("        probabilities: &[],\n");
+                out.push_str("        ascii: 0,\n");
+                out.push_str("        non_ascii: 0,\n");
+                out.push_str("    },\n");
+            }
+        }
+    }
+    out.push_str("];\n\n");
+
+    let tables = models.iter().flat_map(|model| model.spec.tables.iter());
+    for (i, table) in tables.enumerate() {
+        let _ = writeln!(
+            out,
+            "pub const {}_INDEX: usize = {};",
+            table.table.to_ascii_uppercase(),
+            i
+        );
+    }
+    Ok(out)
+}
+
+/// Writes `models` in the binary format of `chardetng::SingleByteModel`.
+pub fn binary(models: &[Model]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(b"CDNGMODL");
+    out.extend_from_slice(&1u16.to_le_bytes());
+    let count: usize = models.iter().map(|model| model.spec.tables.len()).sum();
+    out.extend_from_slice(&(count as u16).to_le_bytes());
+    for model in models.iter() {
+        let ascii = model.spec.ascii.classes();
+        for (i, table) in model.spec.tables.iter().enumerate() {
+            let name = table.encoding.name();
+            out.push(name.len() as u8);
+            out.extend_from_slice(name.as_bytes());
+            out.push(ascii as u8);
+            out.push((model.classes.stored() - ascii) as u8);
+            out.extend_from_slice(&model.classes.ascii_table());
+            out.extend_from_slice(&model.classes.upper_table(i, table.encoding));
+            out.extend_from_slice(&(model.scores.len() as u32).to_le_bytes());
+            out.extend_from_slice(&model.scores);
+        }
+    }
+    out
+}
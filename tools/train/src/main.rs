@@ -0,0 +1,324 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Model training for `chardetng`.
+//!
+//! Gathering statistics and generating code are separate steps so that
+//! the code can be regenerated from the checked-in model directory
+//! without the corpora:
+//!
+//! 1. `count` counts the character class pairs of UTF-8 corpora using the
+//!    class definitions of a model.
+//! 2. `quantize` turns the counts into the scores stored in the model
+//!    directory.
+//! 3. `frequent` ranks the level 1 CJK characters of UTF-8 corpora.
+//! 4. `generate` writes `src/data.rs` from the class definitions, the
+//!    scores and the frequent character lists. The output only depends on
+//!    the model directory.
+//! 5. `binary` writes the single-byte models in the format that
+//!    `chardetng::SingleByteModel::from_bytes` loads at run time.
+//!
+//! The scores in the model directory of this repository were extracted
+//! from the `data.rs` that the external traindet tool generated, so
+//! `generate` reproduces that file from stored scores. Going from corpora
+//! to those scores is not reproducible: `count` and `quantize` are not a
+//! port of traindet's statistics gathering, and the charcounts files that
+//! traindet read are not part of this repository. Running them on a
+//! corpus trains a new model whose scores differ from the stored ones.
+
+mod codegen;
+mod model;
+mod stats;
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: chardetng_train [--model-dir DIR] COMMAND [ARG]...
+
+Commands:
+  count MODEL CORPUS...
+                      Print the class pair counts of the UTF-8 CORPUS
+                      files using the classes in DIR/MODEL.classes
+  quantize [--scale N] COUNTS
+                      Print new scores in the format of DIR/MODEL.scores
+                      computed from the output of count: occurrences per N
+                      stored pairs (default: 10000), 255 for pairs that
+                      never occur. Not the traindet quantization
+  frequent simplified|kanji|hangul CORPUS...
+                      Print the 128 most frequent level 1 characters of the
+                      UTF-8 CORPUS files for DIR/frequent_*.txt
+  generate [OUTPUT]   Write data.rs generated from DIR to OUTPUT (default:
+                      src/data.rs of this repository, - for standard output)
+  binary OUTPUT       Write the single-byte models of DIR to OUTPUT in the
+                      run-time loadable format (- for standard output)
+
+Options:
+      --model-dir <DIR>
+                      Model directory (default: tools/train/model of this
+                      repository)
+  -h, --help          Print this help
+
+Models: vietnamese central cyrillic western icelandic greek turkish hebrew
+arabic baltic thai
+";
+
+const DEFAULT_SCALE: u64 = 10000;
+
+fn default_model_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("model")
+}
+
+fn default_output() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../src/data.rs")
+}
+
+fn usage_error() -> ! {
+    eprint!("{}", USAGE);
+    process::exit(2);
+}
+
+fn read_corpora(paths: &[String]) -> Result<Vec<String>, String> {
+    if paths.is_empty() {
+        usage_error();
+    }
+    paths
+        .iter()
+        .map(|path| fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e)))
+        .collect()
+}
+
+fn model_spec(name: Option<&String>) -> &'static model::ModelSpec {
+    let name = name.unwrap_or_else(|| usage_error());
+    model::model_spec(name).unwrap_or_else(|| {
+        eprintln!("chardetng_train: unknown model: {}", name);
+        process::exit(2);
+    })
+}
+
+fn count(dir: &Path, args: &[String]) -> Result<String, String> {
+    let spec = model_spec(args.first());
+    let classes = model::read_classes(dir, spec)?;
+    let mut counts = stats::Counts::new(&classes);
+    for text in read_corpora(&args[1..])?.iter() {
+        counts.add(&classes, text);
+    }
+    let header = format!(
+        "Pair counts of the {} model. Row: current class, column: previous class.",
+        spec.name
+    );
+    Ok(model::format_matrix(
+        &header,
+        &counts.rows(spec.ascii.classes()),
+        1,
+    ))
+}
+
+fn quantize(dir: &Path, args: &[String]) -> Result<String, String> {
+    let (scale, path) = match args {
+        [path] => (DEFAULT_SCALE, path),
+        [option, scale, path] if option == "--scale" => {
+            let scale = scale
+                .parse::<u64>()
+                .ok()
+                .filter(|&scale| scale > 0)
+                .ok_or_else(|| format!("bad scale: {}", scale))?;
+            (scale, path)
+        }
+        _ => usage_error(),
+    };
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    // The header names the model so that the shape can be checked.
+    let spec = text
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("# Pair counts of the "))
+        .and_then(|rest| rest.split(' ').next())
+        .and_then(model::model_spec)
+        .ok_or_else(|| format!("{}: not the output of count", path))?;
+    let classes = model::read_classes(dir, spec)?;
+    let rows = model::parse_matrix::<u64>(&text, spec.ascii.classes(), classes.stored())
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let header = format!(
+        "Pair scores of the {} model. Row: current class, column: previous class.",
+        spec.name
+    );
+    Ok(model::format_matrix(
+        &header,
+        &stats::quantize(&rows, scale),
+        3,
+    ))
+}
+
+fn frequent(args: &[String]) -> Result<String, String> {
+    let kind = args
+        .first()
+        .and_then(|name| stats::Frequent::from_name(name))
+        .unwrap_or_else(|| usage_error());
+    let corpora = read_corpora(&args[1..])?;
+    let frequent = stats::frequent(kind, corpora.iter().map(|text| &text[..]))?;
+    let mut out =
+        String::from("# The 128 most frequent level 1 characters, most frequent first.\n");
+    for row in frequent.chunks(16) {
+        let row: Vec<String> = row
+            .iter()
+            .map(|&u| std::char::from_u32(u32::from(u)).unwrap().to_string())
+            .collect();
+        out.push_str(&row.join(" "));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn read_models(dir: &Path) -> Result<Vec<model::Model>, String> {
+    model::MODELS
+        .iter()
+        .map(|spec| model::read_model(dir, spec))
+        .collect()
+}
+
+fn generate(dir: &Path) -> Result<String, String> {
+    let models = read_models(dir)?;
+    let frequent = codegen::FREQUENT
+        .iter()
+        .map(|name| model::read_frequent(dir, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    codegen::generate(&models, &frequent)
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut dir = default_model_dir();
+    match args.first().map(|arg| &arg[..]) {
+        Some("-h") | Some("--help") => {
+            print!("{}", USAGE);
+            return;
+        }
+        Some("--model-dir") if args.len() > 1 => {
+            dir = PathBuf::from(args.remove(1));
+            args.remove(0);
+        }
+        _ => {}
+    }
+    if args.is_empty() {
+        usage_error();
+    }
+    let command = args.remove(0);
+    let mut output = None;
+    let result = match &command[..] {
+        "count" => count(&dir, &args).map(String::into_bytes),
+        "quantize" => quantize(&dir, &args).map(String::into_bytes),
+        "frequent" => frequent(&args).map(String::into_bytes),
+        "generate" => {
+            output = match &args[..] {
+                [] => Some(default_output()),
+                [path] if path == "-" => None,
+                [path] => Some(PathBuf::from(path)),
+                _ => usage_error(),
+            };
+            generate(&dir).map(String::into_bytes)
+        }
+        "binary" => {
+            output = match &args[..] {
+                [path] if path == "-" => None,
+                [path] => Some(PathBuf::from(path)),
+                _ => usage_error(),
+            };
+            read_models(&dir).map(|models| codegen::binary(&models))
+        }
+        _ => usage_error(),
+    };
+    let bytes = result.unwrap_or_else(|err| {
+        eprintln!("chardetng_train: {}", err);
+        process::exit(1);
+    });
+    let written = match output {
+        Some(path) => fs::write(&path, bytes).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => std::io::stdout()
+            .write_all(&bytes)
+            .map_err(|e| e.to_string()),
+    };
+    if let Err(err) = written {
+        eprintln!("chardetng_train: {}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only checks the code generation step, since the stored scores were
+    /// extracted from `data.rs`.
+    #[test]
+    fn test_generate_reproduces_data_rs() {
+        let generated = generate(&default_model_dir()).unwrap();
+        let current = fs::read_to_string(default_output()).unwrap();
+        assert!(
+            generated == current,
+            "data.rs differs from the model directory"
+        );
+    }
+
+    #[test]
+    fn test_binary_loads() {
+        let models = read_models(&default_model_dir()).unwrap();
+        let bytes: &'static [u8] = Box::leak(codegen::binary(&models).into_boxed_slice());
+        let model = Box::leak(Box::new(
+            chardetng::SingleByteModel::from_bytes(bytes).unwrap(),
+        ));
+        let mut detector = chardetng::EncodingDetector::with_model(model);
+        detector.feed("Привет, как дела?".as_bytes(), true);
+        assert_eq!(detector.guess(None, true), encoding_rs::UTF_8);
+        let mut detector = chardetng::EncodingDetector::with_model(model);
+        let (bytes, _, _) = encoding_rs::KOI8_U.encode("Привет, как дела?");
+        detector.feed(&bytes, true);
+        assert_eq!(detector.guess(None, false), encoding_rs::KOI8_U);
+    }
+
+    #[test]
+    fn test_count_and_quantize() {
+        let spec = model::model_spec("greek").unwrap();
+        let classes = model::read_classes(&default_model_dir(), spec).unwrap();
+        let mut counts = stats::Counts::new(&classes);
+        counts.add(&classes, "Αα β");
+        let rows = counts.rows(spec.ascii.classes());
+        let alpha = classes.caseless_class('α');
+        let beta = classes.caseless_class('β');
+        assert_eq!(alpha, classes.caseless_class('Α'));
+        assert_eq!(rows[alpha][0], Some(1));
+        assert_eq!(rows[alpha][alpha], Some(1));
+        assert_eq!(rows[0][alpha], Some(1));
+        assert_eq!(rows[beta][0], Some(1));
+        assert_eq!(rows[0][beta], Some(1));
+        assert_eq!(rows[0][0], None);
+        let scores = stats::quantize(&rows, 10);
+        assert_eq!(scores[alpha][alpha], Some(2));
+        assert_eq!(scores[beta][alpha], Some(255));
+    }
+
+    #[test]
+    fn test_matrix_round_trip() {
+        let spec = model::model_spec("thai").unwrap();
+        let dir = default_model_dir();
+        let classes = model::read_classes(&dir, spec).unwrap();
+        let text = fs::read_to_string(dir.join("thai.scores")).unwrap();
+        let rows =
+            model::parse_matrix::<u8>(&text, spec.ascii.classes(), classes.stored()).unwrap();
+        let header = "Pair scores of the thai model. Row: current class, column: previous class.";
+        let formatted = model::format_matrix(header, &rows, 3);
+        assert_eq!(
+            model::parse_matrix::<u8>(&formatted, spec.ascii.classes(), classes.stored()).unwrap(),
+            rows
+        );
+    }
+}
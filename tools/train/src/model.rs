@@ -0,0 +1,534 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The models and their input files.
+//!
+//! `<model>.classes` defines the character classes of a model. Each line
+//! before the first section is `N: lower... | upper...`, where `N` counts
+//! up from 1 and the characters are either literal or `U+XXXX`. The first
+//! lower-case character labels the class in the generated comments.
+//! Class 0 is implicitly space-like. ASCII letters must come first (one
+//! class for all of them in non-Latin models).
+//!
+//! Each `[table]` section then lists the characters of that encoding's
+//! upper half that are not space-like and not in a class, under labels
+//! named after the unstored categories in `data_consts.rs.in`. A numeric
+//! label puts characters of that encoding into a class only for that
+//! encoding. Upper-case characters get the class with the high bit set.
+//! Unmappable bytes and C1 controls are unmapped, all other characters
+//! not listed are space-like.
+//!
+//! `<model>.scores` has one line per current class with one token per
+//! previous class: the score, 255 for implausible or `.` for pairs that
+//! are not stored (both ASCII).
+
+use encoding_rs::Encoding;
+use encoding_rs::IBM866_INIT;
+use encoding_rs::ISO_8859_13_INIT;
+use encoding_rs::ISO_8859_2_INIT;
+use encoding_rs::ISO_8859_4_INIT;
+use encoding_rs::ISO_8859_5_INIT;
+use encoding_rs::ISO_8859_6_INIT;
+use encoding_rs::ISO_8859_7_INIT;
+use encoding_rs::ISO_8859_8_INIT;
+use encoding_rs::KOI8_U_INIT;
+use encoding_rs::WINDOWS_1250_INIT;
+use encoding_rs::WINDOWS_1251_INIT;
+use encoding_rs::WINDOWS_1252_INIT;
+use encoding_rs::WINDOWS_1253_INIT;
+use encoding_rs::WINDOWS_1254_INIT;
+use encoding_rs::WINDOWS_1255_INIT;
+use encoding_rs::WINDOWS_1256_INIT;
+use encoding_rs::WINDOWS_1257_INIT;
+use encoding_rs::WINDOWS_1258_INIT;
+use encoding_rs::WINDOWS_874_INIT;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const ASCII_DIGIT: u8 = 100;
+
+pub const UNMAPPED: u8 = 255;
+
+/// The labels of the unstored categories, in the order of their values.
+const CATEGORIES: [&str; 6] = [
+    "plausible_next_to_alphabetic_on_either_side",
+    "implausible_next_to_alphabetic_on_either_side",
+    "implausible_before_alphabetic",
+    "implausible_after_alphabetic",
+    "plausible_next_to_non_ascii_alphabetic_on_either_side",
+    "plausible_next_to_ascii_alphabetic_on_either_side",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Ascii {
+    /// Each ASCII letter is a class of its own.
+    Latin,
+    /// All ASCII letters are one class.
+    NonLatin,
+    /// Like `Latin`, but `i` is a non-ASCII class, because its upper case
+    /// is `İ`.
+    Turkish,
+}
+
+impl Ascii {
+    /// The number of ASCII classes, including the space-like class.
+    pub fn classes(self) -> usize {
+        match self {
+            Ascii::Latin => 27,
+            Ascii::NonLatin => 2,
+            Ascii::Turkish => 26,
+        }
+    }
+
+    pub fn table(self) -> &'static str {
+        match self {
+            Ascii::Latin => "latin_ascii",
+            Ascii::NonLatin => "non_latin_ascii",
+            Ascii::Turkish => "turkish_ascii",
+        }
+    }
+}
+
+pub struct TableSpec {
+    /// The name of the class table in `DetectorData`.
+    pub table: &'static str,
+    /// The name of the encoding_rs constant.
+    pub constant: &'static str,
+    pub encoding: &'static Encoding,
+}
+
+pub struct ModelSpec {
+    pub name: &'static str,
+    /// The cargo feature of `chardetng` that compiles the model in or
+    /// `None` if the model is always compiled in.
+    pub feature: Option<&'static str>,
+    pub ascii: Ascii,
+    pub tables: &'static [TableSpec],
+}
+
+macro_rules! table {
+    ($table:literal, $constant:ident) => {
+        TableSpec {
+            table: $table,
+            constant: stringify!($constant),
+            encoding: &$constant,
+        }
+    };
+}
+
+/// The models in the order of `SINGLE_BYTE_DATA`.
+pub static MODELS: [ModelSpec; 11] = [
+    ModelSpec {
+        name: "vietnamese",
+        feature: Some("vietnamese"),
+        ascii: Ascii::Latin,
+        tables: &[table!("windows_1258", WINDOWS_1258_INIT)],
+    },
+    ModelSpec {
+        name: "central",
+        feature: Some("central"),
+        ascii: Ascii::Latin,
+        tables: &[
+            table!("windows_1250", WINDOWS_1250_INIT),
+            table!("iso_8859_2", ISO_8859_2_INIT),
+        ],
+    },
+    ModelSpec {
+        name: "cyrillic",
+        feature: Some("cyrillic"),
+        ascii: Ascii::NonLatin,
+        tables: &[
+            table!("windows_1251", WINDOWS_1251_INIT),
+            table!("koi8_u", KOI8_U_INIT),
+            table!("iso_8859_5", ISO_8859_5_INIT),
+            table!("ibm866", IBM866_INIT),
+        ],
+    },
+    ModelSpec {
+        name: "western",
+        feature: None,
+        ascii: Ascii::Latin,
+        tables: &[table!("windows_1252", WINDOWS_1252_INIT)],
+    },
+    ModelSpec {
+        name: "icelandic",
+        feature: None,
+        ascii: Ascii::Latin,
+        tables: &[table!("windows_1252_icelandic", WINDOWS_1252_INIT)],
+    },
+    ModelSpec {
+        name: "greek",
+        feature: Some("greek"),
+        ascii: Ascii::NonLatin,
+        tables: &[
+            table!("windows_1253", WINDOWS_1253_INIT),
+            table!("iso_8859_7", ISO_8859_7_INIT),
+        ],
+    },
+    ModelSpec {
+        name: "turkish",
+        feature: Some("turkish"),
+        ascii: Ascii::Turkish,
+        tables: &[table!("windows_1254", WINDOWS_1254_INIT)],
+    },
+    ModelSpec {
+        name: "hebrew",
+        feature: Some("hebrew"),
+        ascii: Ascii::NonLatin,
+        tables: &[
+            table!("windows_1255", WINDOWS_1255_INIT),
+            table!("iso_8859_8", ISO_8859_8_INIT),
+        ],
+    },
+    ModelSpec {
+        name: "arabic",
+        feature: Some("arabic"),
+        ascii: Ascii::NonLatin,
+        tables: &[
+            table!("windows_1256", WINDOWS_1256_INIT),
+            table!("iso_8859_6", ISO_8859_6_INIT),
+        ],
+    },
+    ModelSpec {
+        name: "baltic",
+        feature: Some("baltic"),
+        ascii: Ascii::Latin,
+        tables: &[
+            table!("windows_1257", WINDOWS_1257_INIT),
+            table!("iso_8859_13", ISO_8859_13_INIT),
+            table!("iso_8859_4", ISO_8859_4_INIT),
+        ],
+    },
+    ModelSpec {
+        name: "thai",
+        feature: Some("thai"),
+        ascii: Ascii::NonLatin,
+        tables: &[table!("windows_874", WINDOWS_874_INIT)],
+    },
+];
+
+pub fn model_spec(name: &str) -> Option<&'static ModelSpec> {
+    MODELS.iter().find(|spec| spec.name == name)
+}
+
+/// The parsed `<model>.classes` file.
+pub struct Classes {
+    /// One label per class, starting with the space-like class 0.
+    pub labels: Vec<char>,
+    /// Class (with the high bit set for upper case) by character.
+    letters: HashMap<char, u8>,
+    /// Per-table overrides of `letters` by character.
+    overrides: Vec<HashMap<char, u8>>,
+}
+
+impl Classes {
+    /// The number of classes whose pairs are stored.
+    pub fn stored(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns the caseless class of `c` for counting or 0 for characters
+    /// that are not in a class.
+    pub fn caseless_class(&self, c: char) -> usize {
+        self.letters
+            .get(&c)
+            .map_or(0, |&class| usize::from(class & 0x7F))
+    }
+
+    /// Computes the lower half of the class table.
+    pub fn ascii_table(&self) -> [u8; 128] {
+        let mut table = [0u8; 128];
+        for (b, slot) in table.iter_mut().enumerate() {
+            let c = char::from(b as u8);
+            *slot = if c.is_ascii_digit() {
+                ASCII_DIGIT
+            } else {
+                self.letters.get(&c).copied().unwrap_or(0)
+            };
+        }
+        table
+    }
+
+    /// Computes the upper half of the class table for the `index`th
+    /// table of the model.
+    pub fn upper_table(&self, index: usize, encoding: &'static Encoding) -> [u8; 128] {
+        let mut table = [0u8; 128];
+        for (b, slot) in table.iter_mut().enumerate() {
+            let byte = [0x80 | b as u8];
+            *slot = match encoding.decode_without_bom_handling_and_without_replacement(&byte) {
+                Some(s) => {
+                    let c = s.chars().next().unwrap();
+                    if ('\u{80}'..='\u{9F}').contains(&c) {
+                        UNMAPPED
+                    } else if let Some(&class) = self.overrides[index].get(&c) {
+                        class
+                    } else {
+                        self.letters.get(&c).copied().unwrap_or(0)
+                    }
+                }
+                None => UNMAPPED,
+            };
+        }
+        table
+    }
+}
+
+fn parse_char(token: &str) -> Result<char, String> {
+    if let Some(hex) = token.strip_prefix("U+") {
+        return u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| format!("bad code point: {}", token));
+    }
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("not a single character: {}", token)),
+    }
+}
+
+/// Parses `lower... | upper...` and inserts the characters with `class`.
+fn parse_members(
+    members: &str,
+    class: u8,
+    map: &mut HashMap<char, u8>,
+    labels: Option<&mut Vec<char>>,
+) -> Result<(), String> {
+    let mut halves = members.splitn(2, '|');
+    let lower = halves.next().unwrap_or("");
+    let upper = halves.next().unwrap_or("");
+    let mut first = None;
+    for (half, flag) in [(lower, 0u8), (upper, 0x80u8)].iter() {
+        for token in half.split_whitespace() {
+            let c = parse_char(token)?;
+            if map.insert(c, class | flag).is_some() {
+                return Err(format!("duplicate character: {}", token));
+            }
+            first = first.or(Some(c));
+        }
+    }
+    if let Some(labels) = labels {
+        labels.push(first.ok_or("empty class")?);
+    }
+    Ok(())
+}
+
+pub fn parse_classes(spec: &ModelSpec, text: &str) -> Result<Classes, String> {
+    let mut classes = Classes {
+        labels: vec![' '],
+        letters: HashMap::new(),
+        overrides: Vec::new(),
+    };
+    let mut lines = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            lines.push((number + 1, line));
+        }
+    }
+    let mut iter = lines.into_iter().peekable();
+    while let Some(&(number, line)) = iter.peek() {
+        if line.starts_with('[') {
+            break;
+        }
+        iter.next();
+        let (label, members) = split_label(line).map_err(|e| format!("line {}: {}", number, e))?;
+        if label.parse::<usize>() != Ok(classes.labels.len()) {
+            return Err(format!(
+                "line {}: expected class {}",
+                number,
+                classes.labels.len()
+            ));
+        }
+        let class = classes.labels.len() as u8;
+        parse_members(
+            members,
+            class,
+            &mut classes.letters,
+            Some(&mut classes.labels),
+        )
+        .map_err(|e| format!("line {}: {}", number, e))?;
+    }
+    let stored = classes.stored();
+    for table in spec.tables.iter() {
+        let mut overrides = HashMap::new();
+        match iter.next() {
+            Some((_, line)) if line == format!("[{}]", table.table) => {}
+            Some((number, _)) => {
+                return Err(format!("line {}: expected [{}]", number, table.table));
+            }
+            None => return Err(format!("missing [{}]", table.table)),
+        }
+        while let Some(&(number, line)) = iter.peek() {
+            if line.starts_with('[') {
+                break;
+            }
+            iter.next();
+            let (label, members) =
+                split_label(line).map_err(|e| format!("line {}: {}", number, e))?;
+            let class = if let Some(i) = CATEGORIES.iter().position(|&c| c == label) {
+                stored + i
+            } else {
+                match label.parse::<usize>() {
+                    Ok(class) if class > 0 && class < stored => class,
+                    _ => return Err(format!("line {}: unknown label: {}", number, label)),
+                }
+            };
+            parse_members(members, class as u8, &mut overrides, None)
+                .map_err(|e| format!("line {}: {}", number, e))?;
+        }
+        classes.overrides.push(overrides);
+    }
+    if let Some((number, _)) = iter.next() {
+        return Err(format!("line {}: unexpected section", number));
+    }
+    // The unstored categories follow the stored classes and must stay
+    // below `ASCII_DIGIT`.
+    if stored <= spec.ascii.classes() || stored + CATEGORIES.len() > usize::from(ASCII_DIGIT) {
+        return Err(format!("bad number of classes: {}", stored));
+    }
+    Ok(classes)
+}
+
+fn split_label(line: &str) -> Result<(&str, &str), String> {
+    let colon = line.find(':').ok_or("missing colon")?;
+    Ok((line[..colon].trim(), &line[colon + 1..]))
+}
+
+/// Returns `false` for the pairs that `compute_index` in `data.rs` doesn't
+/// store.
+pub fn is_stored(previous: usize, current: usize, ascii: usize) -> bool {
+    !((previous == 0 && current == 0) || (previous < ascii && current < ascii))
+}
+
+/// Parses a `.scores` or `.counts` file into rows of current classes with
+/// a column per previous class. `None` marks the pairs that are not stored.
+pub fn parse_matrix<T: FromStr>(
+    text: &str,
+    ascii: usize,
+    stored: usize,
+) -> Result<Vec<Vec<Option<T>>>, String> {
+    let mut rows = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let current = rows.len();
+        let mut row = Vec::new();
+        for token in line.split_whitespace() {
+            let previous = row.len();
+            let value = if token == "." {
+                None
+            } else {
+                let value = token
+                    .parse::<T>()
+                    .map_err(|_| format!("line {}: bad value: {}", number + 1, token))?;
+                Some(value)
+            };
+            if value.is_some() != is_stored(previous, current, ascii) {
+                return Err(format!(
+                    "line {}: column {} must be {}",
+                    number + 1,
+                    previous,
+                    if value.is_some() { "`.`" } else { "a number" }
+                ));
+            }
+            row.push(value);
+        }
+        if row.len() != stored {
+            return Err(format!("line {}: expected {} columns", number + 1, stored));
+        }
+        rows.push(row);
+    }
+    if rows.len() != stored {
+        return Err(format!("expected {} rows", stored));
+    }
+    Ok(rows)
+}
+
+/// Formats rows as parsed by `parse_matrix`.
+pub fn format_matrix<T: Display>(header: &str, rows: &[Vec<Option<T>>], width: usize) -> String {
+    let mut out = String::new();
+    for line in header.lines() {
+        let _ = writeln!(out, "# {}", line);
+    }
+    for row in rows.iter() {
+        let mut first = true;
+        for value in row.iter() {
+            if !first {
+                out.push(' ');
+            }
+            first = false;
+            match value {
+                Some(value) => {
+                    let _ = write!(out, "{:>1$}", value, width);
+                }
+                None => {
+                    let _ = write!(out, "{:>1$}", ".", width);
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// A model with its inputs read from the model directory.
+pub struct Model {
+    pub spec: &'static ModelSpec,
+    pub classes: Classes,
+    pub scores: Vec<u8>,
+}
+
+pub fn read_classes(dir: &Path, spec: &ModelSpec) -> Result<Classes, String> {
+    let path = dir.join(format!("{}.classes", spec.name));
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_classes(spec, &text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn read_model(dir: &Path, spec: &'static ModelSpec) -> Result<Model, String> {
+    let classes = read_classes(dir, spec)?;
+    let path = dir.join(format!("{}.scores", spec.name));
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let rows = parse_matrix::<u8>(&text, spec.ascii.classes(), classes.stored())
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    // Dropping the pairs that are not stored yields the layout of
+    // `compute_index`.
+    let scores = rows.into_iter().flatten().flatten().collect();
+    Ok(Model {
+        spec,
+        classes,
+        scores,
+    })
+}
+
+/// Reads `frequent_<name>.txt`.
+pub fn read_frequent(dir: &Path, name: &str) -> Result<Vec<u16>, String> {
+    let path = dir.join(format!("frequent_{}.txt", name));
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut frequent = Vec::new();
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        for token in line.split_whitespace() {
+            let c = parse_char(token).map_err(|e| format!("{}: {}", path.display(), e))?;
+            if c > '\u{FFFF}' {
+                return Err(format!("{}: not in the BMP: {}", path.display(), token));
+            }
+            frequent.push(c as u16);
+        }
+    }
+    if frequent.len() != 128 {
+        return Err(format!("{}: expected 128 characters", path.display()));
+    }
+    Ok(frequent)
+}
@@ -0,0 +1,170 @@
+// Copyright Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Statistics gathering from UTF-8 corpora for training new models. This
+//! is not a port of traindet, so it doesn't reproduce the stored scores.
+
+use crate::model::is_stored;
+use crate::model::Classes;
+use encoding_rs::Encoding;
+use encoding_rs::EUC_KR;
+use encoding_rs::GBK;
+use encoding_rs::SHIFT_JIS;
+use std::collections::HashMap;
+
+/// Pair counts of a model: rows of current classes with a column per
+/// previous class.
+pub struct Counts {
+    stored: usize,
+    counts: Vec<u64>,
+}
+
+impl Counts {
+    pub fn new(classes: &Classes) -> Counts {
+        let stored = classes.stored();
+        Counts {
+            stored,
+            counts: vec![0; stored * stored],
+        }
+    }
+
+    /// Counts the class pairs of `text`. Characters that are not in a class
+    /// count as space-like, and so do the start and the end of `text`.
+    pub fn add(&mut self, classes: &Classes, text: &str) {
+        let mut previous = 0;
+        for c in text.chars() {
+            let current = classes.caseless_class(c);
+            self.counts[current * self.stored + previous] += 1;
+            previous = current;
+        }
+        self.counts[previous] += 1;
+    }
+
+    pub fn rows(&self, ascii: usize) -> Vec<Vec<Option<u64>>> {
+        self.counts
+            .chunks(self.stored)
+            .enumerate()
+            .map(|(current, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(previous, &count)| {
+                        if is_stored(previous, current, ascii) {
+                            Some(count)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Turns pair counts into scores: the number of occurrences per `scale`
+/// stored pairs, capped at 254. Pairs that never occur get 255, which the
+/// detector treats as implausible.
+pub fn quantize(rows: &[Vec<Option<u64>>], scale: u64) -> Vec<Vec<Option<u8>>> {
+    let total: u64 = rows.iter().flatten().flatten().sum();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|count| {
+                    count.map(|count| {
+                        if count == 0 {
+                            255
+                        } else {
+                            let scaled =
+                                u128::from(count) * u128::from(scale) / u128::from(total.max(1));
+                            scaled.min(254) as u8
+                        }
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The frequent CJK character lists and the level 1 range that the
+/// detector consults each list for.
+#[derive(Clone, Copy)]
+pub enum Frequent {
+    /// GB2312 level 1 Hanzi as encoded in GBK.
+    Simplified,
+    /// JIS X 0208 level 1 Kanji as encoded in Shift_JIS.
+    Kanji,
+    /// KS X 1001 Hangul as encoded in EUC-KR.
+    Hangul,
+}
+
+impl Frequent {
+    pub fn from_name(name: &str) -> Option<Frequent> {
+        match name {
+            "simplified" => Some(Frequent::Simplified),
+            "kanji" => Some(Frequent::Kanji),
+            "hangul" => Some(Frequent::Hangul),
+            _ => None,
+        }
+    }
+
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            Frequent::Simplified => GBK,
+            Frequent::Kanji => SHIFT_JIS,
+            Frequent::Hangul => EUC_KR,
+        }
+    }
+
+    fn is_level_1(self, c: char) -> bool {
+        let is_script = match self {
+            Frequent::Simplified | Frequent::Kanji => ('\u{4E00}'..='\u{9FFF}').contains(&c),
+            Frequent::Hangul => ('\u{AC00}'..='\u{D7A3}').contains(&c),
+        };
+        if !is_script {
+            return false;
+        }
+        let mut buffer = [0u8; 4];
+        let (bytes, _, unmappable) = self.encoding().encode(c.encode_utf8(&mut buffer));
+        if unmappable || bytes.len() != 2 {
+            return false;
+        }
+        let (lead, trail) = (bytes[0], bytes[1]);
+        match self {
+            Frequent::Simplified => (0xB0..=0xD7).contains(&lead),
+            Frequent::Kanji => lead < 0x98 || (lead == 0x98 && trail < 0x73),
+            Frequent::Hangul => (0xB0..=0xC8).contains(&lead),
+        }
+    }
+}
+
+/// Counts the level 1 characters of `texts` and returns the 128 most
+/// frequent ones, most frequent first. Ties are broken by code point so
+/// that the output only depends on the input.
+pub fn frequent<'a, I: Iterator<Item = &'a str>>(
+    kind: Frequent,
+    texts: I,
+) -> Result<Vec<u16>, String> {
+    let mut counts: HashMap<char, u64> = HashMap::new();
+    for text in texts {
+        for c in text.chars() {
+            *counts.entry(c).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(char, u64)> = counts
+        .into_iter()
+        .filter(|&(c, _)| kind.is_level_1(c))
+        .collect();
+    if ranked.len() < 128 {
+        return Err(format!(
+            "only {} distinct level 1 characters in the corpus",
+            ranked.len()
+        ));
+    }
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    Ok(ranked[..128].iter().map(|&(c, _)| c as u16).collect())
+}